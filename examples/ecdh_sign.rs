@@ -0,0 +1,54 @@
+// Alice and Bob agree on a shared key over secp256k1, then Alice signs a message
+// and Bob checks it against her public key.
+//
+// cargo run --example ecdh_sign
+use ecc_rust::ecdh::ecdh_shared_secret;
+use ecc_rust::ecdsa::ECDSA;
+use ecc_rust::EllipticCurve;
+use sha256::digest;
+
+fn main() {
+    let ecdsa = ECDSA::new(
+        EllipticCurve::secp256k1(),
+        EllipticCurve::secp256k1_generator(),
+        EllipticCurve::secp256k1_order(),
+    )
+    .expect("secp256k1 parameters are consistent");
+
+    // key pairs, d in [1, q) and P = dA
+    let (alice_priv, alice_pub) = ecdsa.generate_key_pair();
+    let (bob_priv, bob_pub) = ecdsa.generate_key_pair();
+
+    // ECDH, d_a P_b = d_a d_b A = d_b P_a
+    let ec = ecdsa.ec();
+    let order = ecdsa.q_order();
+    let shared_x = ecdh_shared_secret(ec, alice_priv.scalar(), bob_pub.point(), order)
+        .expect("Alice could not derive");
+    let bob_x = ecdh_shared_secret(ec, bob_priv.scalar(), alice_pub.point(), order)
+        .expect("Bob could not derive");
+    assert_eq!(shared_x, bob_x, "ECDH secrets differ");
+    let shared_key = digest(shared_x.to_bytes_be().as_slice());
+    println!("shared key: {}", shared_key);
+
+    // Alice signs, Bob verifies with her public key
+    let message = b"Hello Bob!";
+    let signature = ecdsa
+        .sign_bytes(&alice_priv, message)
+        .expect("Alice could not sign");
+    println!(
+        "signature over {:?}: (r: {:x}, s: {:x})",
+        String::from_utf8_lossy(message),
+        signature.0,
+        signature.1
+    );
+
+    assert!(
+        ecdsa.verify_bytes(message, &signature, &alice_pub),
+        "signature rejected"
+    );
+    assert!(
+        !ecdsa.verify_bytes(message, &signature, &bob_pub),
+        "signature accepted under the wrong key"
+    );
+    println!("signature verified");
+}
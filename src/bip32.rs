@@ -0,0 +1,272 @@
+use crate::ecdsa::int2octets;
+use crate::{EccError, EllipticCurve, Point};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use hmac::{Hmac, Mac};
+use num_bigint::BigUint;
+use sha2::Sha512;
+
+// BIP32 hierarchical deterministic keys over secp256k1
+// the version bytes, depth, parent fingerprint and child number of the serialized
+// xprv/xpub are left to the caller, only the key and its chain code are derived
+#[derive(PartialEq, Debug, Clone)]
+pub struct ExtendedPrivKey {
+    pub key: BigUint,
+    pub chain_code: [u8; 32],
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct ExtendedPubKey {
+    pub key: Point,
+    pub chain_code: [u8; 32],
+}
+
+// indices from 2^31 up derive hardened children, which need the private key
+pub const HARDENED: u32 = 1 << 31;
+
+impl ExtendedPrivKey {
+    // I = HMAC-SHA512(key = "Bitcoin seed", data = seed)
+    // key = I[0..32], chain_code = I[32..64]
+    // fails with InvalidEncoding unless the seed is 16 to 64 bytes long, and with
+    // InvalidScalar when I[0..32] is 0 or not below n (odds about 2^-127), in which
+    // case BIP32 asks for another seed
+    pub fn from_seed(seed: &[u8]) -> Result<ExtendedPrivKey, EccError> {
+        if !(16..=64).contains(&seed.len()) {
+            return Err(EccError::InvalidEncoding(
+                "BIP32 seeds are 16 to 64 bytes".to_string(),
+            ));
+        }
+        let (il, chain_code) = hmac_sha512(b"Bitcoin seed", &[seed]);
+        let key = BigUint::from_bytes_be(&il);
+        let n = EllipticCurve::secp256k1_order();
+        if key == BigUint::from(0u32) || key >= n {
+            return Err(EccError::InvalidScalar);
+        }
+        Ok(ExtendedPrivKey { key, chain_code })
+    }
+    // CKDpriv
+    // hardened: I = HMAC-SHA512(chain_code, 0x00 || ser256(k) || ser32(index))
+    // normal:   I = HMAC-SHA512(chain_code, serP(kG) || ser32(index))
+    // child key = I[0..32] + k mod n, child chain_code = I[32..64]
+    // fails with InvalidScalar when I[0..32] is not below n or the child key is 0,
+    // BIP32 then moves on to the next index
+    pub fn child_private_key(&self, index: u32) -> Result<ExtendedPrivKey, EccError> {
+        let ec = EllipticCurve::secp256k1();
+        let n = EllipticCurve::secp256k1_order();
+        let data = if index >= HARDENED {
+            let mut data = Vec::with_capacity(33);
+            data.push(0x00);
+            data.extend_from_slice(&int2octets(&self.key, 32));
+            data
+        } else {
+            let pub_key = ec.scalar_mul_ct(&EllipticCurve::secp256k1_generator(), &self.key)?;
+            ser_p(&pub_key)?
+        };
+        let (il, chain_code) = hmac_sha512(&self.chain_code, &[&data, &index.to_be_bytes()]);
+        let il = BigUint::from_bytes_be(&il);
+        if il >= n {
+            return Err(EccError::InvalidScalar);
+        }
+        let key = (il + &self.key) % &n;
+        if key == BigUint::from(0u32) {
+            return Err(EccError::InvalidScalar);
+        }
+        Ok(ExtendedPrivKey { key, chain_code })
+    }
+    // N((k, c)) = (kG, c)
+    // fails with PointNotOnCurve when g is not a point of ec and with InvalidOrder when
+    // order is 0
+    pub fn to_public(
+        &self,
+        ec: &EllipticCurve,
+        g: &Point,
+        order: &BigUint,
+    ) -> Result<ExtendedPubKey, EccError> {
+        if order == &BigUint::from(0u32) {
+            return Err(EccError::InvalidOrder);
+        }
+        Ok(ExtendedPubKey {
+            key: ec.scalar_mul_ct(g, &(&self.key % order))?,
+            chain_code: self.chain_code,
+        })
+    }
+}
+
+impl ExtendedPubKey {
+    // serP, the 33-byte compressed point: 0x02 or 0x03 for the parity of y, then x
+    // fails with InvalidPoint for the identity
+    pub fn to_bytes(&self) -> Result<[u8; 33], EccError> {
+        let bytes = ser_p(&self.key)?;
+        Ok(bytes.try_into().expect("serP is 33 bytes"))
+    }
+}
+
+fn ser_p(c: &Point) -> Result<Vec<u8>, EccError> {
+    match c {
+        Point::Coordinate(x, y) => {
+            let mut bytes = Vec::with_capacity(33);
+            bytes.push(if y.bit(0) { 0x03 } else { 0x02 });
+            bytes.extend_from_slice(&int2octets(x, 32));
+            Ok(bytes)
+        }
+        Point::Identity => Err(EccError::InvalidPoint),
+    }
+}
+
+// (I[0..32], I[32..64])
+fn hmac_sha512(key: &[u8], parts: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC takes keys of any size");
+    for part in parts {
+        mac.update(part);
+    }
+    let i = mac.finalize().into_bytes();
+    let mut il = [0u8; 32];
+    let mut ir = [0u8; 32];
+    il.copy_from_slice(&i[..32]);
+    ir.copy_from_slice(&i[32..]);
+    (il, ir)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // (index, chain code, private key, compressed public key)
+    type Step = (u32, &'static str, &'static str, &'static str);
+
+    fn check_chain(seed: &str, master: (&str, &str, &str), steps: &[Step]) {
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+        let check = |key: &ExtendedPrivKey, chain_code: &str, priv_key: &str, pub_key: &str| {
+            assert_eq!(hex::encode(key.chain_code), chain_code);
+            assert_eq!(hex::encode(int2octets(&key.key, 32)), priv_key);
+            let public = key.to_public(&ec, &g, &n).expect("G is on the curve");
+            assert_eq!(public.chain_code, key.chain_code);
+            let bytes = public.to_bytes().expect("kG is not the identity");
+            assert_eq!(hex::encode(bytes), pub_key);
+        };
+
+        let seed = hex::decode(seed).expect("Cannot decode hex");
+        let mut key = ExtendedPrivKey::from_seed(&seed).expect("Could not derive master key");
+        check(&key, master.0, master.1, master.2);
+        for (index, chain_code, priv_key, pub_key) in steps {
+            key = key
+                .child_private_key(*index)
+                .expect("Could not derive child key");
+            check(&key, chain_code, priv_key, pub_key);
+        }
+    }
+
+    #[test]
+    fn test_vector_1() {
+        check_chain(
+            "000102030405060708090a0b0c0d0e0f",
+            (
+                "873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508",
+                "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+                "0339a36013301597daef41fbe593a02cc513d0b55527ec2df1050e2e8ff49c85c2",
+            ),
+            &[
+                // m/0H
+                (
+                    HARDENED,
+                    "47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141",
+                    "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+                    "035a784662a4a20a65bf6aab9ae98a6c068a81c52e4b032c0fb5400c706cfccc56",
+                ),
+                // m/0H/1
+                (
+                    1,
+                    "2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19",
+                    "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+                    "03501e454bf00751f24b1b489aa925215d66af2234e3891c3b21a52bedb3cd711c",
+                ),
+                // m/0H/1/2H
+                (
+                    HARDENED + 2,
+                    "04466b9cc8e161e966409ca52986c584f07e9dc81f735db683c3ff6ec7b1503f",
+                    "cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca",
+                    "0357bfe1e341d01c69fe5654309956cbea516822fba8a601743a012a7896ee8dc2",
+                ),
+                // m/0H/1/2H/2
+                (
+                    2,
+                    "cfb71883f01676f587d023cc53a35bc7f88f724b1f8c2892ac1275ac822a3edd",
+                    "0f479245fb19a38a1954c5c7c0ebab2f9bdfd96a17563ef28a6a4b1a2a764ef4",
+                    "02e8445082a72f29b75ca48748a914df60622a609cacfce8ed0e35804560741d29",
+                ),
+                // m/0H/1/2H/2/1000000000
+                (
+                    1_000_000_000,
+                    "c783e67b921d2beb8f6b389cc646d7263b4145701dadd2161548a8b078e65e9e",
+                    "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8",
+                    "022a471424da5e657499d1ff51cb43c47481a03b1e77f951fe64cec9f5a48f7011",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_vector_2() {
+        check_chain(
+            "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542",
+            (
+                "60499f801b896d83179a4374aeb7822aaeaceaa0db1f85ee3e904c4defbd9689",
+                "4b03d6fc340455b363f51020ad3ecca4f0850280cf436c70c727923f6db46c3e",
+                "03cbcaa9c98c877a26977d00825c956a238e8dddfbd322cce4f74b0b5bd6ace4a7",
+            ),
+            &[
+                // m/0
+                (
+                    0,
+                    "f0909affaa7ee7abe5dd4e100598d4dc53cd709d5a5c2cac40e7412f232f7c9c",
+                    "abe74a98f6c7eabee0428f53798f0ab8aa1bd37873999041703c742f15ac7e1e",
+                    "02fc9e5af0ac8d9b3cecfe2a888e2117ba3d089d8585886c9c826b6b22a98d12ea",
+                ),
+                // m/0/2147483647H
+                (
+                    u32::MAX,
+                    "be17a268474a6bb9c61e1d720cf6215e2a88c5406c4aee7b38547f585c9a37d9",
+                    "877c779ad9687164e9c2f4f0f4ff0340814392330693ce95a58fe18fd52e6e93",
+                    "03c01e7425647bdefa82b12d9bad5e3e6865bee0502694b94ca58b666abc0a5c3b",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_vector_3() {
+        // the master key has leading zeros, which must be kept in the hardened data
+        check_chain(
+            "4b381541583be4423346c643850da4b320e46a87ae3d2a4e6da11eba819cd4acba45d239319ac14f863b8d5ab5a0d0c64d2e8a1e7d1457df2e5a3c51c73235be",
+            (
+                "01d28a3e53cffa419ec122c968b3259e16b65076495494d97cae10bbfec3c36f",
+                "00ddb80b067e0d4993197fe10f2657a844a384589847602d56f0c629c81aae32",
+                "03683af1ba5743bdfc798cf814efeeab2735ec52d95eced528e692b8e34c4e5669",
+            ),
+            &[
+                // m/0H
+                (
+                    HARDENED,
+                    "e5fea12a97b927fc9dc3d2cb0d1ea1cf50aa5a1fdc1f933e8906bb38df3377bd",
+                    "491f7a2eebc7b57028e0d3faa0acda02e75c33b03c48fb288c41e2ea44e1daef",
+                    "026557fdda1d5d43d79611f784780471f086d58e8126b8c40acb82272a7712e7f2",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_from_seed_length() {
+        assert!(matches!(
+            ExtendedPrivKey::from_seed(&[0u8; 15]),
+            Err(EccError::InvalidEncoding(_))
+        ));
+        assert!(matches!(
+            ExtendedPrivKey::from_seed(&[0u8; 65]),
+            Err(EccError::InvalidEncoding(_))
+        ));
+        assert!(ExtendedPrivKey::from_seed(&[0u8; 64]).is_ok());
+    }
+}
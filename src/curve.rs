@@ -0,0 +1,102 @@
+use crate::edwards::TwistedEdwardsCurve;
+use crate::{EccError, EllipticCurve, Point};
+use num_bigint::BigUint;
+
+// the group law of a curve, whatever its form, for protocols written once over any
+// of them (see ecdsa::sign_generic)
+// MontgomeryCurve has no implementation: it only offers the x-only ladder, with no
+// addition of two arbitrary points
+pub trait Curve {
+    type Point: Clone + PartialEq + core::fmt::Debug;
+
+    // the neutral element: Point::Identity in short Weierstrass form, (0, 1) on a
+    // twisted Edwards curve
+    fn identity(&self) -> Self::Point;
+    fn is_on_curve(&self, c: &Self::Point) -> bool;
+    // add, double and scalar_mul fail with PointNotOnCurve unless their operands are
+    // points of the curve. EllipticCurve reduces coordinates above p first,
+    // TwistedEdwardsCurve only takes reduced ones
+    fn add(&self, c: &Self::Point, d: &Self::Point) -> Result<Self::Point, EccError>;
+    fn double(&self, c: &Self::Point) -> Result<Self::Point, EccError>;
+    fn scalar_mul(&self, c: &Self::Point, d: &BigUint) -> Result<Self::Point, EccError>;
+    // scalar_mul for a secret d, private keys and nonces, over a number of steps that
+    // does not depend on d
+    fn scalar_mul_ct(&self, c: &Self::Point, d: &BigUint) -> Result<Self::Point, EccError>;
+}
+
+impl Curve for EllipticCurve {
+    type Point = Point;
+
+    fn identity(&self) -> Point {
+        Point::Identity
+    }
+    fn is_on_curve(&self, c: &Point) -> bool {
+        EllipticCurve::is_on_curve(self, c)
+    }
+    fn add(&self, c: &Point, d: &Point) -> Result<Point, EccError> {
+        EllipticCurve::add(self, c, d)
+    }
+    fn double(&self, c: &Point) -> Result<Point, EccError> {
+        self.doubling(c)
+    }
+    fn scalar_mul(&self, c: &Point, d: &BigUint) -> Result<Point, EccError> {
+        EllipticCurve::scalar_mul(self, c, d)
+    }
+    fn scalar_mul_ct(&self, c: &Point, d: &BigUint) -> Result<Point, EccError> {
+        EllipticCurve::scalar_mul_ct(self, c, d)
+    }
+}
+
+impl Curve for TwistedEdwardsCurve {
+    type Point = Point;
+
+    fn identity(&self) -> Point {
+        TwistedEdwardsCurve::neutral()
+    }
+    fn is_on_curve(&self, c: &Point) -> bool {
+        TwistedEdwardsCurve::is_on_curve(self, c)
+    }
+    fn add(&self, c: &Point, d: &Point) -> Result<Point, EccError> {
+        TwistedEdwardsCurve::add(self, c, d)
+    }
+    fn double(&self, c: &Point) -> Result<Point, EccError> {
+        self.doubling(c)
+    }
+    fn scalar_mul(&self, c: &Point, d: &BigUint) -> Result<Point, EccError> {
+        TwistedEdwardsCurve::scalar_mul(self, c, d)
+    }
+    fn scalar_mul_ct(&self, c: &Point, d: &BigUint) -> Result<Point, EccError> {
+        TwistedEdwardsCurve::scalar_mul_ct(self, c, d)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 2G + 3G = 5G = 5 (G + I), through the trait only
+    fn check_group_law<C: Curve>(curve: &C, g: &C::Point) {
+        let identity = curve.identity();
+        assert!(curve.is_on_curve(g));
+        assert!(curve.is_on_curve(&identity));
+        assert_eq!(curve.add(g, &identity).as_ref(), Ok(g));
+        let g2 = curve.double(g).expect("G is on the curve");
+        assert_eq!(curve.add(g, g), Ok(g2.clone()));
+        let g3 = curve.add(&g2, g).expect("2G is on the curve");
+        let g5 = curve.add(&g2, &g3).expect("3G is on the curve");
+        assert_eq!(curve.scalar_mul(g, &BigUint::from(5u32)), Ok(g5));
+        assert_eq!(curve.scalar_mul(g, &BigUint::from(0u32)), Ok(identity));
+    }
+
+    #[test]
+    fn test_group_law() {
+        check_group_law(
+            &EllipticCurve::secp256k1(),
+            &EllipticCurve::secp256k1_generator(),
+        );
+        check_group_law(
+            &TwistedEdwardsCurve::ed25519(),
+            &TwistedEdwardsCurve::ed25519_generator(),
+        );
+    }
+}
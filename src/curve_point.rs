@@ -0,0 +1,292 @@
+use crate::{EccError, EllipticCurve, Point};
+use core::ops::{Add, Mul, Neg};
+use num_bigint::BigUint;
+
+// a point bound to its curve, so that the group law can be written with operators
+//
+// let p3 = p1 + p2;
+// let p4 = &p1 * &scalar;
+// let p5 = &p1 * Scalar::from(5u64);
+//
+// new only accepts points of the curve and the group law never leaves it, so the
+// operators cannot fail on points built with it.
+// Panics: + panics when the two points are on different curves (checked_add returns
+// PointNotOnCurve instead), and every operator panics when the fields were set to an
+// invalid point by hand
+#[derive(PartialEq, Debug, Clone)]
+pub struct CurvePoint<'a> {
+    pub ec: &'a EllipticCurve,
+    pub point: Point,
+}
+
+impl<'a> CurvePoint<'a> {
+    // point is kept with its coordinates reduced mod p
+    // fails with PointNotOnCurve unless point is a point of ec
+    pub fn new(ec: &'a EllipticCurve, point: Point) -> Result<Self, EccError> {
+        // point + I checks and reduces point and costs nothing
+        let point = ec.add(&point, &Point::Identity)?;
+        Ok(CurvePoint { ec, point })
+    }
+    // self + rhs without the panics of +
+    // fails with PointNotOnCurve when rhs is on another curve or either point is not a
+    // point of its curve
+    pub fn checked_add(&self, rhs: &CurvePoint<'a>) -> Result<CurvePoint<'a>, EccError> {
+        if self.ec != rhs.ec {
+            return Err(EccError::PointNotOnCurve);
+        }
+        let point = self.ec.add(&self.point, &rhs.point)?;
+        Ok(CurvePoint { ec: self.ec, point })
+    }
+}
+
+// the scalar of a CurvePoint product, so that small constants and BigUint values
+// multiply the same way: &g * Scalar::from(5u64)
+#[derive(PartialEq, Debug, Clone)]
+pub struct Scalar(pub BigUint);
+
+impl From<u64> for Scalar {
+    fn from(k: u64) -> Self {
+        Scalar(BigUint::from(k))
+    }
+}
+
+impl From<BigUint> for Scalar {
+    fn from(k: BigUint) -> Self {
+        Scalar(k)
+    }
+}
+
+impl<'a> Add<&CurvePoint<'a>> for &CurvePoint<'a> {
+    type Output = CurvePoint<'a>;
+
+    fn add(self, rhs: &CurvePoint<'a>) -> CurvePoint<'a> {
+        assert_eq!(self.ec, rhs.ec, "Points are on different curves");
+        let point = self
+            .ec
+            .add(&self.point, &rhs.point)
+            .expect("Point is not on curve");
+        CurvePoint { ec: self.ec, point }
+    }
+}
+
+impl<'a> Add<CurvePoint<'a>> for CurvePoint<'a> {
+    type Output = CurvePoint<'a>;
+
+    fn add(self, rhs: CurvePoint<'a>) -> CurvePoint<'a> {
+        &self + &rhs
+    }
+}
+
+impl<'a> Mul<&BigUint> for &CurvePoint<'a> {
+    type Output = CurvePoint<'a>;
+
+    fn mul(self, rhs: &BigUint) -> CurvePoint<'a> {
+        let point = self
+            .ec
+            .scalar_mul(&self.point, rhs)
+            .expect("Point is not on curve");
+        CurvePoint { ec: self.ec, point }
+    }
+}
+
+impl<'a> Mul<&BigUint> for CurvePoint<'a> {
+    type Output = CurvePoint<'a>;
+
+    fn mul(self, rhs: &BigUint) -> CurvePoint<'a> {
+        &self * rhs
+    }
+}
+
+impl<'a> Mul<&Scalar> for &CurvePoint<'a> {
+    type Output = CurvePoint<'a>;
+
+    fn mul(self, rhs: &Scalar) -> CurvePoint<'a> {
+        self * &rhs.0
+    }
+}
+
+impl<'a> Mul<Scalar> for &CurvePoint<'a> {
+    type Output = CurvePoint<'a>;
+
+    fn mul(self, rhs: Scalar) -> CurvePoint<'a> {
+        self * &rhs.0
+    }
+}
+
+impl<'a> Mul<Scalar> for CurvePoint<'a> {
+    type Output = CurvePoint<'a>;
+
+    fn mul(self, rhs: Scalar) -> CurvePoint<'a> {
+        &self * &rhs.0
+    }
+}
+
+// small scalars, 3 * G or -1 * G without going through a BigUint
+impl<'a> Mul<u64> for &CurvePoint<'a> {
+    type Output = CurvePoint<'a>;
+
+    fn mul(self, rhs: u64) -> CurvePoint<'a> {
+        let point = self
+            .ec
+            .scalar_mul_u64(&self.point, rhs)
+            .expect("Point is not on curve");
+        CurvePoint { ec: self.ec, point }
+    }
+}
+
+impl<'a> Mul<i64> for &CurvePoint<'a> {
+    type Output = CurvePoint<'a>;
+
+    fn mul(self, rhs: i64) -> CurvePoint<'a> {
+        let point = self
+            .ec
+            .scalar_mul_i64(&self.point, rhs)
+            .expect("Point is not on curve");
+        CurvePoint { ec: self.ec, point }
+    }
+}
+
+impl<'a> Mul<&CurvePoint<'a>> for u64 {
+    type Output = CurvePoint<'a>;
+
+    fn mul(self, rhs: &CurvePoint<'a>) -> CurvePoint<'a> {
+        rhs * self
+    }
+}
+
+impl<'a> Mul<&CurvePoint<'a>> for i64 {
+    type Output = CurvePoint<'a>;
+
+    fn mul(self, rhs: &CurvePoint<'a>) -> CurvePoint<'a> {
+        rhs * self
+    }
+}
+
+impl<'a> Neg for &CurvePoint<'a> {
+    type Output = CurvePoint<'a>;
+
+    fn neg(self) -> CurvePoint<'a> {
+        CurvePoint {
+            ec: self.ec,
+            point: self.ec.negate(&self.point),
+        }
+    }
+}
+
+impl<'a> Neg for CurvePoint<'a> {
+    type Output = CurvePoint<'a>;
+
+    fn neg(self) -> CurvePoint<'a> {
+        -&self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn toy_curve() -> EllipticCurve {
+        // y^2 = x^3 + 2x + 2 mod 17
+        EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve")
+    }
+
+    fn point(ec: &EllipticCurve, x: u32, y: u32) -> CurvePoint<'_> {
+        CurvePoint::new(ec, Point::Coordinate(BigUint::from(x), BigUint::from(y)))
+            .expect("Point is not on curve")
+    }
+
+    fn identity(ec: &EllipticCurve) -> CurvePoint<'_> {
+        CurvePoint::new(ec, Point::Identity).expect("I is on every curve")
+    }
+
+    #[test]
+    fn test_add() {
+        let ec = toy_curve();
+        // (6,3) + (5,1) = (10,6)
+        let p1 = point(&ec, 6, 3);
+        let p2 = point(&ec, 5, 1);
+        assert_eq!(&p1 + &p2, point(&ec, 10, 6));
+        let p3 = p1 + p2;
+        assert_eq!(p3, point(&ec, 10, 6));
+    }
+
+    #[test]
+    fn test_mul() {
+        let ec = toy_curve();
+        // 16 * (5,1) = (10,11)
+        let p1 = point(&ec, 5, 1);
+        let scalar = BigUint::from(16u32);
+        let p4 = &p1 * &scalar;
+        assert_eq!(p4, point(&ec, 10, 11));
+        assert_eq!(p1 * &BigUint::from(19u32), identity(&ec));
+    }
+
+    #[test]
+    fn test_mul_small() {
+        let ec = toy_curve();
+        let g = point(&ec, 5, 1);
+        let three = &(&g + &g) + &g;
+        assert_eq!(&g * 3u64, three);
+        assert_eq!(3u64 * &g, three);
+        assert_eq!(&g * 3i64, three);
+        assert_eq!(-1i64 * &g, -&g);
+        assert_eq!(&g * -3i64, -&three);
+        assert_eq!(&g * 19u64, identity(&ec));
+    }
+
+    #[test]
+    fn test_mul_scalar() {
+        let ec = toy_curve();
+        let g = point(&ec, 5, 1);
+        let three = &(&g + &g) + &g;
+        assert_eq!(&g * Scalar::from(3u64), three);
+        assert_eq!(&g * &Scalar::from(BigUint::from(3u32)), three);
+        assert_eq!(g.clone() * Scalar::from(19u64), identity(&ec));
+        assert_eq!(&g * Scalar::from(16u64), &g * &BigUint::from(16u32));
+    }
+
+    #[test]
+    fn test_neg() {
+        let ec = toy_curve();
+        let p1 = point(&ec, 5, 1);
+        assert_eq!(-&p1, point(&ec, 5, 16));
+        assert_eq!(&p1 + &(-&p1), identity(&ec));
+        assert_eq!(-(-p1.clone()), p1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Points are on different curves")]
+    fn test_add_different_curves() {
+        let ec = toy_curve();
+        let other = EllipticCurve::secp256k1();
+        let _ = point(&ec, 5, 1) + identity(&other);
+    }
+
+    #[test]
+    fn test_checked_add() {
+        let ec = toy_curve();
+        let other = EllipticCurve::secp256k1();
+        let p1 = point(&ec, 6, 3);
+        let p2 = point(&ec, 5, 1);
+        assert_eq!(p1.checked_add(&p2), Ok(point(&ec, 10, 6)));
+        assert_eq!(
+            p1.checked_add(&identity(&other)),
+            Err(EccError::PointNotOnCurve)
+        );
+    }
+
+    #[test]
+    fn test_new_off_curve() {
+        let ec = toy_curve();
+        let off_curve = Point::Coordinate(BigUint::from(5u32), BigUint::from(2u32));
+        assert_eq!(
+            CurvePoint::new(&ec, off_curve),
+            Err(EccError::PointNotOnCurve)
+        );
+    }
+}
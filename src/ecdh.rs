@@ -0,0 +1,142 @@
+use crate::{EccError, EllipticCurve, Point};
+use num_bigint::BigUint;
+
+// x-coordinate of my_priv * their_pub
+// d_a P_b = d_a d_b A = d_b P_a, so both sides end up with the same x
+// their_pub must lie in the subgroup of the given (prime) order: a point with a
+// small-order part, T alone or T + Q, would give away my_priv modulo the order of T,
+// and clearing the cofactor only catches T alone
+// fails with InvalidPoint unless their_pub is a reduced point of the curve other than
+// I, and with SmallSubgroupPoint unless order their_pub = I
+pub fn ecdh_shared_secret(
+    curve: &EllipticCurve,
+    my_priv: &BigUint,
+    their_pub: &Point,
+    order: &BigUint,
+) -> Result<BigUint, EccError> {
+    check_their_pub(curve, their_pub)?;
+    if !curve.is_in_prime_order_subgroup(their_pub, order) {
+        return Err(EccError::SmallSubgroupPoint);
+    }
+    match curve.scalar_mul_ct(their_pub, my_priv)? {
+        Point::Coordinate(x, _) => Ok(x),
+        Point::Identity => Err(EccError::InvalidPoint),
+    }
+}
+
+// fails with InvalidPoint unless their_pub is a reduced point of the curve other than I
+fn check_their_pub(curve: &EllipticCurve, their_pub: &Point) -> Result<(), EccError> {
+    match their_pub {
+        Point::Identity => Err(EccError::InvalidPoint),
+        Point::Coordinate(x, y) => {
+            if x >= &curve.p || y >= &curve.p || !curve.is_on_curve(their_pub) {
+                return Err(EccError::InvalidPoint);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use num_bigint::RandBigInt;
+
+    #[test]
+    fn test_shared_secret_sec256k1() {
+        let ec = EllipticCurve::secp256k1();
+        let a_gen = EllipticCurve::secp256k1_generator();
+        let q_order = EllipticCurve::secp256k1_order();
+        let mut rng = rand::thread_rng();
+
+        let alice_priv = rng.gen_biguint_range(&BigUint::from(1u32), &q_order);
+        let alice_pub = ec
+            .scalar_mul(&a_gen, &alice_priv)
+            .expect("A is on the curve");
+        let bob_priv = rng.gen_biguint_range(&BigUint::from(1u32), &q_order);
+        let bob_pub = ec.scalar_mul(&a_gen, &bob_priv).expect("A is on the curve");
+
+        let alice_secret = ecdh_shared_secret(&ec, &alice_priv, &bob_pub, &q_order)
+            .expect("Alice could not derive");
+        let bob_secret =
+            ecdh_shared_secret(&ec, &bob_priv, &alice_pub, &q_order).expect("Bob could not derive");
+        assert_eq!(alice_secret, bob_secret);
+
+        // both equal x(d_a d_b A)
+        match ec.scalar_mul(&a_gen, &((&alice_priv * &bob_priv) % &q_order)) {
+            Ok(Point::Coordinate(x, _)) => assert_eq!(x, alice_secret),
+            _ => panic!("d_a d_b A is the identity"),
+        }
+    }
+
+    #[test]
+    fn test_shared_secret_rejects_bad_points() {
+        let ec = EllipticCurve::secp256k1();
+        let a_gen = EllipticCurve::secp256k1_generator();
+        let q_order = EllipticCurve::secp256k1_order();
+        let one = BigUint::from(1u32);
+
+        assert_eq!(
+            ecdh_shared_secret(&ec, &one, &Point::Identity, &q_order),
+            Err(EccError::InvalidPoint)
+        );
+        let off_curve = Point::Coordinate(BigUint::from(1u32), BigUint::from(1u32));
+        assert_eq!(
+            ecdh_shared_secret(&ec, &one, &off_curve, &q_order),
+            Err(EccError::InvalidPoint)
+        );
+        // q A is the identity
+        assert_eq!(
+            ecdh_shared_secret(&ec, &q_order, &a_gen, &q_order),
+            Err(EccError::InvalidPoint)
+        );
+    }
+
+    #[test]
+    fn test_shared_secret_rejects_small_subgroup() {
+        // y^2 = x^3 + x + 4 mod 17 has 14 points, A = (4, 2) of order 7 and h = 2
+        let ec = EllipticCurve::with_cofactor(
+            BigUint::from(1u32),
+            BigUint::from(4u32),
+            BigUint::from(17u32),
+            BigUint::from(2u32),
+        )
+        .expect("Cannot build curve");
+        let a_gen = Point::Coordinate(BigUint::from(4u32), BigUint::from(2u32));
+        let order = BigUint::from(7u32);
+        let my_priv = BigUint::from(3u32);
+
+        // (3, 0) has order 2, 3 (3, 0) = (3, 0) would leak the parity of my_priv
+        let order_two = Point::Coordinate(BigUint::from(3u32), BigUint::from(0u32));
+        assert_eq!(
+            ecdh_shared_secret(&ec, &my_priv, &order_two, &order),
+            Err(EccError::SmallSubgroupPoint)
+        );
+        // (0, 2) has order 14, some kA plus (3, 0): h (0, 2) is not I so clearing the
+        // cofactor would let it through, the subgroup check does not
+        let mixed = Point::Coordinate(BigUint::from(0u32), BigUint::from(2u32));
+        assert_eq!(
+            ecdh_shared_secret(&ec, &my_priv, &mixed, &order),
+            Err(EccError::SmallSubgroupPoint)
+        );
+        assert_eq!(
+            ecdh_shared_secret(&ec, &my_priv, &Point::Identity, &order),
+            Err(EccError::InvalidPoint)
+        );
+        let off_curve = Point::Coordinate(BigUint::from(3u32), BigUint::from(1u32));
+        assert_eq!(
+            ecdh_shared_secret(&ec, &my_priv, &off_curve, &order),
+            Err(EccError::InvalidPoint)
+        );
+
+        let their_priv = BigUint::from(5u32);
+        let their_pub = ec
+            .scalar_mul(&a_gen, &their_priv)
+            .expect("A is on the curve");
+        let my_pub = ec.scalar_mul(&a_gen, &my_priv).expect("A is on the curve");
+        assert_eq!(
+            ecdh_shared_secret(&ec, &my_priv, &their_pub, &order),
+            ecdh_shared_secret(&ec, &their_priv, &my_pub, &order)
+        );
+    }
+}
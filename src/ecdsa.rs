@@ -1,204 +1,2461 @@
-use ec_generic::{EllipticCurve, FiniteField, Point};
-use num_bigint::{BigInt, BigUint, RandBigInt};
-use sha256::digest;
-struct ECDSA {
+use crate::curve::Curve;
+use crate::keys::{scalar_in_range, PrivateKey, PublicKey, SecretScalar};
+use crate::rng;
+use crate::{
+    ct_eq_biguint, is_probable_prime, parse_hex, EccError, EllipticCurve, FiniteField, Point,
+};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use hmac::{Hmac, Mac};
+use num_bigint::BigUint;
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+// (hash, signature, pub_key), see ECDSA::verify_each
+pub type BatchEntry<'a> = (&'a BigUint, &'a (BigUint, BigUint), &'a PublicKey);
+// (hash, signature, recovery_id, pub_key), see ECDSA::verify_batch_recoverable
+pub type RecoverableBatchEntry<'a> = (&'a BigUint, &'a (BigUint, BigUint), u8, &'a PublicKey);
+// ((r, s), recovery_id)
+type RecoverableSignature = ((BigUint, BigUint), u8);
+// (r, s)
+#[derive(PartialEq, Debug, Clone)]
+pub struct Signature(pub (BigUint, BigUint));
+
+impl From<(BigUint, BigUint)> for Signature {
+    fn from(signature: (BigUint, BigUint)) -> Self {
+        Signature(signature)
+    }
+}
+
+impl From<Signature> for (BigUint, BigUint) {
+    fn from(signature: Signature) -> Self {
+        signature.0
+    }
+}
+
+impl Signature {
+    // SEQUENCE { INTEGER r, INTEGER s }
+    pub fn to_der(&self) -> Vec<u8> {
+        let (r, s) = &self.0;
+        let mut body = der_integer(r);
+        body.extend(der_integer(s));
+
+        let mut der = vec![0x30];
+        der.extend(der_length(body.len()));
+        der.extend(body);
+        der
+    }
+    pub fn from_der(bytes: &[u8]) -> Result<Signature, EccError> {
+        let (tag, body, rest) = der_read(bytes)?;
+        if tag != 0x30 {
+            return Err(EccError::InvalidEncoding("expected a SEQUENCE".to_string()));
+        }
+        if !rest.is_empty() {
+            return Err(EccError::InvalidEncoding(
+                "trailing bytes after the SEQUENCE".to_string(),
+            ));
+        }
+        let (r, body) = der_read_integer(body)?;
+        let (s, body) = der_read_integer(body)?;
+        if !body.is_empty() {
+            return Err(EccError::InvalidEncoding(
+                "trailing bytes inside the SEQUENCE".to_string(),
+            ));
+        }
+        Ok(Signature((r, s)))
+    }
+}
+
+// same as Signature::to_der, for signatures kept as plain (r, s) tuples
+pub fn signature_to_der(sig: &(BigUint, BigUint)) -> Vec<u8> {
+    Signature(sig.clone()).to_der()
+}
+
+pub fn signature_from_der(bytes: &[u8]) -> Result<(BigUint, BigUint), EccError> {
+    Signature::from_der(bytes).map(|signature| signature.0)
+}
+
+// (r, order - s) when s > order / 2, (r, s) otherwise, so that only one of the two
+// valid forms of a signature is ever produced. s is taken mod order first, an s out
+// of range comes back reduced
+pub fn normalize_signature(sig: &(BigUint, BigUint), order: &BigUint) -> (BigUint, BigUint) {
+    let (r, s) = sig;
+    let s = s % order;
+    let high = order - &s;
+    if high < s {
+        (r.clone(), high)
+    } else {
+        (r.clone(), s)
+    }
+}
+
+// the private key d behind two signatures sharing r, i.e. the same k
+// the signatures are taken as produced: after a low-S flip of one s the d returned
+// is not the signer's, see ECDSA::recover_key_from_nonce_reuse
+// s1 - s2 = k^-1 (h1 - h2), so k = (h1 - h2) / (s1 - s2) and d = (s1 k - h1) / r mod q
+// None when the r differ or are 0, or when s1 = s2 or h1 = h2 leave k undetermined
+pub fn detect_nonce_reuse(
+    sig1: &(BigUint, BigUint),
+    hash1: &BigUint,
+    sig2: &(BigUint, BigUint),
+    hash2: &BigUint,
+    order: &BigUint,
+) -> Option<BigUint> {
+    let ((r1, s1), (r2, s2)) = (sig1, sig2);
+    if r1 != r2 || r1 % order == BigUint::from(0u32) {
+        return None;
+    }
+    let k = FiniteField::divide(
+        &FiniteField::subtract(hash1, hash2, order),
+        &FiniteField::subtract(s1, s2, order),
+        order,
+    )
+    .ok()?;
+    if k == BigUint::from(0u32) {
+        return None;
+    }
+    let s1k_minus_h1 = FiniteField::subtract(&FiniteField::mult(s1, &k, order), hash1, order);
+    FiniteField::divide(&s1k_minus_h1, r1, order).ok()
+}
+
+// short form below 128, long form 0x8n followed by n length bytes otherwise
+fn der_length(len: usize) -> Vec<u8> {
+    if len < 0x80 {
+        return vec![len as u8];
+    }
+    let bytes: Vec<u8> = len
+        .to_be_bytes()
+        .into_iter()
+        .skip_while(|b| *b == 0)
+        .collect();
+    let mut der = vec![0x80 | bytes.len() as u8];
+    der.extend(bytes);
+    der
+}
+
+// positive INTEGER, a 0x00 is prepended when the high bit is set
+fn der_integer(n: &BigUint) -> Vec<u8> {
+    let mut bytes = n.to_bytes_be();
+    if bytes[0] & 0x80 != 0 {
+        bytes.insert(0, 0);
+    }
+    let mut der = vec![0x02];
+    der.extend(der_length(bytes.len()));
+    der.extend(bytes);
+    der
+}
+
+// returns (tag, value, remaining bytes)
+pub(crate) fn der_read(bytes: &[u8]) -> Result<(u8, &[u8], &[u8]), EccError> {
+    if bytes.len() < 2 {
+        return Err(EccError::InvalidEncoding("truncated header".to_string()));
+    }
+    let tag = bytes[0];
+    let (len, header) = if bytes[1] < 0x80 {
+        (bytes[1] as usize, 2)
+    } else {
+        let count = (bytes[1] & 0x7f) as usize;
+        if count == 0 || count > core::mem::size_of::<usize>() || bytes.len() < 2 + count {
+            return Err(EccError::InvalidEncoding("invalid length".to_string()));
+        }
+        let len = bytes[2..2 + count]
+            .iter()
+            .fold(0usize, |len, b| (len << 8) | *b as usize);
+        if len < 0x80 || bytes[2] == 0 {
+            return Err(EccError::InvalidEncoding("non-minimal length".to_string()));
+        }
+        (len, 2 + count)
+    };
+    if bytes.len() - header < len {
+        return Err(EccError::InvalidEncoding("truncated value".to_string()));
+    }
+    Ok((tag, &bytes[header..header + len], &bytes[header + len..]))
+}
+
+pub(crate) fn der_read_integer(bytes: &[u8]) -> Result<(BigUint, &[u8]), EccError> {
+    let (tag, value, rest) = der_read(bytes)?;
+    if tag != 0x02 {
+        return Err(EccError::InvalidEncoding("expected an INTEGER".to_string()));
+    }
+    if value.is_empty() {
+        return Err(EccError::InvalidEncoding("empty INTEGER".to_string()));
+    }
+    if value[0] & 0x80 != 0 {
+        return Err(EccError::InvalidEncoding("negative INTEGER".to_string()));
+    }
+    if value.len() > 1 && value[0] == 0 && value[1] & 0x80 == 0 {
+        return Err(EccError::InvalidEncoding("non-minimal INTEGER".to_string()));
+    }
+    Ok((BigUint::from_bytes_be(value), rest))
+}
+
+// RFC 6979 section 3.2 nonce generator, HMAC-SHA256 based
+struct Rfc6979 {
+    k: Vec<u8>,
+    v: Vec<u8>,
+    q: BigUint,
+    qlen: u64,
+}
+
+impl Rfc6979 {
+    fn new(priv_key: &BigUint, hash: &BigUint, q: &BigUint) -> Self {
+        let qlen = q.bits();
+        let rlen = qlen.div_ceil(8) as usize;
+        // bits2octets(h) = int2octets(h mod q)
+        let x = int2octets(priv_key, rlen);
+        let h = int2octets(&(hash % q), rlen);
+
+        let mut drbg = Rfc6979 {
+            k: vec![0x00; 32],
+            v: vec![0x01; 32],
+            q: q.clone(),
+            qlen,
+        };
+        for sep in [0x00u8, 0x01] {
+            // K = HMAC_K(V || sep || x || h), V = HMAC_K(V)
+            drbg.k = drbg.hmac(&[&drbg.v, &[sep], &x, &h]);
+            drbg.v = drbg.hmac(&[&drbg.v]);
+        }
+        drbg
+    }
+
+    // next k in [1, q)
+    fn next_k(&mut self) -> BigUint {
+        loop {
+            let mut t = Vec::new();
+            while (t.len() as u64) * 8 < self.qlen {
+                self.v = self.hmac(&[&self.v]);
+                t.extend_from_slice(&self.v);
+            }
+            // bits2int: keep the leftmost qlen bits
+            let k = BigUint::from_bytes_be(&t) >> ((t.len() as u64) * 8 - self.qlen);
+
+            // K = HMAC_K(V || 0x00), V = HMAC_K(V), ready for a retry
+            self.k = self.hmac(&[&self.v, &[0x00]]);
+            self.v = self.hmac(&[&self.v]);
+
+            if k >= BigUint::from(1u32) && k < self.q {
+                return k;
+            }
+        }
+    }
+
+    fn hmac(&self, parts: &[&[u8]]) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.k).expect("HMAC takes keys of any size");
+        for part in parts {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+// big-endian, left padded to len bytes
+pub(crate) fn int2octets(x: &BigUint, len: usize) -> Vec<u8> {
+    let bytes = x.to_bytes_be();
+    let mut out = vec![0u8; len.saturating_sub(bytes.len())];
+    out.extend_from_slice(&bytes);
+    out
+}
+
+// how a digest is brought below the order
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum HashReduction {
+    // the whole digest mod max
+    Modulo,
+    // the leftmost bits(max) bits of the digest, then mod max (FIPS 186)
+    Truncate,
+}
+
+// digest as an integer, shifted right when it is wider than bits
+fn leftmost_bits(digest: &[u8], bits: u64) -> BigUint {
+    let dlen = digest.len() as u64 * 8;
+    let e = BigUint::from_bytes_be(digest);
+    if dlen > bits {
+        e >> (dlen - bits)
+    } else {
+        e
+    }
+}
+
+// the message digest behind sign_bytes, verify_bytes, sign_with_key_bytes and
+// find_signer. As FIPS 186 asks, the leftmost bits(q) bits of the digest are kept
+// and reduced mod q (see hash_to_scalar)
+pub trait Hasher: Send + Sync {
+    fn digest(&self, msg: &[u8]) -> Vec<u8>;
+}
+
+// the default
+pub struct Sha256Hasher;
+
+pub struct Sha512Hasher;
+
+impl Hasher for Sha256Hasher {
+    fn digest(&self, msg: &[u8]) -> Vec<u8> {
+        Sha256::digest(msg).to_vec()
+    }
+}
+
+impl Hasher for Sha512Hasher {
+    fn digest(&self, msg: &[u8]) -> Vec<u8> {
+        Sha512::digest(msg).to_vec()
+    }
+}
+
+// Timing: k A when signing and d A in generate_pub_key go through the Montgomery
+// ladder (scalar_mul_montgomery, with the caveats of scalar_mul_ct), and k^-1 goes
+// through FiniteField::inv_multiplication_ct, which verify also uses to invert s.
+// verify compares x mod q with r limb by limb (ct_eq_biguint). Hashing, the key
+// range checks and num-bigint's own limb arithmetic are not constant-time.
+#[allow(clippy::upper_case_acronyms)]
+pub struct ECDSA {
     ec: EllipticCurve,
     a_gen: Point,
     q_order: BigUint, //order of the group
+    // reject public keys that hP sends to the identity
+    clear_cofactor: bool,
+    // digest of sign_bytes and verify_bytes
+    hasher: Box<dyn Hasher>,
 }
 
 impl ECDSA {
+    // fails with InvalidPoint when the generator is not a point of the curve and with
+    // InvalidOrder when order is not prime or order * generator is not the identity,
+    // e.g. p passed for n
+    pub fn new(ec: EllipticCurve, generator: Point, order: BigUint) -> Result<ECDSA, EccError> {
+        let reduced = matches!(&generator, Point::Coordinate(x, y) if x < &ec.p && y < &ec.p);
+        if !reduced || !ec.is_on_curve(&generator) {
+            return Err(EccError::InvalidPoint);
+        }
+        if !is_probable_prime(&order) || !ec.is_in_subgroup(&generator, &order) {
+            return Err(EccError::InvalidOrder);
+        }
+        Ok(ECDSA {
+            ec,
+            a_gen: generator,
+            q_order: order,
+            clear_cofactor: false,
+            hasher: Box::new(Sha256Hasher),
+        })
+    }
+    // every parameter in hex, with or without a 0x prefix, checked as in new
+    pub fn from_hex(
+        a: &str,
+        b: &str,
+        p: &str,
+        gx: &str,
+        gy: &str,
+        n: &str,
+    ) -> Result<ECDSA, EccError> {
+        let ec = EllipticCurve::from_hex(a, b, p)?;
+        let generator = Point::from_hex(gx, gy)?;
+        ECDSA::new(ec, generator, parse_hex(n)?)
+    }
+    // see verify
+    pub fn with_clear_cofactor(mut self, clear_cofactor: bool) -> Self {
+        self.clear_cofactor = clear_cofactor;
+        self
+    }
+    // see sign_bytes
+    pub fn with_hasher(mut self, hasher: Box<dyn Hasher>) -> Self {
+        self.hasher = hasher;
+        self
+    }
+    pub fn ec(&self) -> &EllipticCurve {
+        &self.ec
+    }
+    pub fn a_gen(&self) -> &Point {
+        &self.a_gen
+    }
+    pub fn q_order(&self) -> &BigUint {
+        &self.q_order
+    }
     // Generates
-    pub fn generate_key_pair(&self) -> (BigUint, Point) {
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    pub fn generate_key_pair(&self) -> (PrivateKey, PublicKey) {
         let priv_key = self.generate_priv_key();
         let pub_key = self.generate_pub_key(&priv_key);
 
         (priv_key, pub_key)
     }
-    pub fn generate_priv_key(&self) -> BigUint {
-        self.generate_random_positive_no_less_than(&self.q_order)
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    pub fn generate_priv_key(&self) -> PrivateKey {
+        let d = self.generate_random_positive_no_less_than(&self.q_order);
+        PrivateKey::new(d, &self.q_order).expect("d is drawn from [1, q)")
     }
     // (0, q)
+    #[cfg(any(feature = "std", feature = "getrandom"))]
     pub fn generate_random_positive_no_less_than(&self, q: &BigUint) -> BigUint {
-        let mut rng = rand::thread_rng();
-        rng.gen_biguint_range(&BigUint::from(1u32), q)
+        rng::gen_biguint_range(&BigUint::from(1u32), q)
     }
-    pub fn generate_pub_key(&self, priv_key: &BigUint) -> Point {
-        self.ec
-            .scalar_mul(&self.a_gen, priv_key)
-            .expect("Could not generate Pub Key.")
+    pub fn generate_pub_key(&self, priv_key: &PrivateKey) -> PublicKey {
+        let point = self
+            .ec
+            .scalar_mul_montgomery(&self.a_gen, priv_key.scalar())
+            .expect("A is checked by ECDSA::new");
+        PublicKey::new(point, &self.ec).expect("dA is on the curve and d is not a multiple of q")
     }
     // returns (r,s)
-    pub fn sign(&self, priv_key: &BigUint, hash: &BigUint) -> (BigUint, BigUint) {
+    // fails with InvalidScalar when hash is not below q or priv_key is not in [1, q)
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    pub fn sign(
+        &self,
+        priv_key: &PrivateKey,
+        hash: &BigUint,
+    ) -> Result<(BigUint, BigUint), EccError> {
         // R = kA
-        // r = x-component( R )
+        // r = x-component( R ) mod q
         // s = ( hash(msg) + d*r ) k^-1
-        assert!(hash < &self.q_order, "Hash should be less than order");
-        assert!(priv_key < &self.q_order, "Hash should be less than order");
-        let k = self.generate_random_positive_no_less_than(&self.q_order);
-        let R = EllipticCurve::scalar_mul(&self.ec, &self.a_gen, &k).expect("Could not gen R");
-
-        if let Point::Coor(r, _) = R {
-            let dr = FiniteField::mult(&priv_key, &r, &self.q_order).expect("Could not d*rmod p");
-            let hash_plus_dr = FiniteField::add(&hash, &dr, &self.q_order).expect("could not add");
-            let k_inv =
-                FiniteField::inv_mult_prime(&k, &self.q_order).expect("Could not inverse k");
-            let s =
-                FiniteField::mult(&hash_plus_dr, &k_inv, &self.q_order).expect("Could not find s");
-            return (r, s);
-        }
-        panic!("Error while generating signature");
+        // a new k is drawn whenever r or s comes out as zero
+        // s is always returned in its low form, see normalize_s
+        self.check_sign_inputs(priv_key, hash)?;
+        loop {
+            let k = SecretScalar::new(self.generate_random_positive_no_less_than(&self.q_order));
+            if let Some(signature) = self.sign_with_k(priv_key.scalar(), hash, k.scalar())? {
+                return Ok(signature);
+            }
+        }
+    }
+    // same as sign, but k is drawn from rng, for targets without thread_rng or getrandom
+    pub fn sign_with_rng<R: RngCore>(
+        &self,
+        priv_key: &PrivateKey,
+        hash: &BigUint,
+        rng: &mut R,
+    ) -> Result<(BigUint, BigUint), EccError> {
+        self.check_sign_inputs(priv_key, hash)?;
+        loop {
+            let k = SecretScalar::new(rng::gen_biguint_range_with(
+                rng,
+                &BigUint::from(1u32),
+                &self.q_order,
+            ));
+            if let Some(signature) = self.sign_with_k(priv_key.scalar(), hash, k.scalar())? {
+                return Ok(signature);
+            }
+        }
+    }
+    // same as sign, along with the recovery id that recover_pub_key and
+    // verify_batch_recoverable take
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    pub fn sign_recoverable(
+        &self,
+        priv_key: &PrivateKey,
+        hash: &BigUint,
+    ) -> Result<((BigUint, BigUint), u8), EccError> {
+        self.check_sign_inputs(priv_key, hash)?;
+        loop {
+            let k = SecretScalar::new(self.generate_random_positive_no_less_than(&self.q_order));
+            if let Some(signature) =
+                self.sign_with_k_recoverable(priv_key.scalar(), hash, k.scalar())?
+            {
+                return Ok(signature);
+            }
+        }
+    }
+    // same as sign, but k is derived from priv_key and hash as in RFC 6979 (HMAC-SHA256)
+    pub fn sign_deterministic(
+        &self,
+        priv_key: &PrivateKey,
+        hash: &BigUint,
+    ) -> Result<(BigUint, BigUint), EccError> {
+        self.check_sign_inputs(priv_key, hash)?;
+        let mut nonces = Rfc6979::new(priv_key.scalar(), hash, &self.q_order);
+        loop {
+            let k = SecretScalar::new(nonces.next_k());
+            if let Some(signature) = self.sign_with_k(priv_key.scalar(), hash, k.scalar())? {
+                return Ok(signature);
+            }
+        }
+    }
+    // the private key behind two signatures of pub_key that share r, i.e. the same k,
+    // over different hashes
+    // sign returns low-S signatures, so one s may have been replaced by q - s: both
+    // (r, s2) and (r, q - s2) give a candidate (see detect_nonce_reuse), and only
+    // the one with d A = pub_key is returned
+    // None when the r differ, when the hashes or signatures leave k undetermined, or
+    // when no candidate matches pub_key
+    pub fn recover_key_from_nonce_reuse(
+        &self,
+        sig1: &(BigUint, BigUint),
+        hash1: &BigUint,
+        sig2: &(BigUint, BigUint),
+        hash2: &BigUint,
+        pub_key: &PublicKey,
+    ) -> Option<PrivateKey> {
+        let (r2, s2) = sig2;
+        let q = &self.q_order;
+        [s2 % q, q - s2 % q]
+            .into_iter()
+            .filter_map(|s2| detect_nonce_reuse(sig1, hash1, &(r2.clone(), s2), hash2, q))
+            .filter_map(|d| PrivateKey::new(d, q).ok())
+            .find(|d| self.generate_pub_key(d) == *pub_key)
+    }
+    // a PrivateKey built for another order can be out of range for this one
+    fn check_sign_inputs(&self, priv_key: &PrivateKey, hash: &BigUint) -> Result<(), EccError> {
+        check_sign_inputs(priv_key, hash, &self.q_order)
+    }
+    // first RFC 6979 candidate for k
+    pub fn deterministic_k(&self, priv_key: &PrivateKey, hash: &BigUint) -> SecretScalar {
+        SecretScalar::new(Rfc6979::new(priv_key.scalar(), hash, &self.q_order).next_k())
+    }
+    // None when r or s comes out as zero and a new k is needed
+    fn sign_with_k(
+        &self,
+        priv_key: &BigUint,
+        hash: &BigUint,
+        k: &BigUint,
+    ) -> Result<Option<(BigUint, BigUint)>, EccError> {
+        Ok(self
+            .sign_with_k_recoverable(priv_key, hash, k)?
+            .map(|(signature, _)| signature))
+    }
+    // sign_with_k, with the recovery id of the returned signature (see recover_pub_key)
+    fn sign_with_k_recoverable(
+        &self,
+        priv_key: &BigUint,
+        hash: &BigUint,
+        k: &BigUint,
+    ) -> Result<Option<RecoverableSignature>, EccError> {
+        let r_point = self
+            .ec
+            .scalar_mul_montgomery(&self.a_gen, k)
+            .expect("A is checked by ECDSA::new");
+        let Some((r, s)) = sign_with_r_point(priv_key, hash, k, &r_point, &self.q_order)? else {
+            return Ok(None);
+        };
+        let Point::Coordinate(x, y) = r_point else {
+            unreachable!("r is 0 when R = I")
+        };
+        // x(R) is a field element, it can be >= q when p > q
+        let mut recovery_id = (y.bit(0) as u8) | ((x >= self.q_order) as u8) << 1;
+        // q - s is the signature of -R, whose y has the other parity
+        let low_s = self.normalize_s(&s);
+        if low_s != s {
+            recovery_id ^= 1;
+        }
+        Ok(Some(((r, low_s), recovery_id)))
+    }
+    // sign, then normalize_signature. sign already returns the low-S form, this spells
+    // it out for callers that have to guarantee it
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    pub fn sign_normalized(
+        &self,
+        priv_key: &PrivateKey,
+        hash: &BigUint,
+    ) -> Result<(BigUint, BigUint), EccError> {
+        let signature = self.sign(priv_key, hash)?;
+        Ok(normalize_signature(&signature, &self.q_order))
+    }
+    // hasher digest of msg brought below q (sha256 unless set with with_hasher), then sign
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    pub fn sign_bytes(
+        &self,
+        priv_key: &PrivateKey,
+        msg: &[u8],
+    ) -> Result<(BigUint, BigUint), EccError> {
+        self.sign(priv_key, &self.digest_bytes(msg))
+    }
+    // as sign_bytes, with k from RFC 6979
+    pub fn sign_bytes_deterministic(
+        &self,
+        priv_key: &PrivateKey,
+        msg: &[u8],
+    ) -> Result<(BigUint, BigUint), EccError> {
+        self.sign_deterministic(priv_key, &self.digest_bytes(msg))
+    }
+    // hasher digest of msg brought below q, then verify
+    pub fn verify_bytes(
+        &self,
+        msg: &[u8],
+        signature: &(BigUint, BigUint),
+        pub_key: &PublicKey,
+    ) -> bool {
+        self.verify(&self.digest_bytes(msg), signature, pub_key)
+    }
+    fn digest_bytes(&self, msg: &[u8]) -> BigUint {
+        self.hash_to_scalar(&self.hasher.digest(msg))
+    }
+    // priv_bytes is the big-endian encoding of the private key, msg is hashed as in
+    // sign_bytes
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    pub fn sign_with_key_bytes(
+        &self,
+        priv_bytes: &[u8],
+        msg: &[u8],
+    ) -> Result<(BigUint, BigUint), EccError> {
+        let priv_key = PrivateKey::new(BigUint::from_bytes_be(priv_bytes), &self.q_order)?;
+
+        self.sign(&priv_key, &self.digest_bytes(msg))
+    }
+    // D(msg) in [0, max) for any hash D, e.g. ecdsa.hash_message::<Sha512_256>(msg, q)
+    // whatever the width of D, the leftmost bits(max) bits are kept and reduced mod max,
+    // as hash_to_scalar does for max = q
+    pub fn hash_message<D: Digest>(&self, msg: &[u8], max: &BigUint) -> BigUint {
+        self.hash_bytes_less_than_with::<D>(msg, max, HashReduction::Truncate)
+    }
+    // FIPS 186: keep the leftmost bits(q) bits of the digest, a digest wider than
+    // q is shifted right rather than reduced. What is left is below 2q, so a single
+    // reduction brings it into [0, q)
+    pub fn hash_to_scalar(&self, digest: &[u8]) -> BigUint {
+        leftmost_bits(digest, self.q_order.bits()) % &self.q_order
     }
     //// u1 = s^-1 * hash(msg) mod q
     //// u2 = s^-1 * r mod q
     //// P = u1 A + u2 B mod q = (xp, yp)       # A is generator and B is pub key.
-    //// if r == xp return 1
-    pub fn verify(&self, hash: &BigUint, signature: &(BigUint, BigUint), pub_key: &Point) -> bool {
-        assert!(hash < &self.q_order, "Hash should be less than order");
+    //// if r == xp mod q return 1
+    // a hash that is not below q never verifies
+    pub fn verify(
+        &self,
+        hash: &BigUint,
+        signature: &(BigUint, BigUint),
+        pub_key: &PublicKey,
+    ) -> bool {
+        if !self.check_pub_key(pub_key) {
+            return false;
+        }
+        // u1 A + u2 B in one pass with Shamir's trick
+        verify_with(hash, signature, &self.q_order, |u1, u2| {
+            self.ec.mul_add(u1, &self.a_gen, u2, pub_key.point())
+        })
+    }
+    fn check_pub_key(&self, pub_key: &PublicKey) -> bool {
+        // an off-curve key would have u2 B computed on some other curve
+        if !pub_key.is_valid_on(&self.ec) {
+            return false;
+        }
+        // a key with no component in the order-q subgroup is cleared to I
+        if self.clear_cofactor && self.ec.clear_cofactor(pub_key.point()) == Ok(Point::Identity) {
+            return false;
+        }
+        // with cofactor 1 every point other than I has order q, otherwise the key
+        // must lie in the order-q subgroup
+        self.ec.cofactor == BigUint::from(1u32)
+            || self
+                .ec
+                .is_in_prime_order_subgroup(pub_key.point(), &self.q_order)
+    }
+    // (r, s) and (r, q - s) are both valid, min(s, q - s) picks the low one
+    pub fn normalize_s(&self, s: &BigUint) -> BigUint {
+        let (_, s) = normalize_signature(&(BigUint::from(0u32), s.clone()), &self.q_order);
+        s
+    }
+    // like verify, but a high-S signature is rejected
+    pub fn verify_strict(
+        &self,
+        hash: &BigUint,
+        signature: &(BigUint, BigUint),
+        pub_key: &PublicKey,
+    ) -> bool {
         let (r, s) = signature;
+        // r and s in [1, q) before the low-S test, which subtracts s from q
+        let zero = BigUint::from(0u32);
+        if *r == zero || *s == zero || r >= &self.q_order || s >= &self.q_order {
+            return false;
+        }
+        if self.normalize_s(s) != *s {
+            return false;
+        }
+        self.verify(hash, signature, pub_key)
+    }
+    // the public key that produced signature over hash
+    // bit 0 of recovery_id is the parity of y(R), bit 1 is set when x(R) = r + q
+    // R = (x, sqrt(x^3 + ax + b)) with the parity picked by bit 0
+    // P = r^-1 (sR - hash A)
+    pub fn recover_pub_key(
+        &self,
+        hash: &BigUint,
+        signature: &(BigUint, BigUint),
+        recovery_id: u8,
+    ) -> Result<PublicKey, EccError> {
+        if hash >= &self.q_order {
+            return Err(EccError::InvalidScalar);
+        }
+        let (r, s) = signature;
+        if recovery_id > 3 {
+            return Err(EccError::InvalidEncoding(format!(
+                "invalid recovery id: {}",
+                recovery_id
+            )));
+        }
+        let zero = BigUint::from(0u32);
+        if *r == zero || *s == zero || r >= &self.q_order || s >= &self.q_order {
+            return Err(EccError::InvalidScalar);
+        }
+        let r_point = self.recover_r_point(r, recovery_id)?;
 
-        let s_inv =
-            FiniteField::inv_mult_prime(&s, &self.q_order).expect("Could not get s inverse");
-        let u1 = FiniteField::mult(&s_inv, hash, &self.q_order)
-            .expect("Could not multiply hash and s inv");
-        let u2 = FiniteField::mult(&s_inv, &r, &self.q_order).expect("Could not compute u2");
-        let u1a = EllipticCurve::scalar_mul(&self.ec, &self.a_gen, &u1).expect("Error in u1 A");
-        let u1b = EllipticCurve::scalar_mul(&self.ec, &pub_key, &u2).expect("Error in u1 A");
-        let p = EllipticCurve::add(&self.ec, &u1a, &u1b).expect("Could not compute point P");
-
-        if let Point::Coor(xp, _) = p {
-            if xp == *r {
-                return true;
+        // r^-1 (sR - hash A) = (s r^-1) R + (hash r^-1) (-A)
+        let r_inv = FiniteField::inv(r, &self.q_order)?;
+        let u1 = FiniteField::mult(s, &r_inv, &self.q_order);
+        let u2 = FiniteField::mult(hash, &r_inv, &self.q_order);
+        let point = self
+            .ec
+            .mul_add(&u1, &r_point, &u2, &self.ec.negate(&self.a_gen))?;
+        let pub_key = PublicKey::new(point, &self.ec)?;
+        if !self.verify(hash, signature, &pub_key) {
+            return Err(EccError::InvalidSignature);
+        }
+        Ok(pub_key)
+    }
+    // the R of a signature, from r and the recovery id
+    // fails with InvalidPoint when x(R) is not below p or has no point above it
+    fn recover_r_point(&self, r: &BigUint, recovery_id: u8) -> Result<Point, EccError> {
+        let x = if recovery_id & 2 != 0 {
+            r + &self.q_order
+        } else {
+            r.clone()
+        };
+        if x >= self.ec.p {
+            return Err(EccError::InvalidPoint);
+        }
+        let x3 = x.modpow(&BigUint::from(3u32), &self.ec.p);
+        let ax = FiniteField::mult(&self.ec.a, &x, &self.ec.p);
+        let alpha = FiniteField::add(
+            &FiniteField::add(&x3, &ax, &self.ec.p),
+            &self.ec.b,
+            &self.ec.p,
+        );
+        let y = FiniteField::sqrt(&alpha, &self.ec.p).ok_or(EccError::InvalidPoint)?;
+        let y = if y.bit(0) == (recovery_id & 1 != 0) {
+            y
+        } else {
+            FiniteField::neg(&y, &self.ec.p)
+        };
+        Ok(Point::Coordinate(x, y))
+    }
+    // every key recover_pub_key finds over the four recovery ids, x(R) = r and
+    // x(R) = r + q with both parities of y. A recovery id whose R is not on the curve
+    // is skipped, out of range r, s or hash still fail with InvalidScalar
+    pub fn recover_pub_keys(
+        &self,
+        hash: &BigUint,
+        signature: &(BigUint, BigUint),
+    ) -> Result<Vec<PublicKey>, EccError> {
+        let mut pub_keys = Vec::new();
+        for recovery_id in 0..4 {
+            match self.recover_pub_key(hash, signature, recovery_id) {
+                Ok(pub_key) => pub_keys.push(pub_key),
+                Err(EccError::InvalidPoint | EccError::InvalidSignature) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(pub_keys)
+    }
+    // entries are (hash, signature, pub_key), true only when every signature is valid
+    // this is not a batch verification: each entry goes through verify in turn,
+    // stopping at the first failure, so it costs as much as the loop it replaces. A
+    // random linear combination needs each nonce point R and r only gives x(R), so
+    // callers that want the faster check should keep the recovery id from
+    // sign_recoverable and use verify_batch_recoverable
+    pub fn verify_each(&self, entries: &[BatchEntry]) -> bool {
+        entries
+            .iter()
+            .all(|(hash, signature, pub_key)| self.verify(hash, signature, pub_key))
+    }
+    // entries are (hash, signature, recovery_id, pub_key), with the recovery id that
+    // sign_recoverable returned along with the signature
+    //
+    // a signature is valid when R = u1 A + u2 B, u1 = hash s^-1 and u2 = r s^-1, and r
+    // only gives x(R): the recovery id picks R itself, as in recover_pub_key. With
+    // random zi the whole batch then comes down to a single multi_scalar_mul,
+    //     (sum zi u1i) A + sum (zi u2i) Bi - sum zi Ri == I
+    // a bad entry leaves the sum at I for one value of its zi mod q only, so with zi
+    // drawn from [1, min(q, 2^128)) a bad batch passes with probability 2^-128 at most
+    // (1/q for toy curves)
+    // an entry whose R cannot be recovered, x(R) not below p or without a point above
+    // it, is left out of the sum and checked with verify. A valid signature given a
+    // wrong recovery id that still gives a point fails the batch
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    pub fn verify_batch_recoverable(&self, entries: &[RecoverableBatchEntry]) -> bool {
+        let bound = self.batch_coefficient_bound();
+        let z: Vec<BigUint> = entries
+            .iter()
+            .map(|_| rng::gen_biguint_range(&BigUint::from(1u32), &bound))
+            .collect();
+        self.verify_batch_with_coefficients(entries, &z)
+    }
+    // same as verify_batch_recoverable, but the zi are drawn from rng
+    pub fn verify_batch_recoverable_with_rng<R: RngCore>(
+        &self,
+        entries: &[RecoverableBatchEntry],
+        rng: &mut R,
+    ) -> bool {
+        let bound = self.batch_coefficient_bound();
+        let z: Vec<BigUint> = entries
+            .iter()
+            .map(|_| rng::gen_biguint_range_with(rng, &BigUint::from(1u32), &bound))
+            .collect();
+        self.verify_batch_with_coefficients(entries, &z)
+    }
+    fn batch_coefficient_bound(&self) -> BigUint {
+        (BigUint::from(1u32) << 128u32).min(self.q_order.clone())
+    }
+    fn verify_batch_with_coefficients(
+        &self,
+        entries: &[RecoverableBatchEntry],
+        z: &[BigUint],
+    ) -> bool {
+        let q = &self.q_order;
+        let zero = BigUint::from(0u32);
+        let mut a_coefficient = BigUint::from(0u32);
+        let mut points = Vec::with_capacity(2 * entries.len() + 1);
+        let mut coefficients = Vec::with_capacity(2 * entries.len() + 1);
+        for ((hash, signature, recovery_id, pub_key), zi) in entries.iter().zip(z) {
+            let (r, s) = signature;
+            // the same checks as verify, entry by entry
+            if *hash >= q || !self.check_pub_key(pub_key) {
+                return false;
             }
+            if *r == zero || *s == zero || r >= q || s >= q {
+                return false;
+            }
+            let Ok(r_point) = self.recover_r_point(r, *recovery_id) else {
+                if !self.verify(hash, signature, pub_key) {
+                    return false;
+                }
+                continue;
+            };
+            // R = u1 A + u2 B lies in the order-q subgroup, a small order component
+            // in R could cancel against another entry's
+            if self.ec.cofactor != BigUint::from(1u32)
+                && !self.ec.is_in_prime_order_subgroup(&r_point, q)
+            {
+                return false;
+            }
+            let Ok(s_inv) = FiniteField::inv(s, q) else {
+                return false;
+            };
+            let u1 = FiniteField::mult(&s_inv, hash, q);
+            let u2 = FiniteField::mult(&s_inv, r, q);
+            a_coefficient = FiniteField::add(&a_coefficient, &FiniteField::mult(zi, &u1, q), q);
+            points.push(pub_key.point().clone());
+            coefficients.push(FiniteField::mult(zi, &u2, q));
+            points.push(self.ec.negate(&r_point));
+            coefficients.push(zi.clone());
+        }
+        points.push(self.a_gen.clone());
+        coefficients.push(a_coefficient);
+        let terms: Vec<_> = points.iter().zip(&coefficients).collect();
+        self.ec.multi_scalar_mul(&terms) == Ok(Point::Identity)
+    }
+    // items are (message, signature), returns the index of the first candidate
+    // public key that verifies every item, messages hashed as in verify_bytes
+    pub fn find_signer(
+        &self,
+        items: &[(Vec<u8>, (BigUint, BigUint))],
+        candidates: &[PublicKey],
+    ) -> Option<usize> {
+        if items.is_empty() {
+            return None;
         }
+        let hashes: Vec<BigUint> = items
+            .iter()
+            .map(|(msg, _)| self.digest_bytes(msg))
+            .collect();
+        candidates.iter().position(|pub_key| {
+            items
+                .iter()
+                .zip(&hashes)
+                .all(|((_, signature), hash)| self.verify(hash, signature, pub_key))
+        })
+    }
+    pub fn generate_hash_less_than(
+        &self,
+        message: &str,
+        max: &BigUint,
+        reduction: HashReduction,
+    ) -> BigUint {
+        self.hash_bytes_less_than(message.as_bytes(), max, reduction)
+    }
+    // sha256 of message in [0, max)
+    pub fn hash_bytes_less_than(
+        &self,
+        message: &[u8],
+        max: &BigUint,
+        reduction: HashReduction,
+    ) -> BigUint {
+        self.hash_bytes_less_than_with::<Sha256>(message, max, reduction)
+    }
+    // hash_bytes_less_than with D in place of sha256
+    pub fn hash_bytes_less_than_with<D: Digest>(
+        &self,
+        message: &[u8],
+        max: &BigUint,
+        reduction: HashReduction,
+    ) -> BigUint {
+        let hash_bytes = D::digest(message);
+        let hash = match reduction {
+            HashReduction::Modulo => BigUint::from_bytes_be(&hash_bytes),
+            HashReduction::Truncate => leftmost_bits(&hash_bytes, max.bits()),
+        };
+        hash % max
+    }
+}
+
+// ECDSA over any Curve, e.g. TwistedEdwardsCurve: ECDSA::sign_deterministic through
+// the group law of the trait alone, on the same scalar code (sign_with_r_point and
+// verify_with). ECDSA keeps EllipticCurve for its table of multiples of A, mul_add and
+// the cofactor checks, none of which the trait offers
+// fails with InvalidScalar unless hash < order and d is in [1, order), and with
+// PointNotOnCurve when generator is not a point of curve
+pub fn sign_generic<C: Curve<Point = Point>>(
+    curve: &C,
+    generator: &Point,
+    order: &BigUint,
+    priv_key: &PrivateKey,
+    hash: &BigUint,
+) -> Result<(BigUint, BigUint), EccError> {
+    check_sign_inputs(priv_key, hash, order)?;
+    let mut nonces = Rfc6979::new(priv_key.scalar(), hash, order);
+    loop {
+        let k = SecretScalar::new(nonces.next_k());
+        let r_point = curve.scalar_mul_ct(generator, k.scalar())?;
+        if let Some(signature) =
+            sign_with_r_point(priv_key.scalar(), hash, k.scalar(), &r_point, order)?
+        {
+            return Ok(normalize_signature(&signature, order));
+        }
+    }
+}
+
+// the verify of sign_generic: x(u1 G + u2 B) = r mod q with u1 = hash / s and
+// u2 = r / s. B must be a point of curve other than the identity with q B = I, which
+// also rules out the small-order points of a curve with a cofactor
+pub fn verify_generic<C: Curve<Point = Point>>(
+    curve: &C,
+    generator: &Point,
+    order: &BigUint,
+    hash: &BigUint,
+    signature: &(BigUint, BigUint),
+    pub_key: &Point,
+) -> bool {
+    let identity = curve.identity();
+    if *pub_key == identity
+        || !curve.is_on_curve(pub_key)
+        || curve.scalar_mul(pub_key, order) != Ok(identity)
+    {
         return false;
     }
-    pub fn generate_hash_less_than(&self, message: &str, max: &BigUint) -> BigUint {
-        let hash = digest(message);
-        let hash_bytes = hex::decode(hash).expect("Could not decode hash");
-        let hash = BigUint::from_bytes_be(&hash_bytes);
-        let hash = hash.modpow(&BigUint::from(1u32), &(max - BigUint::from(1u32)));
-        hash
+    verify_with(hash, signature, order, |u1, u2| {
+        let u1g = curve.scalar_mul(generator, u1)?;
+        let u2b = curve.scalar_mul(pub_key, u2)?;
+        curve.add(&u1g, &u2b)
+    })
+}
+
+// the scalar side of ECDSA, shared by ECDSA and sign_generic / verify_generic so that
+// the two cannot drift apart; only the group operations differ
+
+// fails with InvalidScalar unless hash < order and d is in [1, order)
+fn check_sign_inputs(
+    priv_key: &PrivateKey,
+    hash: &BigUint,
+    order: &BigUint,
+) -> Result<(), EccError> {
+    if hash >= order || !scalar_in_range(priv_key.scalar(), order) {
+        return Err(EccError::InvalidScalar);
+    }
+    Ok(())
+}
+
+// r = x(R) mod q, s = k^-1 (hash + d r) mod q for R = kG, None when either is 0 and a
+// new k is needed. s is returned as computed, the callers normalize it
+// d r, hash + d r and k^-1 all give away d or k and are held as SecretScalar
+// fails when k has no inverse mod order, i.e. order is not prime
+fn sign_with_r_point(
+    priv_key: &BigUint,
+    hash: &BigUint,
+    k: &BigUint,
+    r_point: &Point,
+    order: &BigUint,
+) -> Result<Option<(BigUint, BigUint)>, EccError> {
+    let zero = BigUint::from(0u32);
+    // the identity and, on a twisted Edwards curve, its (0, 1) both give r = 0
+    let r = match r_point {
+        Point::Coordinate(x, _) => x % order,
+        Point::Identity => return Ok(None),
+    };
+    if r == zero {
+        return Ok(None);
+    }
+    let dr = SecretScalar::new(FiniteField::mult(priv_key, &r, order));
+    let hash_plus_dr = SecretScalar::new(FiniteField::add(hash, dr.scalar(), order));
+    // k is secret, its inverse goes through the fixed-round gcd
+    let k_inv = SecretScalar::new(FiniteField::inv_multiplication_ct(k, order)?);
+    let s = FiniteField::mult(hash_plus_dr.scalar(), k_inv.scalar(), order);
+    if s == zero {
+        return Ok(None);
+    }
+    Ok(Some((r, s)))
+}
+
+// u1 = hash s^-1, u2 = r s^-1 and x(combine(u1, u2)) = r mod q, where combine
+// returns u1 G + u2 B
+// false unless hash < q and r and s are in [1, q), an r left as a raw x >= q is not
+// canonical
+fn verify_with<F>(
+    hash: &BigUint,
+    signature: &(BigUint, BigUint),
+    order: &BigUint,
+    combine: F,
+) -> bool
+where
+    F: FnOnce(&BigUint, &BigUint) -> Result<Point, EccError>,
+{
+    let (r, s) = signature;
+    let zero = BigUint::from(0u32);
+    if hash >= order || *r == zero || *s == zero || r >= order || s >= order {
+        return false;
+    }
+    let Ok(s_inv) = FiniteField::inv_multiplication_ct(s, order) else {
+        return false;
+    };
+    let u1 = FiniteField::mult(&s_inv, hash, order);
+    let u2 = FiniteField::mult(&s_inv, r, order);
+    match combine(&u1, &u2) {
+        Ok(Point::Coordinate(x, _)) => ct_eq_biguint(&(x % order), r),
+        _ => false,
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use num_bigint::RandBigInt;
+    use rand::Rng;
+    use sha256::digest;
 
     #[test]
     fn test_sign_verify() {
-        let elliptic_curve = EllipticCurve {
-            a: BigUint::from(2u32),
-            b: BigUint::from(2u32),
-            p: BigUint::from(17u32),
-        };
-        let ecdsa = ECDSA {
-            ec: elliptic_curve,
-            a_gen: Point::Coor(BigUint::from(5u32), BigUint::from(1u32)),
-            q_order: BigUint::from(19u32),
-        };
+        let elliptic_curve = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let ecdsa = ECDSA::new(
+            elliptic_curve,
+            Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32)),
+            BigUint::from(19u32),
+        )
+        .expect("Cannot build ECDSA");
 
-        let priv_key = BigUint::from(7u32);
+        let priv_key =
+            PrivateKey::new(BigUint::from(7u32), &ecdsa.q_order).expect("Cannot build priv_key");
         let pub_key = ecdsa.generate_pub_key(&priv_key);
 
-        let hash = ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order);
-        let signature = ecdsa.sign(&priv_key, &hash);
-        println!("Signature: {:?}", signature);
-
-        println!("Verify {}", ecdsa.verify(&hash, &signature, &pub_key));
+        let hash =
+            ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, HashReduction::Modulo);
+        let signature = ecdsa.sign(&priv_key, &hash).expect("Could not sign");
         assert!(ecdsa.verify(&hash, &signature, &pub_key));
     }
     #[test]
-    #[should_panic]
+    fn test_detect_nonce_reuse() {
+        let elliptic_curve = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let ecdsa = ECDSA::new(
+            elliptic_curve,
+            Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32)),
+            BigUint::from(19u32),
+        )
+        .expect("Cannot build ECDSA");
+        let q = &ecdsa.q_order;
+        let priv_key = PrivateKey::new(BigUint::from(7u32), q).expect("Cannot build priv_key");
+        let pub_key = ecdsa.generate_pub_key(&priv_key);
+
+        let (h1, h2) = (BigUint::from(3u32), BigUint::from(11u32));
+        let mut flipped = 0;
+        for k in 1u32..19 {
+            let k = BigUint::from(k);
+            let (Some(sig1), Some(sig2)) = (
+                ecdsa
+                    .sign_with_k(priv_key.scalar(), &h1, &k)
+                    .expect("Could not sign"),
+                ecdsa
+                    .sign_with_k(priv_key.scalar(), &h2, &k)
+                    .expect("Could not sign"),
+            ) else {
+                continue;
+            };
+            assert!(ecdsa.verify(&h1, &sig1, &pub_key));
+            assert!(ecdsa.verify(&h2, &sig2, &pub_key));
+            // low-S flipped exactly one s when the plain formula gives another key
+            if detect_nonce_reuse(&sig1, &h1, &sig2, &h2, q).as_ref() != Some(priv_key.scalar()) {
+                flipped += 1;
+            }
+            assert_eq!(
+                ecdsa.recover_key_from_nonce_reuse(&sig1, &h1, &sig2, &h2, &pub_key),
+                Some(priv_key.clone())
+            );
+            // for any other public key, only a candidate that matches it comes back
+            let (r, s2) = &sig2;
+            let candidates = [s2.clone(), q - s2]
+                .map(|s2| detect_nonce_reuse(&sig1, &h1, &(r.clone(), s2), &h2, q));
+            for d in 1u32..19 {
+                let d = BigUint::from(d);
+                let other = ecdsa.generate_pub_key(
+                    &PrivateKey::new(d.clone(), q).expect("Cannot build priv_key"),
+                );
+                let found = ecdsa.recover_key_from_nonce_reuse(&sig1, &h1, &sig2, &h2, &other);
+                if candidates.contains(&Some(d.clone())) {
+                    assert_eq!(found.map(|d| d.scalar().clone()), Some(d));
+                } else {
+                    assert_eq!(found, None);
+                }
+            }
+        }
+        assert!(flipped > 0);
+
+        let sig = (BigUint::from(5u32), BigUint::from(4u32));
+        let other_r = (BigUint::from(6u32), BigUint::from(9u32));
+        assert_eq!(detect_nonce_reuse(&sig, &h1, &other_r, &h2, q), None);
+        assert_eq!(detect_nonce_reuse(&sig, &h1, &sig, &h1, q), None);
+        assert_eq!(
+            ecdsa.recover_key_from_nonce_reuse(&sig, &h1, &other_r, &h2, &pub_key),
+            None
+        );
+        // the same signature twice fixes nothing
+        assert_eq!(
+            ecdsa.recover_key_from_nonce_reuse(&sig, &h1, &sig, &h1, &pub_key),
+            None
+        );
+    }
+
+    #[test]
     fn test_sign_verify_tampered() {
         let q_order = BigUint::from(19u32);
-        let elliptic_curve = EllipticCurve {
-            a: BigUint::from(2u32),
-            b: BigUint::from(2u32),
-            p: BigUint::from(17u32),
-        };
-        let ecdsa = ECDSA {
-            ec: elliptic_curve,
-            a_gen: Point::Coor(BigUint::from(5u32), BigUint::from(1u32)),
-            q_order: q_order,
-        };
+        let elliptic_curve = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let ecdsa = ECDSA::new(
+            elliptic_curve,
+            Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32)),
+            q_order,
+        )
+        .expect("Cannot build ECDSA");
 
-        let priv_key = ecdsa.generate_priv_key();
+        // a fixed key and an RFC 6979 nonce, so the tampered signature is the same on
+        // every run: with q = 19 a random one could verify by chance
+        let priv_key =
+            PrivateKey::new(BigUint::from(7u32), &ecdsa.q_order).expect("Cannot build priv_key");
         let pub_key = ecdsa.generate_pub_key(&priv_key);
 
-        let hash = ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order);
-        let signature = ecdsa.sign(&priv_key, &hash);
-        println!("Signature: {:?}", signature);
+        let hash =
+            ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, HashReduction::Modulo);
+        let signature = ecdsa
+            .sign_deterministic(&priv_key, &hash)
+            .expect("Could not sign");
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
         let (r, s) = signature;
-        let tampered_signature = &(
-            r,
-            (s + BigUint::from(1u32)).modpow(&BigUint::from(1u32), &ecdsa.q_order),
+        let tampered_signature = (r, (s + BigUint::from(1u32)) % &ecdsa.q_order);
+        assert!(!ecdsa.verify(&hash, &tampered_signature, &pub_key));
+    }
+    #[test]
+    fn test_sign_verify_sec256k1() {
+        let ecdsa = ECDSA::from_hex(
+            "0",
+            "7",
+            "0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+            "0x79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "0x483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+            "0xfffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+        )
+        .expect("Cannot build secp256k1");
+
+        let priv_key =
+            parse_hex("fffffffffffffffffffffffffffffff0baaedce6af48a03cbfd25e8cd0364141")
+                .expect("Cannot parse priv_key");
+        let priv_key = PrivateKey::new(priv_key, &ecdsa.q_order).expect("Cannot build priv_key");
+        let pub_key = ecdsa.generate_pub_key(&priv_key);
+
+        let hash =
+            ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, HashReduction::Modulo);
+        let signature = ecdsa.sign(&priv_key, &hash).expect("Could not sign");
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
+    }
+    #[test]
+    fn test_from_hex_malformed() {
+        let p = "fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f";
+        let gx = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let gy = "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+        let n = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+        assert!(ECDSA::from_hex("0", "7", p, gx, gy, n).is_ok());
+
+        assert!(matches!(
+            ECDSA::from_hex("0", "7", p, "0xg", gy, n),
+            Err(EccError::InvalidEncoding(_))
+        ));
+        assert!(matches!(
+            ECDSA::from_hex("0", "7", p, gx, gy, ""),
+            Err(EccError::InvalidEncoding(_))
+        ));
+        // well-formed hex, but (gx, gy + 1) is not on the curve
+        let gy_plus_one = "483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b9";
+        assert!(matches!(
+            ECDSA::from_hex("0", "7", p, gx, gy_plus_one, n),
+            Err(EccError::InvalidPoint)
+        ));
+        assert!(matches!(
+            ECDSA::from_hex("0", "7", p, gx, gy, "5"),
+            Err(EccError::InvalidOrder)
+        ));
+    }
+    #[test]
+    fn test_verify_each_sec256k1() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+
+        // plain signatures, without a recovery id
+        let messages = ["one", "two", "three", "four", "five"];
+        let mut hashes = Vec::new();
+        let mut signatures = Vec::new();
+        let mut pub_keys = Vec::new();
+        for message in messages {
+            let (priv_key, pub_key) = ecdsa.generate_key_pair();
+            let hash =
+                ecdsa.generate_hash_less_than(message, &ecdsa.q_order, HashReduction::Modulo);
+            signatures.push(ecdsa.sign(&priv_key, &hash).expect("Could not sign"));
+            hashes.push(hash);
+            pub_keys.push(pub_key);
+        }
+
+        let entries: Vec<BatchEntry> = (0..messages.len())
+            .map(|i| (&hashes[i], &signatures[i], &pub_keys[i]))
+            .collect();
+        assert!(ecdsa.verify_each(&entries));
+
+        // tamper with the third signature only
+        let (r, s) = signatures[2].clone();
+        let tampered = (r, (s + BigUint::from(1u32)) % &ecdsa.q_order);
+        let mut bad = entries.clone();
+        bad[2] = (&hashes[2], &tampered, &pub_keys[2]);
+        assert!(!ecdsa.verify_each(&bad));
+    }
+    #[test]
+    fn test_verify_batch_recoverable_sec256k1() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+
+        let messages = ["one", "two", "three", "four", "five"];
+        let mut hashes = Vec::new();
+        let mut signatures = Vec::new();
+        let mut pub_keys = Vec::new();
+        for message in messages {
+            let (priv_key, pub_key) = ecdsa.generate_key_pair();
+            let hash =
+                ecdsa.generate_hash_less_than(message, &ecdsa.q_order, HashReduction::Modulo);
+            let signature = ecdsa
+                .sign_recoverable(&priv_key, &hash)
+                .expect("Could not sign");
+            // the recovery id is the one recover_pub_key needs
+            assert_eq!(
+                ecdsa.recover_pub_key(&hash, &signature.0, signature.1),
+                Ok(pub_key.clone())
+            );
+            signatures.push(signature);
+            hashes.push(hash);
+            pub_keys.push(pub_key);
+        }
+
+        let entries: Vec<RecoverableBatchEntry> = (0..messages.len())
+            .map(|i| (&hashes[i], &signatures[i].0, signatures[i].1, &pub_keys[i]))
+            .collect();
+        assert!(ecdsa.verify_batch_recoverable(&entries));
+
+        // tamper with the third signature only
+        let (r, s) = signatures[2].0.clone();
+        let tampered = (r, (s + BigUint::from(1u32)) % &ecdsa.q_order);
+        let mut bad = entries.clone();
+        bad[2] = (&hashes[2], &tampered, signatures[2].1, &pub_keys[2]);
+        assert!(!ecdsa.verify_batch_recoverable(&bad));
+
+        // a valid signature with the wrong recovery id
+        let mut bad = entries.clone();
+        bad[4].2 ^= 1;
+        assert!(!ecdsa.verify_batch_recoverable(&bad));
+    }
+    #[test]
+    fn test_verify_batch_mixed() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        // y^2 = x^3 + 2x + 2 mod 17, order 19
+        let ecdsa = ECDSA::new(
+            EllipticCurve::new(
+                BigUint::from(2u32),
+                BigUint::from(2u32),
+                BigUint::from(17u32),
+            )
+            .expect("Cannot build curve"),
+            Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32)),
+            BigUint::from(19u32),
+        )
+        .expect("Cannot build ECDSA");
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let mut hashes = Vec::new();
+        let mut signatures = Vec::new();
+        let mut pub_keys = Vec::new();
+        for i in 0..8u32 {
+            let priv_key =
+                PrivateKey::new(BigUint::from(i + 2), &ecdsa.q_order).expect("d is in [1, q)");
+            let hash = BigUint::from((3 * i + 1) % 19);
+            let k = BigUint::from(i + 5);
+            let Some(signature) = ecdsa
+                .sign_with_k_recoverable(priv_key.scalar(), &hash, &k)
+                .expect("Could not sign")
+            else {
+                continue;
+            };
+            pub_keys.push(ecdsa.generate_pub_key(&priv_key));
+            hashes.push(hash);
+            signatures.push(signature);
+        }
+        let entries: Vec<RecoverableBatchEntry> = (0..hashes.len())
+            .map(|i| (&hashes[i], &signatures[i].0, signatures[i].1, &pub_keys[i]))
+            .collect();
+        assert!(entries.len() >= 4);
+        for (hash, signature, _, pub_key) in &entries {
+            assert!(ecdsa.verify(hash, signature, pub_key));
+        }
+        for _ in 0..20 {
+            assert!(ecdsa.verify_batch_recoverable_with_rng(&entries, &mut rng));
+        }
+
+        // one valid signature checked against the next entry's hash, the rest valid:
+        // any zi in [1, q) keeps the bad term away from I
+        let wrong_hash = (entries[1].0 + BigUint::from(1u32)) % &ecdsa.q_order;
+        let mut mixed = entries.clone();
+        mixed[1].0 = &wrong_hash;
+        assert!(!ecdsa.verify(mixed[1].0, mixed[1].1, mixed[1].3));
+        for _ in 0..20 {
+            assert!(!ecdsa.verify_batch_recoverable_with_rng(&mixed, &mut rng));
+        }
+        // a key swapped between two entries
+        let mut mixed = entries.clone();
+        mixed.swap(0, 2);
+        mixed[0].3 = entries[0].3;
+        mixed[2].3 = entries[2].3;
+        assert!(!ecdsa.verify_batch_recoverable_with_rng(&mixed, &mut rng));
+
+        // p < q, so bit 1 of a recovery id asks for x(R) = r + 19 >= 17: that R cannot
+        // be recovered and the entry is checked with verify
+        let mut unrecoverable = entries.clone();
+        unrecoverable[0].2 |= 2;
+        assert!(ecdsa.verify_batch_recoverable_with_rng(&unrecoverable, &mut rng));
+        let other_hash = (entries[0].0 + BigUint::from(1u32)) % &ecdsa.q_order;
+        unrecoverable[0].0 = &other_hash;
+        assert!(!ecdsa.verify_batch_recoverable_with_rng(&unrecoverable, &mut rng));
+    }
+    #[test]
+    fn test_sign_with_rng() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let priv_key = PrivateKey::new(BigUint::from(0xc0ffeeu32), &ecdsa.q_order)
+            .expect("Cannot build priv_key");
+        let pub_key = ecdsa.generate_pub_key(&priv_key);
+        let hash = ecdsa.generate_hash_less_than("no_std", &ecdsa.q_order, HashReduction::Modulo);
+
+        let signature = ecdsa
+            .sign_with_rng(&priv_key, &hash, &mut StdRng::seed_from_u64(7))
+            .expect("Could not sign");
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
+
+        // the same seed draws the same k
+        let again = ecdsa
+            .sign_with_rng(&priv_key, &hash, &mut StdRng::seed_from_u64(7))
+            .expect("Could not sign");
+        assert_eq!(signature, again);
+
+        let q = ecdsa.q_order.clone();
+        assert_eq!(
+            ecdsa.sign_with_rng(&priv_key, &q, &mut StdRng::seed_from_u64(7)),
+            Err(EccError::InvalidScalar)
         );
+    }
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_sign_zeroize() {
+        // keys and nonces are wiped on drop, signing and verifying work as before
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let (priv_key, pub_key) = ecdsa.generate_key_pair();
+        let hash = ecdsa.hash_message::<Sha256>(b"wipe me", &ecdsa.q_order);
+        let signature = ecdsa.sign(&priv_key, &hash).expect("Could not sign");
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
+        let deterministic = ecdsa
+            .sign_deterministic(&priv_key, &hash)
+            .expect("Could not sign");
+        assert!(ecdsa.verify(&hash, &deterministic, &pub_key));
 
-        println!(
-            "Verify {}",
-            ecdsa.verify(&hash, &tampered_signature, &pub_key)
+        let k: SecretScalar = ecdsa.deterministic_k(&priv_key, &hash);
+        let copy = k.clone();
+        drop(k);
+        assert!(copy.scalar() < &ecdsa.q_order);
+    }
+    #[test]
+    fn test_verify_each_empty() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        assert!(ecdsa.verify_each(&[]));
+    }
+    #[test]
+    fn test_sign_with_key_bytes_sec256k1() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+
+        let priv_bytes: [u8; 32] = [
+            0xc9, 0xaf, 0xa9, 0xd8, 0x45, 0xba, 0x75, 0x16, 0x6b, 0x5c, 0x21, 0x57, 0x67, 0xb1,
+            0xd6, 0x93, 0x4e, 0x50, 0xc3, 0xdb, 0x36, 0xe8, 0x9b, 0x12, 0x7b, 0x8a, 0x62, 0x2b,
+            0x12, 0x0f, 0x67, 0x21,
+        ];
+        let signature = ecdsa
+            .sign_with_key_bytes(&priv_bytes, b"Hello World!")
+            .expect("Could not sign");
+
+        let priv_key = PrivateKey::new(BigUint::from_bytes_be(&priv_bytes), &ecdsa.q_order)
+            .expect("Cannot build priv_key");
+        let pub_key = ecdsa.generate_pub_key(&priv_key);
+        let hash =
+            ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, HashReduction::Modulo);
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
+
+        // zero and order are not valid private keys
+        let zero = [0u8; 32];
+        assert_eq!(
+            ecdsa.sign_with_key_bytes(&zero, b"Hello World!"),
+            Err(EccError::InvalidScalar)
+        );
+        let order = ecdsa.q_order.to_bytes_be();
+        assert_eq!(
+            ecdsa.sign_with_key_bytes(&order, b"Hello World!"),
+            Err(EccError::InvalidScalar)
         );
-        assert!(ecdsa.verify(&hash, &tampered_signature, &pub_key));
     }
     #[test]
-    fn test_sign_verify_sec256k1() {
-        /*
-                Name	Value
-                p	0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f
-                a	0x0000000000000000000000000000000000000000000000000000000000000000
-                b	0x0000000000000000000000000000000000000000000000000000000000000007
-                G	(79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798, 483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8)
-                n	0xfffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141
-        */
-        let p = BigUint::parse_bytes(
-            b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+    fn test_signature_der_openssl() {
+        // openssl dgst -sha256 -sign k1.pem over "Hello World!" with a secp256k1 key
+        let der = hex::decode(
+            "3046022100968f650298e2ec1116ec0974904ff7114d1f71b2e8337f1a995167b8c4b99dc8\
+             022100fb5ed3178a034893a6bd3f7bc7f16cb1fe2b216d079a9ead38be6f2c07b4a232",
+        )
+        .expect("Cannot decode der");
+        let r = BigUint::parse_bytes(
+            b"968f650298e2ec1116ec0974904ff7114d1f71b2e8337f1a995167b8c4b99dc8",
             16,
         )
-        .expect("Cannot parse p");
-        let a = BigUint::from(0u32);
-        let b = BigUint::from(7u32);
-        let Gx = BigUint::parse_bytes(
-            b"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        .expect("Cannot parse r");
+        let s = BigUint::parse_bytes(
+            b"fb5ed3178a034893a6bd3f7bc7f16cb1fe2b216d079a9ead38be6f2c07b4a232",
             16,
         )
-        .expect("Cannot parse Gx");
+        .expect("Cannot parse s");
+
+        let signature = Signature((r, s));
+        assert_eq!(signature.to_der(), der);
+        assert_eq!(Signature::from_der(&der), Ok(signature.clone()));
+
+        // and it is a valid signature for the openssl key
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let pub_key = Point::Coordinate(
+            BigUint::parse_bytes(
+                b"da33724e52389369536570da63365d8752a7c931d7498ea43af105e27e65c3fc",
+                16,
+            )
+            .expect("Cannot parse x"),
+            BigUint::parse_bytes(
+                b"01364c7c6b4d7185ff89a8cc37fb968482ad0ce4db4283c4337d25d2678015a7",
+                16,
+            )
+            .expect("Cannot parse y"),
+        );
+        let pub_key = PublicKey::new(pub_key, &ecdsa.ec).expect("Cannot build pub_key");
+        let hash =
+            ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, HashReduction::Modulo);
+        assert!(ecdsa.verify(&hash, &signature.0, &pub_key));
+        // openssl does not normalize s, this one happens to be high
+        assert!(!ecdsa.verify_strict(&hash, &signature.0, &pub_key));
+    }
+    #[test]
+    fn test_signature_der_round_trip() {
+        // small values exercise the short INTEGER and padding paths
+        let signature = Signature((BigUint::from(1u32), BigUint::from(0x80u32)));
+        assert_eq!(
+            signature.to_der(),
+            vec![0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x02, 0x00, 0x80]
+        );
+
+        let mut rng = rand::thread_rng();
+        let n = EllipticCurve::secp256k1_order();
+        for _ in 0..100 {
+            let r = rng.gen_biguint_range(&BigUint::from(1u32), &n);
+            let s = rng.gen_biguint_range(&BigUint::from(1u32), &n);
+            let signature = Signature((r, s));
+            let der = signature.to_der();
+            assert_eq!(Signature::from_der(&der), Ok(signature));
+
+            // truncating or extending the encoding must fail cleanly
+            for len in 0..der.len() {
+                assert!(Signature::from_der(&der[..len]).is_err());
+            }
+            let mut extended = der.clone();
+            extended.push(0);
+            assert!(Signature::from_der(&extended).is_err());
+        }
+
+        // random garbage never panics
+        for _ in 0..1000 {
+            let len = rng.gen_range(0..80);
+            let bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            let _ = Signature::from_der(&bytes);
+        }
+    }
+    #[test]
+    fn test_signature_der_tuples() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let (priv_key, pub_key) = ecdsa.generate_key_pair();
+        for msg in [b"one".as_slice(), b"two", b"three"] {
+            let signature = ecdsa.sign_bytes(&priv_key, msg).expect("Could not sign");
+            let der = signature_to_der(&signature);
+            assert_eq!(der, Signature(signature.clone()).to_der());
+            let decoded = signature_from_der(&der).expect("Cannot decode der");
+            assert_eq!(decoded, signature);
+            assert!(ecdsa.verify_bytes(msg, &decoded, &pub_key));
+        }
+
+        // openssl dgst -sha256 -sign k1.pem over "Hello World!", see test_signature_der_openssl
+        let der = hex::decode(
+            "3046022100968f650298e2ec1116ec0974904ff7114d1f71b2e8337f1a995167b8c4b99dc8\
+             022100fb5ed3178a034893a6bd3f7bc7f16cb1fe2b216d079a9ead38be6f2c07b4a232",
+        )
+        .expect("Cannot decode der");
+        let (r, _) = signature_from_der(&der).expect("Cannot decode der");
+        assert_eq!(
+            r.to_str_radix(16),
+            "968f650298e2ec1116ec0974904ff7114d1f71b2e8337f1a995167b8c4b99dc8"
+        );
+        let mut extended = der.clone();
+        extended.push(0);
+        assert!(signature_from_der(&extended).is_err());
+    }
+    #[test]
+    fn test_signature_der_malformed() {
+        // negative r
+        let der = [0x30, 0x06, 0x02, 0x01, 0x80, 0x02, 0x01, 0x01];
+        assert!(Signature::from_der(&der).is_err());
+        // r with a superfluous leading zero
+        let der = [0x30, 0x07, 0x02, 0x02, 0x00, 0x01, 0x02, 0x01, 0x01];
+        assert!(Signature::from_der(&der).is_err());
+        // wrong outer tag
+        let der = [0x31, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01];
+        assert!(Signature::from_der(&der).is_err());
+        // long form for a short length
+        let der = [0x30, 0x81, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01];
+        assert!(Signature::from_der(&der).is_err());
+    }
+    #[test]
+    fn test_find_signer() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+
+        let keys: Vec<(PrivateKey, PublicKey)> =
+            (0..3).map(|_| ecdsa.generate_key_pair()).collect();
+        let candidates: Vec<PublicKey> = keys.iter().map(|(_, pub_key)| pub_key.clone()).collect();
+
+        let signer = &keys[1].0.scalar().to_bytes_be();
+        let items: Vec<(Vec<u8>, (BigUint, BigUint))> = [b"first".to_vec(), b"second".to_vec()]
+            .into_iter()
+            .map(|msg| {
+                let signature = ecdsa
+                    .sign_with_key_bytes(signer, &msg)
+                    .expect("Could not sign");
+                (msg, signature)
+            })
+            .collect();
+        assert_eq!(ecdsa.find_signer(&items, &candidates), Some(1));
+
+        // nobody signed both when one signature comes from another key
+        let mut mixed = items.clone();
+        let other = ecdsa
+            .sign_with_key_bytes(&keys[2].0.scalar().to_bytes_be(), b"second")
+            .expect("Could not sign");
+        mixed[1] = (b"second".to_vec(), other);
+        assert_eq!(ecdsa.find_signer(&mixed, &candidates), None);
 
-        let Gy = BigUint::parse_bytes(
-            b"483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+        assert_eq!(ecdsa.find_signer(&items, &candidates[2..]), None);
+        assert_eq!(ecdsa.find_signer(&[], &candidates), None);
+    }
+
+    #[test]
+    fn test_sign_deterministic_p256() {
+        // RFC 6979 A.2.5, P-256 with SHA-256, message "sample"
+        let ecdsa = ECDSA::new(
+            EllipticCurve::p256(),
+            EllipticCurve::p256_generator(),
+            EllipticCurve::p256_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let priv_key = BigUint::parse_bytes(
+            b"C9AFA9D845BA75166B5C215767B1D6934E50C3DB36E89B127B8A622B120F6721",
             16,
         )
-        .expect("Cannot parse Gy");
+        .expect("Cannot parse priv_key");
+        let priv_key = PrivateKey::new(priv_key, &ecdsa.q_order).expect("Cannot build priv_key");
+        let hash =
+            BigUint::parse_bytes(digest("sample").as_bytes(), 16).expect("Cannot parse hash");
 
-        let n = BigUint::parse_bytes(
-            b"fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+        let k = ecdsa.deterministic_k(&priv_key, &hash);
+        let expected_k = BigUint::parse_bytes(
+            b"A6E3C57DD01ABE90086538398355DD4C3B17AA873382B0F24D6129493D8AAD60",
             16,
         )
-        .expect("Cannot parse n");
+        .expect("Cannot parse k");
+        assert_eq!(k.scalar(), &expected_k);
 
-        let G = Point::Coor(Gx, Gy);
+        let (r, s) = ecdsa
+            .sign_deterministic(&priv_key, &hash)
+            .expect("Could not sign");
+        let expected_r = BigUint::parse_bytes(
+            b"EFD48B2AACB6A8FD1140DD9CD45E81D69D2C877B56AAF991C34D0EA84EAF3716",
+            16,
+        )
+        .expect("Cannot parse r");
+        // the RFC lists s = F7CB1C94...843ACDA8, sign returns its low-S twin q - s
+        let expected_s = BigUint::parse_bytes(
+            b"0834E36AD29A83BF2BC9385E491D6099C8FDF9D1ED67AA7EA5F51F93782857A9",
+            16,
+        )
+        .expect("Cannot parse s");
+        assert_eq!(r, expected_r);
+        assert_eq!(s, expected_s);
 
-        // sign & verify
-        let elliptic_curve = EllipticCurve { a: a, b: b, p: p };
-        let ecdsa = ECDSA {
-            ec: elliptic_curve,
-            a_gen: G,
-            q_order: n,
-        };
+        // U = xG as listed in A.2.5, a = -3 goes through every doubling
+        let pub_key = ecdsa.generate_pub_key(&priv_key);
+        let expected_pub = Point::from_hex(
+            "60FED4BA255A9D31C961EB74C6356D68C049B8923B61FA6CE669622E60F29FB6",
+            "7903FE1008B8BC99A41AE9E95628BC64F2F1B20C2D7E9F5177A3C294D4462299",
+        )
+        .expect("Cannot parse U");
+        assert_eq!(pub_key.point(), &expected_pub);
+        assert!(ecdsa.verify(&hash, &(r.clone(), s.clone()), &pub_key));
+        // and so does the high-S form the RFC lists
+        let high_s = &ecdsa.q_order - &s;
+        assert!(ecdsa.verify(&hash, &(r.clone(), high_s), &pub_key));
+        assert!(!ecdsa.verify(&(&hash + 1u32), &(r, s), &pub_key));
 
-        let priv_key = BigUint::parse_bytes(
-            b"fffffffffffffffffffffffffffffff0baaedce6af48a03cbfd25e8cd0364141",
+        // message "test": k = D16B6AE8...EE0008E0, and s is already low
+        let hash = BigUint::parse_bytes(digest("test").as_bytes(), 16).expect("Cannot parse hash");
+        let signature = ecdsa
+            .sign_deterministic(&priv_key, &hash)
+            .expect("Could not sign");
+        let expected = (
+            parse_hex("F1ABB023518351CD71D881567B1EA663ED3EFCF6C5132B354F28D3B0B7D38367")
+                .expect("Cannot parse r"),
+            parse_hex("019F4113742A2B14BD25926B49C649155F267E60D3814B4C0CC84250E46F0083")
+                .expect("Cannot parse s"),
+        );
+        assert_eq!(signature, expected);
+        assert!(ecdsa.verify_bytes(b"test", &signature, &pub_key));
+        assert!(!ecdsa.verify_bytes(b"sample", &signature, &pub_key));
+    }
+
+    #[test]
+    fn test_sign_deterministic_repeatable() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let (priv_key, pub_key) = ecdsa.generate_key_pair();
+        let hash =
+            ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, HashReduction::Modulo);
+
+        let signature = ecdsa
+            .sign_deterministic(&priv_key, &hash)
+            .expect("Could not sign");
+        assert_eq!(
+            signature,
+            ecdsa
+                .sign_deterministic(&priv_key, &hash)
+                .expect("Could not sign")
+        );
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
+
+        let other_hash =
+            ecdsa.generate_hash_less_than("Hello World?", &ecdsa.q_order, HashReduction::Modulo);
+        assert_ne!(
+            signature,
+            ecdsa
+                .sign_deterministic(&priv_key, &other_hash)
+                .expect("Could not sign")
+        );
+    }
+
+    #[test]
+    fn test_sign_priv_key_out_of_range() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let hash =
+            ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, HashReduction::Modulo);
+        // q is a valid key for a larger order, but not for this curve
+        let priv_key = PrivateKey::new(ecdsa.q_order.clone(), &(&ecdsa.q_order + 1u32))
+            .expect("Cannot build priv_key");
+        assert_eq!(ecdsa.sign(&priv_key, &hash), Err(EccError::InvalidScalar));
+        assert_eq!(
+            ecdsa.sign_deterministic(&priv_key, &hash),
+            Err(EccError::InvalidScalar)
+        );
+
+        // and a hash that is not reduced is rejected the same way
+        let (priv_key, pub_key) = ecdsa.generate_key_pair();
+        let q = ecdsa.q_order.clone();
+        assert_eq!(ecdsa.sign(&priv_key, &q), Err(EccError::InvalidScalar));
+        let signature = ecdsa.sign(&priv_key, &hash).expect("Could not sign");
+        assert!(!ecdsa.verify(&(&hash + &q), &signature, &pub_key));
+    }
+
+    #[test]
+    fn test_normalize_s() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let (priv_key, pub_key) = ecdsa.generate_key_pair();
+        let hash =
+            ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, HashReduction::Modulo);
+
+        let (r, s) = ecdsa.sign(&priv_key, &hash).expect("Could not sign");
+        assert_eq!(ecdsa.normalize_s(&s), s);
+        let high_s = &ecdsa.q_order - &s;
+        assert!(high_s > s);
+        assert_eq!(ecdsa.normalize_s(&high_s), s);
+
+        let low = (r.clone(), s);
+        let high = (r, high_s);
+        assert!(ecdsa.verify(&hash, &low, &pub_key));
+        assert!(ecdsa.verify(&hash, &high, &pub_key));
+        assert!(ecdsa.verify_strict(&hash, &low, &pub_key));
+        assert!(!ecdsa.verify_strict(&hash, &high, &pub_key));
+
+        // already low: no-op, high: flipped, and normalizing twice changes nothing more
+        let q = &ecdsa.q_order;
+        assert_eq!(normalize_signature(&low, q), low);
+        assert_eq!(normalize_signature(&high, q), low);
+        assert_eq!(normalize_signature(&normalize_signature(&high, q), q), low);
+        // s = (q - 1) / 2 is the largest low s, one more is high
+        let half: BigUint = q >> 1;
+        let at_half = (BigUint::from(1u32), half.clone());
+        assert_eq!(normalize_signature(&at_half, q), at_half);
+        let above_half = (BigUint::from(1u32), &half + BigUint::from(1u32));
+        assert_eq!(normalize_signature(&above_half, q), at_half);
+        // s >= q is reduced first rather than underflowing q - s
+        let (r, s) = low.clone();
+        assert_eq!(normalize_signature(&(r.clone(), &s + q), q), low);
+        assert_eq!(normalize_signature(&(r.clone(), q - &s + q), q), low);
+        assert_eq!(
+            normalize_signature(&(r.clone(), q.clone()), q),
+            (r, BigUint::from(0u32))
+        );
+        assert_eq!(ecdsa.normalize_s(&(&s + q + q)), s);
+
+        let signature = ecdsa
+            .sign_normalized(&priv_key, &hash)
+            .expect("Could not sign");
+        assert!(signature.1 <= half);
+        assert!(ecdsa.verify_strict(&hash, &signature, &pub_key));
+    }
+
+    #[test]
+    fn test_verify_rejects_out_of_range() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let (priv_key, pub_key) = ecdsa.generate_key_pair();
+        let hash =
+            ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, HashReduction::Modulo);
+        let (r, s) = ecdsa.sign(&priv_key, &hash).expect("Could not sign");
+        assert!(ecdsa.verify(&hash, &(r.clone(), s.clone()), &pub_key));
+
+        let zero = BigUint::from(0u32);
+        let q = ecdsa.q_order.clone();
+        assert!(!ecdsa.verify(&hash, &(zero.clone(), s.clone()), &pub_key));
+        assert!(!ecdsa.verify(&hash, &(r.clone(), zero.clone()), &pub_key));
+        assert!(!ecdsa.verify(&hash, &(zero.clone(), zero), &pub_key));
+        // r + q and s + q are congruent to r and s but out of range
+        assert!(!ecdsa.verify(&hash, &(&r + &q, s.clone()), &pub_key));
+        assert!(!ecdsa.verify(&hash, &(r.clone(), &s + &q), &pub_key));
+        assert!(!ecdsa.verify(&hash, &(q.clone(), s.clone()), &pub_key));
+        assert!(!ecdsa.verify(&hash, &(r.clone(), q.clone()), &pub_key));
+
+        // verify_strict range checks before it compares s with q - s
+        let one = BigUint::from(1u32);
+        assert!(ecdsa.verify_strict(&hash, &(r.clone(), s.clone()), &pub_key));
+        assert!(!ecdsa.verify_strict(&hash, &(r.clone(), &q + &one), &pub_key));
+        assert!(!ecdsa.verify_strict(&hash, &(r.clone(), &s + &q), &pub_key));
+        assert!(!ecdsa.verify_strict(&hash, &(r.clone(), q.clone()), &pub_key));
+        assert!(!ecdsa.verify_strict(&hash, &(&q + &one, s), &pub_key));
+        assert!(!ecdsa.verify_strict(&hash, &(r, BigUint::from(0u32)), &pub_key));
+    }
+
+    #[test]
+    fn test_verify_rejects_identity() {
+        // r = s = hash makes u1 = u2 = 1, so with P = -A the sum u1 A + u2 P is I
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let pub_key =
+            PublicKey::new(ecdsa.ec.negate(&ecdsa.a_gen), &ecdsa.ec).expect("Cannot build pub_key");
+        let hash = BigUint::from(5u32);
+        assert!(!ecdsa.verify(&hash, &(hash.clone(), hash.clone()), &pub_key));
+    }
+
+    #[test]
+    fn test_hash_to_scalar() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        // a 64-byte digest keeps its first 32 bytes
+        let digest: Vec<u8> = (1..=64).collect();
+        let expected = BigUint::from_bytes_be(&digest[..32]);
+        assert_eq!(ecdsa.hash_to_scalar(&digest), expected);
+
+        // a digest as wide as q is taken as is
+        let digest: Vec<u8> = (1..=32).collect();
+        assert_eq!(ecdsa.hash_to_scalar(&digest), expected);
+
+        // leftmost 256 bits all ones is above q and wraps once
+        let digest = [0xffu8; 64];
+        let ones = BigUint::from_bytes_be(&[0xffu8; 32]);
+        assert_eq!(ecdsa.hash_to_scalar(&digest), &ones - &ecdsa.q_order);
+
+        // 19 is 5 bits wide, 0xb000 keeps 0b10110 = 22 = 3 mod 19
+        let ecdsa = ECDSA::new(
+            EllipticCurve::new(
+                BigUint::from(2u32),
+                BigUint::from(2u32),
+                BigUint::from(17u32),
+            )
+            .expect("Cannot build curve"),
+            Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32)),
+            BigUint::from(19u32),
+        )
+        .expect("Cannot build ECDSA");
+        assert_eq!(ecdsa.hash_to_scalar(&[0xb0, 0x00]), BigUint::from(3u32));
+    }
+
+    #[test]
+    fn test_verify_rejects_off_curve_key() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let (priv_key, pub_key) = ecdsa.generate_key_pair();
+        let hash =
+            ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, HashReduction::Modulo);
+        let signature = ecdsa.sign(&priv_key, &hash).expect("Could not sign");
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
+
+        // (5, 1) is a valid key on y^2 = x^3 + 2x + 2 mod 17 but not on secp256k1
+        let toy = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let fabricated = PublicKey::new(
+            Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32)),
+            &toy,
+        )
+        .expect("Cannot build pub_key");
+        assert!(!fabricated.is_valid_on(&ecdsa.ec));
+        assert!(!ecdsa.verify(&hash, &signature, &fabricated));
+        assert!(!ecdsa.verify_strict(&hash, &signature, &fabricated));
+    }
+
+    #[test]
+    fn test_generate_hash_less_than() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        // sha256("Hello World!")
+        let hash = BigUint::parse_bytes(
+            b"7f83b1657ff1fc53b92dc18148a1d65dfc2d4b1fa3d677284addd200126d9069",
             16,
         )
-        .expect("Cannot parse n");
+        .expect("Cannot parse hash");
+        // a 256-bit order keeps the whole digest either way
+        for reduction in [HashReduction::Modulo, HashReduction::Truncate] {
+            assert_eq!(
+                ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, reduction),
+                hash
+            );
+        }
+
+        // max - 1 is reachable and max itself wraps to 0
+        let max = &hash + BigUint::from(1u32);
+        assert_eq!(
+            ecdsa.generate_hash_less_than("Hello World!", &max, HashReduction::Modulo),
+            hash
+        );
+        assert_eq!(
+            ecdsa.generate_hash_less_than("Hello World!", &hash, HashReduction::Modulo),
+            BigUint::from(0u32)
+        );
+        // the digest only has 255 significant bits, so a 255-bit max drops its last bit
+        assert_eq!(
+            ecdsa.generate_hash_less_than("Hello World!", &max, HashReduction::Truncate),
+            &hash >> 1
+        );
+
+        // 19 is 5 bits wide: the digest mod 19 is 10, its top 5 bits are 0b01111 = 15
+        let max = BigUint::from(19u32);
+        assert_eq!(
+            ecdsa.generate_hash_less_than("Hello World!", &max, HashReduction::Modulo),
+            BigUint::from(10u32)
+        );
+        assert_eq!(
+            ecdsa.generate_hash_less_than("Hello World!", &max, HashReduction::Truncate),
+            BigUint::from(15u32)
+        );
+    }
+
+    #[test]
+    fn test_hash_message_generic() {
+        use sha2::{Sha512, Sha512_256};
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let q = &ecdsa.q_order;
+        let msg = b"Hello World!";
+        let (priv_key, pub_key) = ecdsa.generate_key_pair();
+
+        let h256 = ecdsa.hash_message::<Sha256>(msg, q);
+        let h512_256 = ecdsa.hash_message::<Sha512_256>(msg, q);
+        assert_ne!(h256, h512_256);
+        for hash in [&h256, &h512_256] {
+            let signature = ecdsa.sign(&priv_key, hash).expect("Could not sign");
+            assert!(ecdsa.verify(hash, &signature, &pub_key));
+        }
+        let signature = ecdsa.sign(&priv_key, &h256).expect("Could not sign");
+        assert!(!ecdsa.verify(&h512_256, &signature, &pub_key));
+
+        // the same leftmost-bits reduction whatever the width of the digest
+        assert_eq!(h256, ecdsa.hash_to_scalar(&Sha256::digest(msg)));
+        assert_eq!(
+            ecdsa.hash_message::<Sha512>(msg, q),
+            ecdsa.hash_to_scalar(&Sha512::digest(msg))
+        );
+        assert_eq!(
+            ecdsa.hash_bytes_less_than_with::<Sha256>(msg, q, HashReduction::Modulo),
+            ecdsa.hash_bytes_less_than(msg, q, HashReduction::Modulo)
+        );
+    }
+
+    #[test]
+    fn test_hash_bytes_less_than() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        assert_eq!(
+            ecdsa.hash_bytes_less_than(b"Hello World!", &ecdsa.q_order, HashReduction::Modulo),
+            ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, HashReduction::Modulo)
+        );
+
+        // not valid UTF-8
+        let message = [0xff, 0xfe, 0x00, 0xc3, 0x28, 0x80];
+        let (priv_key, pub_key) = ecdsa.generate_key_pair();
+        let hash = ecdsa.hash_bytes_less_than(&message, &ecdsa.q_order, HashReduction::Modulo);
+        let signature = ecdsa.sign(&priv_key, &hash).expect("Could not sign");
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
+
+        // the lossy UTF-8 round trip hashes to something else
+        let lossy = String::from_utf8_lossy(&message);
+        let lossy_hash =
+            ecdsa.generate_hash_less_than(&lossy, &ecdsa.q_order, HashReduction::Modulo);
+        assert_ne!(lossy_hash, hash);
+        assert!(!ecdsa.verify(&lossy_hash, &signature, &pub_key));
+    }
+
+    #[test]
+    fn test_sign_verify_r_mod_q() {
+        // y^2 = x^3 + 7 mod 43 has 31 points, so x(R) can be above the order
+        let ecdsa = ECDSA::new(
+            EllipticCurve::new(
+                BigUint::from(0u32),
+                BigUint::from(7u32),
+                BigUint::from(43u32),
+            )
+            .expect("Cannot build curve"),
+            Point::Coordinate(BigUint::from(2u32), BigUint::from(12u32)),
+            BigUint::from(31u32),
+        )
+        .expect("Cannot build ECDSA");
+        let priv_key =
+            PrivateKey::new(BigUint::from(7u32), &ecdsa.q_order).expect("Cannot build priv_key");
         let pub_key = ecdsa.generate_pub_key(&priv_key);
+        let hash = BigUint::from(10u32);
+
+        // 3A = (35, 21), r is 35 mod 31 = 4
+        let (r, s) = ecdsa
+            .sign_with_k(priv_key.scalar(), &hash, &BigUint::from(3u32))
+            .expect("Could not sign")
+            .expect("k gives a valid signature");
+        assert_eq!(r, BigUint::from(4u32));
+        assert!(ecdsa.verify(&hash, &(r, s.clone()), &pub_key));
+        // the same signature with the raw x is rejected
+        assert!(!ecdsa.verify(&hash, &(BigUint::from(35u32), s), &pub_key));
+
+        for _ in 0..50 {
+            let (priv_key, pub_key) = ecdsa.generate_key_pair();
+            let hash = rand::thread_rng().gen_biguint_range(&BigUint::from(0u32), &ecdsa.q_order);
+            let signature = ecdsa.sign(&priv_key, &hash).expect("Could not sign");
+            assert!(signature.0 < ecdsa.q_order);
+            assert!(ecdsa.verify(&hash, &signature, &pub_key));
+        }
+    }
+
+    #[test]
+    fn test_verify_clear_cofactor() {
+        // cofactor 1 clears nothing, so the flag does not change the outcome
+        let mut ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let (priv_key, pub_key) = ecdsa.generate_key_pair();
+        let hash =
+            ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order, HashReduction::Modulo);
+        let signature = ecdsa.sign(&priv_key, &hash).expect("Could not sign");
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
+        ecdsa.clear_cofactor = true;
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
+
+        // y^2 = x^3 + 1 mod 5 has 6 points, n = 3 and h = 2; (4, 0) has order 2
+        let mut ecdsa = ECDSA::new(
+            EllipticCurve::with_cofactor(
+                BigUint::from(0u32),
+                BigUint::from(1u32),
+                BigUint::from(5u32),
+                BigUint::from(2u32),
+            )
+            .expect("Cannot build curve"),
+            Point::Coordinate(BigUint::from(0u32), BigUint::from(4u32)),
+            BigUint::from(3u32),
+        )
+        .expect("Cannot build ECDSA")
+        .with_clear_cofactor(true);
+        let small = PublicKey::new(
+            Point::Coordinate(BigUint::from(4u32), BigUint::from(0u32)),
+            &ecdsa.ec,
+        )
+        .expect("Cannot build pub_key");
+        // (2, 2) for hash 1 passes the verification equation with the order-2 key,
+        // the subgroup check rejects the key with or without the flag
+        let hash = BigUint::from(1u32);
+        let forged = (BigUint::from(2u32), BigUint::from(2u32));
+        ecdsa.clear_cofactor = false;
+        assert!(!ecdsa.verify(&hash, &forged, &small));
+        ecdsa.clear_cofactor = true;
+        assert!(!ecdsa.verify(&hash, &forged, &small));
+    }
+
+    #[test]
+    fn test_sign_verify_bytes() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        let (priv_key, pub_key) = ecdsa.generate_key_pair();
+        let signature = ecdsa
+            .sign_bytes(&priv_key, b"hello")
+            .expect("Could not sign");
+        assert!(ecdsa.verify_bytes(b"hello", &signature, &pub_key));
+        assert!(!ecdsa.verify_bytes(b"world", &signature, &pub_key));
+
+        // same as hashing by hand
+        let hash = ecdsa.hash_bytes_less_than(b"hello", &ecdsa.q_order, HashReduction::Modulo);
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
+    }
+
+    #[test]
+    fn test_sign_generic() {
+        use crate::edwards::TwistedEdwardsCurve;
 
-        let hash = ecdsa.generate_hash_less_than("Hello World!", &ecdsa.q_order);
-        let signature = ecdsa.sign(&priv_key, &hash);
-        println!("Signature: {:?}", signature);
+        fn check<C: Curve<Point = Point>>(curve: &C, g: &Point, n: &BigUint) -> (BigUint, BigUint) {
+            let priv_key = PrivateKey::new(BigUint::from(0x1234_5678u32), n).expect("d < n");
+            let pub_key = curve
+                .scalar_mul(g, priv_key.scalar())
+                .expect("G is on the curve");
+            let hash = BigUint::from(0xc0ffeeu32);
+            let signature = sign_generic(curve, g, n, &priv_key, &hash).expect("Could not sign");
+            assert_eq!(
+                sign_generic(curve, g, n, &priv_key, &hash),
+                Ok(signature.clone())
+            );
+            assert!(verify_generic(curve, g, n, &hash, &signature, &pub_key));
+            assert!(!verify_generic(
+                curve,
+                g,
+                n,
+                &(&hash + 1u32),
+                &signature,
+                &pub_key
+            ));
+            assert!(!verify_generic(curve, g, n, &hash, &signature, g));
+            assert!(!verify_generic(
+                curve,
+                g,
+                n,
+                &hash,
+                &signature,
+                &curve.identity()
+            ));
+            let (r, s) = &signature;
+            assert!(!verify_generic(curve, g, n, &hash, &(r.clone(), n - s), g));
+            assert_eq!(
+                sign_generic(curve, g, n, &priv_key, n),
+                Err(EccError::InvalidScalar)
+            );
+            signature
+        }
+
+        // over secp256k1 this is ECDSA::sign_deterministic
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+        let signature = check(&EllipticCurve::secp256k1(), &g, &n);
+        let ecdsa =
+            ECDSA::new(EllipticCurve::secp256k1(), g, n.clone()).expect("Cannot build ECDSA");
+        let priv_key = PrivateKey::new(BigUint::from(0x1234_5678u32), &n).expect("d < n");
+        assert_eq!(
+            ecdsa.sign_deterministic(&priv_key, &BigUint::from(0xc0ffeeu32)),
+            Ok(signature)
+        );
 
-        println!("Verify {}", ecdsa.verify(&hash, &signature, &pub_key));
+        // and the same code signs over Ed25519, a twisted Edwards curve
+        check(
+            &TwistedEdwardsCurve::ed25519(),
+            &TwistedEdwardsCurve::ed25519_generator(),
+            &TwistedEdwardsCurve::ed25519_order(),
+        );
+    }
+
+    // every message has the same digest
+    struct ConstantHasher;
+
+    impl Hasher for ConstantHasher {
+        fn digest(&self, _msg: &[u8]) -> Vec<u8> {
+            vec![0x2a; 20]
+        }
+    }
+
+    #[test]
+    fn test_with_hasher() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA")
+        .with_hasher(Box::new(ConstantHasher));
+        let (priv_key, pub_key) = ecdsa.generate_key_pair();
+        let signature = ecdsa
+            .sign_bytes_deterministic(&priv_key, b"hello")
+            .expect("Could not sign");
+        assert_eq!(
+            ecdsa.sign_bytes_deterministic(&priv_key, b"hello"),
+            Ok(signature.clone())
+        );
+        // the message never reaches the signature
+        assert_eq!(
+            ecdsa.sign_bytes_deterministic(&priv_key, b"world"),
+            Ok(signature.clone())
+        );
+        assert!(ecdsa.verify_bytes(b"hello", &signature, &pub_key));
+        assert!(ecdsa.verify_bytes(b"world", &signature, &pub_key));
+        let hash = BigUint::from_bytes_be(&[0x2a; 20]);
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
+
+        let ecdsa = ecdsa.with_hasher(Box::new(Sha512Hasher));
+        assert!(!ecdsa.verify_bytes(b"hello", &signature, &pub_key));
+        let signature = ecdsa
+            .sign_bytes(&priv_key, b"hello")
+            .expect("Could not sign");
+        assert!(ecdsa.verify_bytes(b"hello", &signature, &pub_key));
+        assert!(!ecdsa.verify_bytes(b"world", &signature, &pub_key));
+        // the 512-bit digest is cut to its leftmost 256 bits as FIPS 186 does, so that
+        // other ECDSA-SHA512 implementations verify the signature, not reduced mod q
+        let hash = ecdsa.hash_message::<Sha512>(b"hello", &ecdsa.q_order);
+        assert!(ecdsa.verify(&hash, &signature, &pub_key));
+        let modulo = BigUint::from_bytes_be(&Sha512::digest(b"hello")) % &ecdsa.q_order;
+        assert_ne!(modulo, hash);
+        assert!(!ecdsa.verify(&modulo, &signature, &pub_key));
+
+        // sign_with_key_bytes and find_signer hash with the same hasher
+        let priv_bytes = int2octets(priv_key.scalar(), 32);
+        let signature = ecdsa
+            .sign_with_key_bytes(&priv_bytes, b"hello")
+            .expect("Could not sign");
         assert!(ecdsa.verify(&hash, &signature, &pub_key));
+        let (_, other) = ecdsa.generate_key_pair();
+        let items = vec![(b"hello".to_vec(), signature)];
+        assert_eq!(
+            ecdsa.find_signer(&items, &[other, pub_key.clone()]),
+            Some(1)
+        );
+        let ecdsa = ecdsa.with_hasher(Box::new(Sha256Hasher));
+        assert_eq!(ecdsa.find_signer(&items, &[pub_key]), None);
+    }
+
+    #[test]
+    fn test_new() {
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+        let ecdsa = ECDSA::new(ec.clone(), g.clone(), n.clone()).expect("Cannot build ECDSA");
+        assert_eq!(ecdsa.q_order(), &n);
+        assert_eq!(ecdsa.a_gen(), &g);
+        assert_eq!(ecdsa.ec(), &ec);
+
+        // the field prime is not the group order
+        assert_eq!(
+            ECDSA::new(ec.clone(), g.clone(), ec.p.clone()).err(),
+            Some(EccError::InvalidOrder)
+        );
+        assert_eq!(
+            ECDSA::new(ec.clone(), g.clone(), &n + 1u32).err(),
+            Some(EccError::InvalidOrder)
+        );
+        assert_eq!(
+            ECDSA::new(ec.clone(), Point::Identity, n.clone()).err(),
+            Some(EccError::InvalidPoint)
+        );
+        let off_curve = Point::Coordinate(BigUint::from(1u32), BigUint::from(1u32));
+        assert_eq!(
+            ECDSA::new(ec, off_curve, n).err(),
+            Some(EccError::InvalidPoint)
+        );
+
+        // y^2 = x^3 + 2x + 2 mod 17, (5, 1) has order 19
+        let toy = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let a_gen = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        assert!(ECDSA::new(toy.clone(), a_gen.clone(), BigUint::from(19u32)).is_ok());
+        assert_eq!(
+            ECDSA::new(toy.clone(), a_gen.clone(), BigUint::from(17u32)).err(),
+            Some(EccError::InvalidOrder)
+        );
+        // 38 A = I too, but signing would have to invert k = 2 mod 38
+        assert_eq!(
+            ECDSA::new(toy, a_gen, BigUint::from(38u32)).err(),
+            Some(EccError::InvalidOrder)
+        );
+
+        // y^2 = x^3 + x + 4 mod 17 with h = 2: (0, 2) has order 14 and is not in the
+        // order-7 subgroup that (4, 2) generates
+        let toy = EllipticCurve::with_cofactor(
+            BigUint::from(1u32),
+            BigUint::from(4u32),
+            BigUint::from(17u32),
+            BigUint::from(2u32),
+        )
+        .expect("Cannot build curve");
+        let a_gen = Point::Coordinate(BigUint::from(4u32), BigUint::from(2u32));
+        assert!(ECDSA::new(toy.clone(), a_gen, BigUint::from(7u32)).is_ok());
+        let mixed = Point::Coordinate(BigUint::from(0u32), BigUint::from(2u32));
+        assert_eq!(
+            ECDSA::new(toy, mixed, BigUint::from(7u32)).err(),
+            Some(EccError::InvalidOrder)
+        );
+    }
+
+    #[test]
+    fn test_recover_pub_key() {
+        let ecdsa = ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        for _ in 0..5 {
+            let (priv_key, pub_key) = ecdsa.generate_key_pair();
+            let hash =
+                ecdsa.hash_bytes_less_than(b"recover me", &ecdsa.q_order, HashReduction::Modulo);
+            let signature = ecdsa.sign(&priv_key, &hash).expect("Could not sign");
+
+            // one of the two parities gives the signer, the other some other key
+            let recovered: Vec<PublicKey> = (0..2)
+                .map(|id| {
+                    ecdsa
+                        .recover_pub_key(&hash, &signature, id)
+                        .expect("Could not recover")
+                })
+                .collect();
+            assert!(recovered.contains(&pub_key));
+            assert_ne!(recovered[0], recovered[1]);
+            assert_eq!(ecdsa.recover_pub_keys(&hash, &signature), Ok(recovered));
+            // r + q is above p on secp256k1
+            assert_eq!(
+                ecdsa.recover_pub_key(&hash, &signature, 2),
+                Err(EccError::InvalidPoint)
+            );
+        }
+
+        let (_, s) = ecdsa
+            .sign(&ecdsa.generate_priv_key(), &BigUint::from(1u32))
+            .expect("Could not sign");
+        let zero = (BigUint::from(0u32), s.clone());
+        assert_eq!(
+            ecdsa.recover_pub_key(&BigUint::from(1u32), &zero, 0),
+            Err(EccError::InvalidScalar)
+        );
+        assert_eq!(
+            ecdsa.recover_pub_keys(&BigUint::from(1u32), &zero),
+            Err(EccError::InvalidScalar)
+        );
+        let signature = (BigUint::from(1u32), s);
+        assert!(ecdsa
+            .recover_pub_key(&BigUint::from(1u32), &signature, 4)
+            .is_err());
+    }
+
+    #[test]
+    fn test_recover_pub_key_r_above_order() {
+        // y^2 = x^3 + 7 mod 43 has 31 points, 3A = (35, 21) and r = 35 - 31 = 4
+        let ecdsa = ECDSA::new(
+            EllipticCurve::new(
+                BigUint::from(0u32),
+                BigUint::from(7u32),
+                BigUint::from(43u32),
+            )
+            .expect("Cannot build curve"),
+            Point::Coordinate(BigUint::from(2u32), BigUint::from(12u32)),
+            BigUint::from(31u32),
+        )
+        .expect("Cannot build ECDSA");
+        let priv_key =
+            PrivateKey::new(BigUint::from(7u32), &ecdsa.q_order).expect("Cannot build priv_key");
+        let pub_key = ecdsa.generate_pub_key(&priv_key);
+        let hash = BigUint::from(10u32);
+        let signature = ecdsa
+            .sign_with_k(priv_key.scalar(), &hash, &BigUint::from(3u32))
+            .expect("Could not sign")
+            .expect("k gives a valid signature");
+        assert!(
+            (2..4).any(|id| ecdsa.recover_pub_key(&hash, &signature, id) == Ok(pub_key.clone()))
+        );
+        assert!(
+            (0..2).all(|id| ecdsa.recover_pub_key(&hash, &signature, id) != Ok(pub_key.clone()))
+        );
+
+        // p = 17 is 1 mod 4, so y(R) goes through the general square root
+        let ecdsa = ECDSA::new(
+            EllipticCurve::new(
+                BigUint::from(2u32),
+                BigUint::from(2u32),
+                BigUint::from(17u32),
+            )
+            .expect("Cannot build curve"),
+            Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32)),
+            BigUint::from(19u32),
+        )
+        .expect("Cannot build ECDSA");
+        for d in 1u32..19 {
+            let priv_key =
+                PrivateKey::new(BigUint::from(d), &ecdsa.q_order).expect("Cannot build priv_key");
+            let pub_key = ecdsa.generate_pub_key(&priv_key);
+            let signature = ecdsa.sign(&priv_key, &hash).expect("Could not sign");
+            assert!((0..4)
+                .any(|id| ecdsa.recover_pub_key(&hash, &signature, id) == Ok(pub_key.clone())));
+            let pub_keys = ecdsa
+                .recover_pub_keys(&hash, &signature)
+                .expect("Could not recover");
+            assert!(pub_keys.contains(&pub_key));
+            assert!(pub_keys
+                .iter()
+                .all(|candidate| ecdsa.verify(&hash, &signature, candidate)));
+        }
     }
 }
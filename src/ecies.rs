@@ -0,0 +1,278 @@
+use crate::ecdh::ecdh_shared_secret;
+use crate::ecdsa::int2octets;
+#[cfg(any(feature = "std", feature = "getrandom"))]
+use crate::rng;
+use crate::{EccError, EllipticCurve, Point};
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use hmac::{Hmac, Mac};
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+
+const TAG_LEN: usize = 32;
+
+// ciphertext = R || c || tag
+// R = kA written as 0x04 || x || y, both padded to the byte width of p
+// (enc_key, mac_key) = KDF(x(kP)), c = m XOR enc_key, tag = HMAC-SHA256(mac_key, R || c)
+// the receiver gets the same x from d R = d k A = k P
+// -R gives the same x as R, the tag covering R is what rejects it
+#[allow(clippy::upper_case_acronyms)]
+pub struct ECIES {}
+
+impl ECIES {
+    // fails with InvalidPoint when generator or pub_key is not a point of ec other than
+    // the identity, with InvalidOrder unless order >= 2 and order * generator = I, and
+    // with SmallSubgroupPoint unless order * pub_key = I
+    // with a prime order kA is never the identity, with a composite multiple of the
+    // order of A it can be, and encrypt fails with InvalidOrder rather than draw again
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    pub fn encrypt(
+        ec: &EllipticCurve,
+        generator: &Point,
+        order: &BigUint,
+        pub_key: &Point,
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, EccError> {
+        if generator == &Point::Identity || !ec.is_on_curve(generator) {
+            return Err(EccError::InvalidPoint);
+        }
+        if order < &BigUint::from(2u32) || !ec.is_in_subgroup(generator, order) {
+            return Err(EccError::InvalidOrder);
+        }
+        let k = rng::gen_biguint_range(&BigUint::from(1u32), order);
+        let ephemeral = match ec.scalar_mul_ct(generator, &k)? {
+            Point::Identity => return Err(EccError::InvalidOrder),
+            point => point,
+        };
+        let shared = ecdh_shared_secret(ec, &k, pub_key, order)?;
+        let (enc_key, mac_key) = Self::derive_keys(ec, &shared, plaintext.len());
+
+        let mut out = Self::encode_point(ec, &ephemeral);
+        out.extend(plaintext.iter().zip(&enc_key).map(|(m, k)| m ^ k));
+        let tag = Self::tag(&mac_key, &out);
+        out.extend_from_slice(&tag);
+        Ok(out)
+    }
+    // fails with InvalidEncoding when ciphertext is too short or R is not 0x04 || x || y,
+    // InvalidPoint when R is not on ec, SmallSubgroupPoint unless order R = I, InvalidMac
+    // when the tag does not match
+    pub fn decrypt(
+        ec: &EllipticCurve,
+        order: &BigUint,
+        priv_key: &BigUint,
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, EccError> {
+        let width = (ec.p.bits() as usize).div_ceil(8);
+        let point_len = 1 + 2 * width;
+        if ciphertext.len() < point_len + TAG_LEN {
+            return Err(EccError::InvalidEncoding(
+                "ciphertext is too short".to_string(),
+            ));
+        }
+        let (authenticated, tag) = ciphertext.split_at(ciphertext.len() - TAG_LEN);
+        let (point, body) = authenticated.split_at(point_len);
+        if point[0] != 0x04 {
+            return Err(EccError::InvalidEncoding(
+                "ephemeral key is not an uncompressed point".to_string(),
+            ));
+        }
+        let ephemeral = Point::Coordinate(
+            BigUint::from_bytes_be(&point[1..1 + width]),
+            BigUint::from_bytes_be(&point[1 + width..]),
+        );
+
+        let shared = ecdh_shared_secret(ec, priv_key, &ephemeral, order)?;
+        let (enc_key, mac_key) = Self::derive_keys(ec, &shared, body.len());
+        // compare every byte so the time taken does not point at the first mismatch
+        let expected = Self::tag(&mac_key, authenticated);
+        let diff = expected
+            .iter()
+            .zip(tag)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        if diff != 0 {
+            return Err(EccError::InvalidMac);
+        }
+        Ok(body.iter().zip(&enc_key).map(|(c, k)| c ^ k).collect())
+    }
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    fn encode_point(ec: &EllipticCurve, point: &Point) -> Vec<u8> {
+        let width = (ec.p.bits() as usize).div_ceil(8);
+        let mut out = Vec::with_capacity(1 + 2 * width);
+        if let Point::Coordinate(x, y) = point {
+            out.push(0x04);
+            out.extend(int2octets(x, width));
+            out.extend(int2octets(y, width));
+        }
+        out
+    }
+    // ANSI X9.63 KDF: SHA-256(x || counter) for counter = 1, 2, ... until there are
+    // enough bytes for enc_key (one per plaintext byte) and a 32 byte mac_key
+    fn derive_keys(ec: &EllipticCurve, shared: &BigUint, len: usize) -> (Vec<u8>, Vec<u8>) {
+        let width = (ec.p.bits() as usize).div_ceil(8);
+        let x = int2octets(shared, width);
+        let mut stream = Vec::with_capacity(len + TAG_LEN);
+        let mut counter = 1u32;
+        while stream.len() < len + TAG_LEN {
+            let mut hasher = Sha256::new();
+            hasher.update(&x);
+            hasher.update(counter.to_be_bytes());
+            stream.extend_from_slice(&hasher.finalize());
+            counter += 1;
+        }
+        stream.truncate(len + TAG_LEN);
+        let mac_key = stream.split_off(len);
+        (stream, mac_key)
+    }
+    fn tag(mac_key: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(mac_key).expect("HMAC takes keys of any size");
+        mac.update(body);
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use num_bigint::RandBigInt;
+
+    fn round_trip(ec: &EllipticCurve, a_gen: &Point, q_order: &BigUint) {
+        let priv_key = rand::thread_rng().gen_biguint_range(&BigUint::from(1u32), q_order);
+        let pub_key = ec.scalar_mul(a_gen, &priv_key).expect("A is on the curve");
+        let plaintext: Vec<u8> = (0u8..32).collect();
+
+        let ciphertext =
+            ECIES::encrypt(ec, a_gen, q_order, &pub_key, &plaintext).expect("Could not encrypt");
+        let width = (ec.p.bits() as usize).div_ceil(8);
+        assert_eq!(ciphertext.len(), 1 + 2 * width + plaintext.len() + TAG_LEN);
+        assert_eq!(
+            ECIES::decrypt(ec, q_order, &priv_key, &ciphertext),
+            Ok(plaintext.clone())
+        );
+
+        // any flipped bit of the body or the tag is caught by the mac
+        for i in [1 + 2 * width, ciphertext.len() - 1] {
+            let mut tampered = ciphertext.clone();
+            tampered[i] ^= 1;
+            assert_eq!(
+                ECIES::decrypt(ec, q_order, &priv_key, &tampered),
+                Err(EccError::InvalidMac)
+            );
+        }
+
+        // -R gives the same shared secret, only the tag over R tells it apart
+        let y = BigUint::from_bytes_be(&ciphertext[1 + width..1 + 2 * width]);
+        let mut negated = ciphertext.clone();
+        negated[1 + width..1 + 2 * width].copy_from_slice(&int2octets(&(&ec.p - y), width));
+        assert_ne!(negated, ciphertext);
+        assert_eq!(
+            ECIES::decrypt(ec, q_order, &priv_key, &negated),
+            Err(EccError::InvalidMac)
+        );
+    }
+
+    #[test]
+    fn test_round_trip_toy_curve() {
+        // y^2 = x^3 + 2x + 2 mod 17, A = (5, 1) of order 19
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let a_gen = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        round_trip(&ec, &a_gen, &BigUint::from(19u32));
+    }
+
+    #[test]
+    fn test_round_trip_sec256k1() {
+        round_trip(
+            &EllipticCurve::secp256k1(),
+            &EllipticCurve::secp256k1_generator(),
+            &EllipticCurve::secp256k1_order(),
+        );
+    }
+
+    #[test]
+    fn test_decrypt_rejects_bad_input() {
+        let ec = EllipticCurve::secp256k1();
+        let a_gen = EllipticCurve::secp256k1_generator();
+        let q_order = EllipticCurve::secp256k1_order();
+        let priv_key = BigUint::from(7u32);
+        let pub_key = ec.scalar_mul(&a_gen, &priv_key).expect("A is on the curve");
+        let ciphertext =
+            ECIES::encrypt(&ec, &a_gen, &q_order, &pub_key, b"hi").expect("Could not encrypt");
+
+        assert!(matches!(
+            ECIES::decrypt(&ec, &q_order, &priv_key, &ciphertext[..64]),
+            Err(EccError::InvalidEncoding(_))
+        ));
+        let mut compressed = ciphertext.clone();
+        compressed[0] = 0x02;
+        assert!(matches!(
+            ECIES::decrypt(&ec, &q_order, &priv_key, &compressed),
+            Err(EccError::InvalidEncoding(_))
+        ));
+        let mut off_curve = ciphertext.clone();
+        off_curve[1] ^= 1;
+        assert_eq!(
+            ECIES::decrypt(&ec, &q_order, &priv_key, &off_curve),
+            Err(EccError::InvalidPoint)
+        );
+        // a different key derives a different mac key
+        assert_eq!(
+            ECIES::decrypt(&ec, &q_order, &BigUint::from(8u32), &ciphertext),
+            Err(EccError::InvalidMac)
+        );
+
+        assert_eq!(
+            ECIES::encrypt(&ec, &a_gen, &q_order, &Point::Identity, b"hi"),
+            Err(EccError::InvalidPoint)
+        );
+
+        // y^2 = x^3 + x + 4 mod 17, order 7 and h = 2, R = (3, 0) of order 2
+        let ec = EllipticCurve::with_cofactor(
+            BigUint::from(1u32),
+            BigUint::from(4u32),
+            BigUint::from(17u32),
+            BigUint::from(2u32),
+        )
+        .expect("Cannot build curve");
+        let mut small_order = vec![0x04, 3, 0];
+        small_order.extend([0u8; TAG_LEN]);
+        assert_eq!(
+            ECIES::decrypt(&ec, &BigUint::from(7u32), &priv_key, &small_order),
+            Err(EccError::SmallSubgroupPoint)
+        );
+    }
+
+    #[test]
+    fn test_encrypt_rejects_bad_generator() {
+        let ec = EllipticCurve::secp256k1();
+        let a_gen = EllipticCurve::secp256k1_generator();
+        let q_order = EllipticCurve::secp256k1_order();
+        let pub_key = ec
+            .scalar_mul(&a_gen, &BigUint::from(7u32))
+            .expect("A is on the curve");
+
+        // these used to draw k forever or panic in the range draw
+        assert_eq!(
+            ECIES::encrypt(&ec, &Point::Identity, &q_order, &pub_key, b"hi"),
+            Err(EccError::InvalidPoint)
+        );
+        let off_curve = Point::Coordinate(BigUint::from(1u32), BigUint::from(1u32));
+        assert_eq!(
+            ECIES::encrypt(&ec, &off_curve, &q_order, &pub_key, b"hi"),
+            Err(EccError::InvalidPoint)
+        );
+        for order in [0u32, 1, 19] {
+            assert_eq!(
+                ECIES::encrypt(&ec, &a_gen, &BigUint::from(order), &pub_key, b"hi"),
+                Err(EccError::InvalidOrder)
+            );
+        }
+        assert_eq!(
+            ECIES::encrypt(&ec, &a_gen, &ec.p, &pub_key, b"hi"),
+            Err(EccError::InvalidOrder)
+        );
+    }
+}
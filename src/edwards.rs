@@ -0,0 +1,541 @@
+use crate::{is_probable_prime, EccError, FiniteField, Point};
+use num_bigint::BigUint;
+use sha2::{Digest, Sha512};
+
+// a x^2 + y^2 = 1 + d x^2 y^2 mod p
+// the neutral element is the point (0, 1), Point::Identity is not used on these curves.
+// new requires a to be a square and d a non-square mod p, which makes the addition law
+// below complete: it holds for doubling, for the neutral element and for opposite
+// points alike, and its denominators 1 +- t are never 0
+#[derive(PartialEq, Debug, Clone)]
+pub struct TwistedEdwardsCurve {
+    pub a: BigUint,
+    pub d: BigUint,
+    pub p: BigUint,
+}
+
+impl TwistedEdwardsCurve {
+    // fails with InvalidModulus when p is not a prime above 3, with SingularCurve when
+    // a = 0, d = 0 or a = d mod p, and with IncompleteCurve unless a is a square and d
+    // a non-square mod p
+    pub fn new(a: BigUint, d: BigUint, p: BigUint) -> Result<Self, EccError> {
+        if p <= BigUint::from(3u32) || !is_probable_prime(&p) {
+            return Err(EccError::InvalidModulus);
+        }
+        // a = d or d = 0 degenerates the curve
+        let zero = BigUint::from(0u32);
+        if &a % &p == zero || &d % &p == zero || &a % &p == &d % &p {
+            return Err(EccError::SingularCurve);
+        }
+        if FiniteField::legendre_symbol(&a, &p) != 1 || FiniteField::legendre_symbol(&d, &p) != -1 {
+            return Err(EccError::IncompleteCurve);
+        }
+        Ok(TwistedEdwardsCurve { a, d, p })
+    }
+    pub fn neutral() -> Point {
+        Point::Coordinate(BigUint::from(0u32), BigUint::from(1u32))
+    }
+    pub fn is_on_curve(&self, c: &Point) -> bool {
+        match c {
+            Point::Coordinate(x, y) => {
+                let x2 = FiniteField::mult(x, x, &self.p);
+                let y2 = FiniteField::mult(y, y, &self.p);
+                let ax2 = FiniteField::mult(&self.a, &x2, &self.p);
+                let lhs = FiniteField::add(&ax2, &y2, &self.p);
+                let dx2y2 =
+                    FiniteField::mult(&self.d, &FiniteField::mult(&x2, &y2, &self.p), &self.p);
+                let rhs = FiniteField::add(&BigUint::from(1u32), &dx2y2, &self.p);
+                lhs == rhs
+            }
+            Point::Identity => false,
+        }
+    }
+    // the operands of the group law must be reduced points of the curve
+    fn check_point(&self, c: &Point) -> Result<(), EccError> {
+        let reduced = match c {
+            Point::Coordinate(x, y) => x < &self.p && y < &self.p,
+            Point::Identity => false,
+        };
+        if !reduced || !self.is_on_curve(c) {
+            return Err(EccError::PointNotOnCurve);
+        }
+        Ok(())
+    }
+    pub fn add(&self, c: &Point, d: &Point) -> Result<Point, EccError> {
+        self.check_point(c)?;
+        self.check_point(d)?;
+        self.add_points(c, d)
+    }
+    fn add_points(&self, c: &Point, d: &Point) -> Result<Point, EccError> {
+        self.add_points_with(c, d, FiniteField::divide)
+    }
+    // add_points with the denominators inverted by the fixed-round gcd, for scalar_mul_ct
+    fn add_points_ct(&self, c: &Point, d: &Point) -> Result<Point, EccError> {
+        self.add_points_with(c, d, |numerator, denominator, p| {
+            let inv = FiniteField::inv_multiplication_ct(denominator, p)?;
+            Ok(FiniteField::mult(numerator, &inv, p))
+        })
+    }
+    fn add_points_with(
+        &self,
+        c: &Point,
+        d: &Point,
+        divide: fn(&BigUint, &BigUint, &BigUint) -> Result<BigUint, EccError>,
+    ) -> Result<Point, EccError> {
+        // t = d x1 x2 y1 y2
+        // x3 = (x1 y2 + y1 x2) / (1 + t) mod p
+        // y3 = (y1 y2 - a x1 x2) / (1 - t) mod p
+        let (Point::Coordinate(x1, y1), Point::Coordinate(x2, y2)) = (c, d) else {
+            return Err(EccError::PointNotOnCurve);
+        };
+        let one = BigUint::from(1u32);
+        let x1x2 = FiniteField::mult(x1, x2, &self.p);
+        let y1y2 = FiniteField::mult(y1, y2, &self.p);
+        let t = FiniteField::mult(&self.d, &FiniteField::mult(&x1x2, &y1y2, &self.p), &self.p);
+
+        let x1y2 = FiniteField::mult(x1, y2, &self.p);
+        let y1x2 = FiniteField::mult(y1, x2, &self.p);
+        let numerator = FiniteField::add(&x1y2, &y1x2, &self.p);
+        let denominator = FiniteField::add(&one, &t, &self.p);
+        let x3 = divide(&numerator, &denominator, &self.p)?;
+
+        let ax1x2 = FiniteField::mult(&self.a, &x1x2, &self.p);
+        let numerator = FiniteField::subtract(&y1y2, &ax1x2, &self.p);
+        let denominator = FiniteField::subtract(&one, &t, &self.p);
+        let y3 = divide(&numerator, &denominator, &self.p)?;
+
+        Ok(Point::Coordinate(x3, y3))
+    }
+    pub fn doubling(&self, c: &Point) -> Result<Point, EccError> {
+        self.check_point(c)?;
+        self.add_points(c, c)
+    }
+    // -(x, y) = (p - x, y)
+    pub fn negate(&self, c: &Point) -> Point {
+        match c {
+            Point::Coordinate(x, y) => Point::Coordinate(FiniteField::neg(x, &self.p), y.clone()),
+            Point::Identity => Point::Identity,
+        }
+    }
+    pub fn scalar_mul(&self, c: &Point, d: &BigUint) -> Result<Point, EccError> {
+        // a = (0, 1)
+        // for i in range(bits(d)-1 to 0)
+        //     a = 2a
+        //     if bit(i)
+        //          a = a + c
+        self.check_point(c)?;
+        let mut a = Self::neutral();
+        for i in (0..d.bits()).rev() {
+            a = self.add_points(&a, &a)?;
+            if d.bit(i) {
+                a = self.add_points(&a, c)?;
+            }
+        }
+        Ok(a)
+    }
+    // Montgomery ladder for secret scalars
+    // r0 = (0, 1), r1 = c
+    // for i in range(n-1 to 0)
+    //     cswap(bit(i))
+    //     r1 = r0 + r1, r0 = 2r0
+    //     cswap(bit(i))
+    // the law is complete, so every step is the same two additions whatever r0 and r1
+    // are, the swaps are masks over the coordinates (FiniteField::cswap) and
+    // n = max(bits(d), bits(p) + 1) does not depend on the leading zeros of d.
+    // num-bigint itself still takes time in the limb count of its operands
+    pub fn scalar_mul_ct(&self, c: &Point, d: &BigUint) -> Result<Point, EccError> {
+        #[cfg(test)]
+        crate::SCALAR_MUL_CT_CALLS.with(|count| count.set(count.get() + 1));
+        self.check_point(c)?;
+        let mut r0 = Self::neutral();
+        let mut r1 = c.clone();
+        let mut swap = BigUint::from(0u32);
+        let n = d.bits().max(self.p.bits() + 1);
+        for i in (0..n).rev() {
+            let bit = BigUint::from(d.bit(i) as u32);
+            swap ^= &bit;
+            Self::cswap(&swap, &mut r0, &mut r1);
+            swap = bit;
+            r1 = self.add_points_ct(&r0, &r1)?;
+            r0 = self.add_points_ct(&r0, &r0)?;
+        }
+        Self::cswap(&swap, &mut r0, &mut r1);
+        Ok(r0)
+    }
+    // swaps c and d when flag is 1, leaves them when it is 0, without branching on flag
+    fn cswap(flag: &BigUint, c: &mut Point, d: &mut Point) {
+        if let (Point::Coordinate(x1, y1), Point::Coordinate(x2, y2)) = (c, d) {
+            FiniteField::cswap(flag, x1, x2);
+            FiniteField::cswap(flag, y1, y2);
+        }
+    }
+    pub fn ed25519() -> Self {
+        /*
+                Ed25519, RFC 8032 section 5.1
+                Name	Value
+                p	0x7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed (2^255 - 19)
+                a	-1 mod p
+                d	0x52036cee2b6ffe738cc740797779e89800700a4d4141d8ab75eb4dca135978a3 (-121665/121666 mod p)
+                g	(216936d3cd6e53fec0a4e231fdd6dc5c692cc7609525a7b2c9562d608f25d51a, 6666666666666666666666666666666666666666666666666666666666666658)
+                n	0x1000000000000000000000000000000014def9dea2f79cd65812631a5cf5d3ed
+        */
+        let p = BigUint::parse_bytes(
+            b"7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed",
+            16,
+        )
+        .expect("Cannot parse p");
+        let a = &p - BigUint::from(1u32);
+        let d = BigUint::parse_bytes(
+            b"52036cee2b6ffe738cc740797779e89800700a4d4141d8ab75eb4dca135978a3",
+            16,
+        )
+        .expect("Cannot parse d");
+        TwistedEdwardsCurve::new(a, d, p).expect("Ed25519 is non-singular")
+    }
+    pub fn ed25519_generator() -> Point {
+        let gx = BigUint::parse_bytes(
+            b"216936d3cd6e53fec0a4e231fdd6dc5c692cc7609525a7b2c9562d608f25d51a",
+            16,
+        )
+        .expect("Cannot parse gx");
+        let gy = BigUint::parse_bytes(
+            b"6666666666666666666666666666666666666666666666666666666666666658",
+            16,
+        )
+        .expect("Cannot parse gy");
+        Point::Coordinate(gx, gy)
+    }
+    pub fn ed25519_order() -> BigUint {
+        BigUint::parse_bytes(
+            b"1000000000000000000000000000000014def9dea2f79cd65812631a5cf5d3ed",
+            16,
+        )
+        .expect("Cannot parse n")
+    }
+}
+
+// EdDSA over edwards25519, RFC 8032 section 5.1
+// keys: h = SHA-512(secret), s = clamp(h[..32]), A = s B, prefix = h[32..]
+// sign: r = SHA-512(prefix || m) mod L, R = r B, k = SHA-512(R || A || m) mod L,
+//       S = r + k s mod L, sig = R || S
+// verify: 8 S B = 8 R + 8 k A
+// points are 32 bytes, y little-endian with the parity of x in the top bit, and
+// scalars 32 bytes little-endian
+pub struct Ed25519 {
+    curve: TwistedEdwardsCurve,
+}
+
+impl Default for Ed25519 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ed25519 {
+    pub fn new() -> Self {
+        Ed25519 {
+            curve: TwistedEdwardsCurve::ed25519(),
+        }
+    }
+    pub fn public_key(&self, secret_key: &[u8; 32]) -> [u8; 32] {
+        let (s, _) = Self::expand(secret_key);
+        self.encode(&self.base_mul(&s))
+    }
+    pub fn sign(&self, secret_key: &[u8; 32], msg: &[u8]) -> [u8; 64] {
+        let (s, prefix) = Self::expand(secret_key);
+        let l = TwistedEdwardsCurve::ed25519_order();
+        let pub_key = self.encode(&self.base_mul(&s));
+
+        let r = Self::hash_to_scalar(&[&prefix, msg]);
+        let r_point = self.encode(&self.base_mul(&r));
+        let k = Self::hash_to_scalar(&[&r_point, &pub_key, msg]);
+        let big_s = FiniteField::add(&r, &FiniteField::mult(&k, &s, &l), &l);
+
+        let mut sig = [0u8; 64];
+        sig[..32].copy_from_slice(&r_point);
+        sig[32..].copy_from_slice(&Self::scalar_bytes(&big_s));
+        sig
+    }
+    // false for points that do not decode and for S >= L
+    pub fn verify(&self, pub_key: &[u8; 32], msg: &[u8], sig: &[u8; 64]) -> bool {
+        let l = TwistedEdwardsCurve::ed25519_order();
+        let r_bytes: [u8; 32] = sig[..32].try_into().expect("sig is 64 bytes");
+        let (Some(a), Some(r)) = (self.decode(pub_key), self.decode(&r_bytes)) else {
+            return false;
+        };
+        let big_s = BigUint::from_bytes_le(&sig[32..]);
+        if big_s >= l {
+            return false;
+        }
+        let k = Self::hash_to_scalar(&[&r_bytes, pub_key, msg]);
+
+        let eight = BigUint::from(8u32);
+        // nothing here is secret, so no ladder
+        let lhs = self
+            .curve
+            .scalar_mul(
+                &TwistedEdwardsCurve::ed25519_generator(),
+                &(&big_s * &eight),
+            )
+            .expect("B is on the curve");
+        let rhs = self.curve.scalar_mul(&r, &eight).and_then(|r8| {
+            let ka8 = self.curve.scalar_mul(&a, &(&k * &eight))?;
+            self.curve.add(&r8, &ka8)
+        });
+        rhs == Ok(lhs)
+    }
+    // (s, prefix) from SHA-512(secret_key), s with its low 3 bits and bit 255
+    // cleared and bit 254 set
+    fn expand(secret_key: &[u8; 32]) -> (BigUint, [u8; 32]) {
+        let h = Sha512::digest(secret_key);
+        let mut s: [u8; 32] = h[..32].try_into().expect("SHA-512 gives 64 bytes");
+        s[0] &= 248;
+        s[31] &= 127;
+        s[31] |= 64;
+        let prefix = h[32..].try_into().expect("SHA-512 gives 64 bytes");
+        (BigUint::from_bytes_le(&s), prefix)
+    }
+    // SHA-512 of the parts as a little-endian integer mod L
+    fn hash_to_scalar(parts: &[&[u8]]) -> BigUint {
+        let mut hasher = Sha512::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        BigUint::from_bytes_le(&hasher.finalize()) % TwistedEdwardsCurve::ed25519_order()
+    }
+    // k B with the ladder of scalar_mul_ct, k being the secret s or the nonce r
+    fn base_mul(&self, k: &BigUint) -> Point {
+        self.curve
+            .scalar_mul_ct(&TwistedEdwardsCurve::ed25519_generator(), k)
+            .expect("B is on the curve")
+    }
+    fn scalar_bytes(n: &BigUint) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        let le = n.to_bytes_le();
+        bytes[..le.len()].copy_from_slice(&le);
+        bytes
+    }
+    fn encode(&self, c: &Point) -> [u8; 32] {
+        let Point::Coordinate(x, y) = c else {
+            unreachable!("edwards points are never Point::Identity");
+        };
+        let mut bytes = Self::scalar_bytes(y);
+        if x.bit(0) {
+            bytes[31] |= 0x80;
+        }
+        bytes
+    }
+    // x^2 = (y^2 - 1) / (d y^2 - a), then the root with the parity of the top bit
+    // None when y >= p, when there is no root, or for x = 0 with the top bit set
+    fn decode(&self, bytes: &[u8; 32]) -> Option<Point> {
+        let p = &self.curve.p;
+        let x_odd = bytes[31] & 0x80 != 0;
+        let mut y_bytes = *bytes;
+        y_bytes[31] &= 0x7f;
+        let y = BigUint::from_bytes_le(&y_bytes);
+        if &y >= p {
+            return None;
+        }
+        let y2 = FiniteField::mult(&y, &y, p);
+        let numerator = FiniteField::subtract(&y2, &BigUint::from(1u32), p);
+        let denominator =
+            FiniteField::subtract(&FiniteField::mult(&self.curve.d, &y2, p), &self.curve.a, p);
+        let x2 = FiniteField::divide(&numerator, &denominator, p).ok()?;
+        let x = FiniteField::sqrt(&x2, p)?;
+        if x == BigUint::from(0u32) && x_odd {
+            return None;
+        }
+        let x = if x.bit(0) != x_odd {
+            FiniteField::neg(&x, p)
+        } else {
+            x
+        };
+        Some(Point::Coordinate(x, y))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ed25519_order() {
+        let curve = TwistedEdwardsCurve::ed25519();
+        let g = TwistedEdwardsCurve::ed25519_generator();
+        let n = TwistedEdwardsCurve::ed25519_order();
+        assert!(curve.is_on_curve(&g));
+        assert!(curve.is_on_curve(&TwistedEdwardsCurve::neutral()));
+
+        assert_eq!(curve.scalar_mul(&g, &n), Ok(TwistedEdwardsCurve::neutral()));
+        let n_plus_one = &n + BigUint::from(1u32);
+        assert_eq!(curve.scalar_mul(&g, &n_plus_one), Ok(g.clone()));
+    }
+
+    #[test]
+    fn test_group_law() {
+        let curve = TwistedEdwardsCurve::ed25519();
+        let g = TwistedEdwardsCurve::ed25519_generator();
+        let neutral = TwistedEdwardsCurve::neutral();
+
+        // the same formula covers the neutral element, doubling and opposite points
+        assert_eq!(curve.add(&g, &neutral), Ok(g.clone()));
+        assert_eq!(curve.add(&g, &curve.negate(&g)), Ok(neutral.clone()));
+        let g2 = curve.doubling(&g).expect("G is on the curve");
+        assert_eq!(curve.add(&g, &g), Ok(g2.clone()));
+        assert_eq!(curve.scalar_mul(&g, &BigUint::from(2u32)), Ok(g2.clone()));
+
+        let g3 = curve.add(&g2, &g).expect("2G is on the curve");
+        assert_eq!(curve.add(&g, &g2), Ok(g3.clone()));
+        assert_eq!(curve.scalar_mul(&g, &BigUint::from(3u32)), Ok(g3));
+        assert_eq!(curve.scalar_mul(&g, &BigUint::from(0u32)), Ok(neutral));
+    }
+
+    #[test]
+    fn test_scalar_mul_ct() {
+        let curve = TwistedEdwardsCurve::ed25519();
+        let g = TwistedEdwardsCurve::ed25519_generator();
+        let n = TwistedEdwardsCurve::ed25519_order();
+        for d in [0u32, 1, 2, 3, 8, 255, 65537] {
+            let d = BigUint::from(d);
+            assert_eq!(curve.scalar_mul_ct(&g, &d), curve.scalar_mul(&g, &d));
+        }
+        assert_eq!(
+            curve.scalar_mul_ct(&g, &n),
+            Ok(TwistedEdwardsCurve::neutral())
+        );
+        let n_minus_one = &n - BigUint::from(1u32);
+        assert_eq!(curve.scalar_mul_ct(&g, &n_minus_one), Ok(curve.negate(&g)));
+        // above bits(p) + 1 the ladder runs for bits(d) steps
+        let big = (&n << 10) + BigUint::from(5u32);
+        assert_eq!(curve.scalar_mul_ct(&g, &big), curve.scalar_mul(&g, &big));
+        assert_eq!(
+            curve.scalar_mul_ct(&Point::Identity, &BigUint::from(2u32)),
+            Err(EccError::PointNotOnCurve)
+        );
+    }
+
+    #[test]
+    fn test_new() {
+        // squares mod 13: 1, 3, 4, 9, 10, 12
+        let p = BigUint::from(13u32);
+        assert!(
+            TwistedEdwardsCurve::new(BigUint::from(1u32), BigUint::from(2u32), p.clone()).is_ok()
+        );
+        // d = 4 is a square
+        assert_eq!(
+            TwistedEdwardsCurve::new(BigUint::from(1u32), BigUint::from(4u32), p.clone()),
+            Err(EccError::IncompleteCurve)
+        );
+        // a = 2 is not a square
+        assert_eq!(
+            TwistedEdwardsCurve::new(BigUint::from(2u32), BigUint::from(5u32), p),
+            Err(EccError::IncompleteCurve)
+        );
+        assert_eq!(
+            TwistedEdwardsCurve::new(
+                BigUint::from(1u32),
+                BigUint::from(2u32),
+                BigUint::from(15u32)
+            ),
+            Err(EccError::InvalidModulus)
+        );
+    }
+
+    fn bytes32(s: &str) -> [u8; 32] {
+        hex::decode(s)
+            .expect("Cannot decode hex")
+            .try_into()
+            .expect("Not 32 bytes")
+    }
+
+    #[test]
+    fn test_ed25519_rfc8032() {
+        // section 7.1, TEST 1 to 3
+        let ed25519 = Ed25519::new();
+        for (secret, public, msg, sig) in [
+            (
+                "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+                "",
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+                 5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            ),
+            (
+                "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+                "72",
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+                 085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+            ),
+            (
+                "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+                "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+                "af82",
+                "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac\
+                 18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a",
+            ),
+        ] {
+            let secret = bytes32(secret);
+            let public = bytes32(public);
+            let msg = hex::decode(msg).expect("Cannot decode hex");
+            let sig: [u8; 64] = hex::decode(sig)
+                .expect("Cannot decode hex")
+                .try_into()
+                .expect("Not 64 bytes");
+            assert_eq!(ed25519.public_key(&secret), public);
+            assert_eq!(ed25519.sign(&secret, &msg), sig);
+            assert!(ed25519.verify(&public, &msg, &sig));
+        }
+    }
+
+    #[test]
+    fn test_ed25519_rejects() {
+        let ed25519 = Ed25519::new();
+        let secret = [7u8; 32];
+        let public = ed25519.public_key(&secret);
+        let sig = ed25519.sign(&secret, b"Hello World!");
+        assert!(ed25519.verify(&public, b"Hello World!", &sig));
+        assert!(!ed25519.verify(&public, b"Hello World?", &sig));
+
+        let mut tampered = sig;
+        tampered[0] ^= 1;
+        assert!(!ed25519.verify(&public, b"Hello World!", &tampered));
+        // S + L is the same scalar but is not accepted
+        let l = TwistedEdwardsCurve::ed25519_order();
+        let s_plus_l = BigUint::from_bytes_le(&sig[32..]) + &l;
+        let mut malleated = sig;
+        malleated[32..].copy_from_slice(&Ed25519::scalar_bytes(&s_plus_l));
+        assert!(!ed25519.verify(&public, b"Hello World!", &malleated));
+        // y = p does not decode
+        let mut bad_key =
+            bytes32("edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f");
+        assert!(!ed25519.verify(&bad_key, b"Hello World!", &sig));
+        bad_key[0] = 0xec;
+        assert!(ed25519.decode(&bad_key).is_some());
+        // encode and decode are inverse
+        let g = TwistedEdwardsCurve::ed25519_generator();
+        assert_eq!(ed25519.decode(&ed25519.encode(&g)), Some(g));
+    }
+
+    #[test]
+    fn test_off_curve() {
+        let curve = TwistedEdwardsCurve::ed25519();
+        let g = TwistedEdwardsCurve::ed25519_generator();
+        let off_curve = Point::Coordinate(BigUint::from(1u32), BigUint::from(1u32));
+        assert!(!curve.is_on_curve(&off_curve));
+        assert!(!curve.is_on_curve(&Point::Identity));
+        assert_eq!(curve.add(&g, &off_curve), Err(EccError::PointNotOnCurve));
+        assert_eq!(
+            curve.scalar_mul(&Point::Identity, &BigUint::from(2u32)),
+            Err(EccError::PointNotOnCurve)
+        );
+        assert_eq!(
+            TwistedEdwardsCurve::new(
+                BigUint::from(3u32),
+                BigUint::from(3u32),
+                BigUint::from(13u32)
+            ),
+            Err(EccError::SingularCurve)
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use alloc::string::String;
+use core::fmt;
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum EccError {
+    InvalidScalar,
+    InvalidEncoding(String),
+    SingularCurve,
+    InvalidPoint,
+    InvalidOrder,
+    PrimeTooLarge,
+    // an operand of the group law is not a reduced point of the curve
+    PointNotOnCurve,
+    // c^-1 mod p with c = 0 mod p
+    NotInvertible,
+    // c / d mod p with d = 0 mod p
+    DivisionByZero,
+    InvalidSignature,
+    // the tag of an ECIES ciphertext does not match its body
+    InvalidMac,
+    // no curve point has the x of a compressed encoding
+    DecompressionFailed,
+    // a public key of small order, or with a small-order component
+    SmallSubgroupPoint,
+    // the field modulus of a curve is not a prime above 3
+    InvalidModulus,
+    // a secret sharing threshold of 0, above the number of shares, or more shares
+    // than the field has non-zero elements
+    InvalidThreshold,
+    // a twisted Edwards curve whose a is not a square or whose d is a square mod p,
+    // its addition law is then not complete
+    IncompleteCurve,
+}
+impl fmt::Display for EccError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EccError::InvalidScalar => write!(f, "scalar out of range"),
+            EccError::InvalidEncoding(reason) => write!(f, "invalid encoding: {}", reason),
+            EccError::SingularCurve => write!(f, "curve is singular"),
+            EccError::InvalidPoint => write!(f, "invalid point"),
+            EccError::InvalidOrder => write!(f, "invalid group order"),
+            EccError::PrimeTooLarge => write!(f, "prime too large"),
+            EccError::PointNotOnCurve => write!(f, "point is not on the curve"),
+            EccError::NotInvertible => write!(f, "value is not invertible"),
+            EccError::DivisionByZero => write!(f, "division by zero"),
+            EccError::InvalidSignature => write!(f, "invalid signature"),
+            EccError::InvalidMac => write!(f, "message authentication failed"),
+            EccError::DecompressionFailed => write!(f, "point decompression failed"),
+            EccError::SmallSubgroupPoint => write!(f, "point is in a small subgroup"),
+            EccError::InvalidModulus => write!(f, "modulus is not a prime above 3"),
+            EccError::InvalidThreshold => write!(f, "invalid threshold or number of shares"),
+            EccError::IncompleteCurve => write!(f, "addition law is not complete"),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for EccError {}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use crate::EllipticCurve;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            EccError::PointNotOnCurve.to_string(),
+            "point is not on the curve"
+        );
+        assert_eq!(
+            EccError::InvalidEncoding("trailing bytes".to_string()).to_string(),
+            "invalid encoding: trailing bytes"
+        );
+    }
+
+    #[test]
+    fn test_downcast() {
+        fn singular() -> Result<EllipticCurve, Box<dyn std::error::Error>> {
+            let ec = EllipticCurve::new(
+                BigUint::from(0u32),
+                BigUint::from(0u32),
+                BigUint::from(17u32),
+            )?;
+            Ok(ec)
+        }
+        let err = singular().expect_err("y^2 = x^3 is singular");
+        assert_eq!(err.to_string(), "curve is singular");
+        assert_eq!(
+            err.downcast_ref::<EccError>(),
+            Some(&EccError::SingularCurve)
+        );
+    }
+}
@@ -0,0 +1,362 @@
+use crate::{EccError, EllipticCurve, FiniteField, Point};
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+
+impl EllipticCurve {
+    // RFC 9380 hash_to_curve, random oracle flavour, with expand_message_xmd over SHA-256
+    // u0, u1 = hash_to_field(msg, 2)
+    // Q0 = map_to_curve(u0), Q1 = map_to_curve(u1)
+    // P = clear_cofactor(Q0 + Q1)
+    // map_to_curve is the simplified SWU map, which needs a and b non-zero; on P-256
+    // this is P256_XMD:SHA-256_SSWU_RO_. Curves with a = 0 or b = 0 (secp256k1, whose
+    // SWU map goes through an isogeny) and the small curves where no Z fits fall back
+    // to try-and-increment over expand_message_xmd(msg || counter). Nothing here is
+    // constant-time
+    // fails with InvalidEncoding when dst is longer than 255 bytes
+    pub fn hash_to_curve(&self, msg: &[u8], dst: &[u8]) -> Result<Point, EccError> {
+        let point = match self.sswu_z() {
+            Some(z) => {
+                let u = self.hash_to_field(msg, dst, 2)?;
+                let q0 = self.map_to_curve_sswu(&u[0], &z);
+                let q1 = self.map_to_curve_sswu(&u[1], &z);
+                self.add(&q0, &q1)?
+            }
+            None => self.try_and_increment(msg, dst)?,
+        };
+        self.clear_cofactor(&point)
+    }
+    // count elements of [0, p), each reduced from L = ceil((bits(p) + 128) / 8) bytes
+    // so that the bias of the reduction is below 2^-128
+    fn hash_to_field(
+        &self,
+        msg: &[u8],
+        dst: &[u8],
+        count: usize,
+    ) -> Result<Vec<BigUint>, EccError> {
+        let len = (self.p.bits() as usize + 128).div_ceil(8);
+        let uniform = expand_message_xmd(msg, dst, count * len)?;
+        Ok(uniform
+            .chunks(len)
+            .map(|chunk| BigUint::from_bytes_be(chunk) % &self.p)
+            .collect())
+    }
+    // RFC 9380 section 6.6.2
+    // tv1 = 1 / (Z^2 u^4 + Z u^2), 0 when the denominator is
+    // x1 = (-b / a) (1 + tv1), or b / (Z a) when tv1 = 0
+    // x2 = Z u^2 x1, and g(x2) = Z^3 u^6 g(x1) is a square when g(x1) is not
+    // y = sqrt(g(x)) for the x that works, with the parity of u
+    fn map_to_curve_sswu(&self, u: &BigUint, z: &BigUint) -> Point {
+        let p = &self.p;
+        let zu2 = FiniteField::mult(z, &FiniteField::mult(u, u, p), p);
+        let denominator = FiniteField::add(&FiniteField::mult(&zu2, &zu2, p), &zu2, p);
+        let x1 = if denominator == BigUint::from(0u32) {
+            FiniteField::divide(&self.b, &FiniteField::mult(z, &self.a, p), p)
+                .expect("Z and a are non-zero")
+        } else {
+            let tv1 = FiniteField::inv(&denominator, p).expect("denominator is non-zero");
+            let minus_b_over_a = FiniteField::divide(&FiniteField::neg(&self.b, p), &self.a, p)
+                .expect("a is non-zero");
+            FiniteField::mult(
+                &minus_b_over_a,
+                &FiniteField::add(&BigUint::from(1u32), &tv1, p),
+                p,
+            )
+        };
+        let (x, y) = match FiniteField::sqrt(&self.rhs(&x1), p) {
+            Some(y) => (x1, y),
+            None => {
+                let x2 = FiniteField::mult(&zu2, &x1, p);
+                let y = FiniteField::sqrt(&self.rhs(&x2), p)
+                    .expect("g(x2) is a square when g(x1) is not");
+                (x2, y)
+            }
+        };
+        // sgn0 of an element of a prime field is its parity
+        let y = if u.bit(0) != y.bit(0) {
+            FiniteField::neg(&y, p)
+        } else {
+            y
+        };
+        Point::Coordinate(x, y)
+    }
+    // RFC 9380 appendix H.2, the first of 1, -1, 2, -2, ... that is
+    // - not a square and not -1
+    // - such that x^3 + ax + b - Z has no root, i.e. is irreducible
+    // - such that g(b / (Z a)) is a square
+    // None when a or b is 0, or when every candidate fails, which only happens for
+    // small p
+    fn sswu_z(&self) -> Option<BigUint> {
+        let p = &self.p;
+        let zero = BigUint::from(0u32);
+        if &self.a % p == zero || &self.b % p == zero {
+            return None;
+        }
+        let mut ctr = BigUint::from(1u32);
+        while ctr <= p >> 1 {
+            for z in [ctr.clone(), FiniteField::neg(&ctr, p)] {
+                if FiniteField::is_quadratic_residue(&z, p) || z == p - BigUint::from(1u32) {
+                    continue;
+                }
+                // the root search is by far the slowest test, it goes last
+                let x = FiniteField::divide(&self.b, &FiniteField::mult(&z, &self.a, p), p)
+                    .expect("Z and a are non-zero");
+                if !FiniteField::is_quadratic_residue(&self.rhs(&x), p) {
+                    continue;
+                }
+                let constant = FiniteField::subtract(&self.b, &z, p);
+                if !cubic_has_root(&self.a, &constant, p) {
+                    return Some(z);
+                }
+            }
+            ctr += 1u32;
+        }
+        None
+    }
+    // for counter in 0, 1, ...
+    //     x = hash_to_field(msg || counter)
+    //     if g(x) is a square: return (x, the even root)
+    fn try_and_increment(&self, msg: &[u8], dst: &[u8]) -> Result<Point, EccError> {
+        let mut counter = 0u32;
+        loop {
+            let input = [msg, &counter.to_be_bytes()].concat();
+            let x = self.hash_to_field(&input, dst, 1)?.remove(0);
+            if let Some(y) = FiniteField::sqrt(&self.rhs(&x), &self.p) {
+                let y = if y.bit(0) {
+                    FiniteField::neg(&y, &self.p)
+                } else {
+                    y
+                };
+                return Ok(Point::Coordinate(x, y));
+            }
+            counter += 1;
+        }
+    }
+}
+
+// RFC 9380 section 5.3.1 with SHA-256
+// b0 = H(0^64 || msg || I2OSP(len, 2) || 0 || dst')
+// b1 = H(b0 || 1 || dst'), bi = H((b0 XOR b(i-1)) || i || dst')
+// with dst' = dst || I2OSP(len(dst), 1), and the output is b1 || b2 || ... cut to len
+// fails with InvalidEncoding when dst is longer than 255 bytes or len is above 255 blocks
+pub(crate) fn expand_message_xmd(msg: &[u8], dst: &[u8], len: usize) -> Result<Vec<u8>, EccError> {
+    let blocks = len.div_ceil(32);
+    if blocks > 255 {
+        return Err(EccError::InvalidEncoding(format!(
+            "cannot expand to {} bytes",
+            len
+        )));
+    }
+    if dst.len() > 255 {
+        return Err(EccError::InvalidEncoding(format!(
+            "domain separation tag of {} bytes",
+            dst.len()
+        )));
+    }
+    let dst_prime = [dst, &[dst.len() as u8]].concat();
+
+    let b0 = Sha256::new()
+        .chain_update([0u8; 64])
+        .chain_update(msg)
+        .chain_update((len as u16).to_be_bytes())
+        .chain_update([0u8])
+        .chain_update(&dst_prime)
+        .finalize();
+    let mut bi = Sha256::new()
+        .chain_update(b0)
+        .chain_update([1u8])
+        .chain_update(&dst_prime)
+        .finalize();
+    let mut out = bi.to_vec();
+    for i in 2..=blocks {
+        let xored: Vec<u8> = b0.iter().zip(bi.iter()).map(|(a, b)| a ^ b).collect();
+        bi = Sha256::new()
+            .chain_update(xored)
+            .chain_update([i as u8])
+            .chain_update(&dst_prime)
+            .finalize();
+        out.extend_from_slice(&bi);
+    }
+    out.truncate(len);
+    Ok(out)
+}
+
+// whether x^3 + c1 x + c0 has a root mod p, i.e. gcd(x^3 + c1 x + c0, x^p - x) != 1
+fn cubic_has_root(c1: &BigUint, c0: &BigUint, p: &BigUint) -> bool {
+    // x^p mod f by square-and-multiply on polynomials of degree < 3,
+    // coefficients from the constant term up
+    let zero = BigUint::from(0u32);
+    let mut r = vec![BigUint::from(1u32), zero.clone(), zero.clone()];
+    for i in (0..p.bits()).rev() {
+        r = cubic_mulmod(&r, &r, c1, c0, p);
+        if p.bit(i) {
+            // r x, with x^3 = -c1 x - c0
+            let top = r[2].clone();
+            r = vec![
+                FiniteField::neg(&FiniteField::mult(c0, &top, p), p),
+                FiniteField::subtract(&r[0], &FiniteField::mult(c1, &top, p), p),
+                r[1].clone(),
+            ];
+        }
+    }
+    r[1] = FiniteField::subtract(&r[1], &BigUint::from(1u32), p);
+
+    let f = vec![c0 % p, c1 % p, zero, BigUint::from(1u32)];
+    poly_degree(&poly_gcd(f, r, p)) > 0
+}
+
+// a b mod x^3 + c1 x + c0 for a and b of degree < 3
+fn cubic_mulmod(
+    a: &[BigUint],
+    b: &[BigUint],
+    c1: &BigUint,
+    c0: &BigUint,
+    p: &BigUint,
+) -> Vec<BigUint> {
+    let mut d = vec![BigUint::from(0u32); 5];
+    for i in 0..3 {
+        for j in 0..3 {
+            d[i + j] = FiniteField::add(&d[i + j], &FiniteField::mult(&a[i], &b[j], p), p);
+        }
+    }
+    // x^4 = -c1 x^2 - c0 x, then x^3 = -c1 x - c0
+    for k in [4, 3] {
+        let top = d[k].clone();
+        d[k - 2] = FiniteField::subtract(&d[k - 2], &FiniteField::mult(c1, &top, p), p);
+        d[k - 3] = FiniteField::subtract(&d[k - 3], &FiniteField::mult(c0, &top, p), p);
+    }
+    d.truncate(3);
+    d
+}
+
+// -1 for the zero polynomial
+fn poly_degree(a: &[BigUint]) -> isize {
+    let zero = BigUint::from(0u32);
+    a.iter()
+        .rposition(|c| c != &zero)
+        .map_or(-1, |d| d as isize)
+}
+
+fn poly_gcd(mut a: Vec<BigUint>, mut b: Vec<BigUint>, p: &BigUint) -> Vec<BigUint> {
+    while poly_degree(&b) >= 0 {
+        // a mod b
+        let db = poly_degree(&b) as usize;
+        let lead_inv = FiniteField::inv(&b[db], p).expect("the leading coefficient is non-zero");
+        while poly_degree(&a) >= db as isize {
+            let da = poly_degree(&a) as usize;
+            let factor = FiniteField::mult(&a[da], &lead_inv, p);
+            for i in 0..=db {
+                let term = FiniteField::mult(&factor, &b[i], p);
+                a[da - db + i] = FiniteField::subtract(&a[da - db + i], &term, p);
+            }
+        }
+        core::mem::swap(&mut a, &mut b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const P256_DST: &[u8] = b"QUUX-V01-CS02-with-P256_XMD:SHA-256_SSWU_RO_";
+
+    #[test]
+    fn test_expand_message_xmd() {
+        // RFC 9380 appendix K.1
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        assert_eq!(
+            expand_message_xmd(b"", dst, 0x20).map(hex::encode),
+            Ok("68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235".to_string())
+        );
+        assert_eq!(
+            expand_message_xmd(b"abc", dst, 0x20).map(hex::encode),
+            Ok("d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615".to_string())
+        );
+        assert_eq!(
+            expand_message_xmd(b"abc", dst, 0x80).map(|out| out.len()),
+            Ok(0x80)
+        );
+
+        assert!(matches!(
+            expand_message_xmd(b"abc", &[0; 256], 32),
+            Err(EccError::InvalidEncoding(_))
+        ));
+        assert!(matches!(
+            expand_message_xmd(b"abc", dst, 256 * 32),
+            Err(EccError::InvalidEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_hash_to_curve_p256() {
+        let ec = EllipticCurve::p256();
+        // RFC 9380 section 8.2 picks Z = -10 for P-256
+        assert_eq!(ec.sswu_z(), Some(&ec.p - BigUint::from(10u32)));
+        assert_eq!(EllipticCurve::secp256k1().sswu_z(), None);
+
+        // RFC 9380 appendix J.1.1
+        for (msg, x, y) in [
+            (
+                &b""[..],
+                "2c15230b26dbc6fc9a37051158c95b79656e17a1a920b11394ca91c44247d3e4",
+                "8a7a74985cc5c776cdfe4b1f19884970453912e9d31528c060be9ab5c43e8415",
+            ),
+            (
+                &b"abc"[..],
+                "0bb8b87485551aa43ed54f009230450b492fead5f1cc91658775dac4a3388a0f",
+                "5c41b3d0731a27a7b14bc0bf0ccded2d8751f83493404c84a88e71ffd424212e",
+            ),
+        ] {
+            let expected = Point::from_hex(x, y).expect("Cannot parse point");
+            assert_eq!(ec.hash_to_curve(msg, P256_DST), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn test_hash_to_curve() {
+        // secp256k1 and y^2 = x^3 + 2x + 2 mod 17 (no Z fits) go through try-and-increment
+        let toy = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        assert_eq!(toy.sswu_z(), None);
+        let msgs: [&[u8]; 5] = [b"", b"a", b"abc", b"Hello World!", &[0xff; 100]];
+        for ec in [toy, EllipticCurve::secp256k1(), EllipticCurve::p256()] {
+            let mut seen = Vec::new();
+            for msg in msgs {
+                let c = ec
+                    .hash_to_curve(msg, b"ecc_rust-test")
+                    .expect("Could not hash");
+                assert!(ec.is_on_curve(&c));
+                assert_eq!(ec.hash_to_curve(msg, b"ecc_rust-test"), Ok(c.clone()));
+                // another tag gives another point
+                if ec.p.bits() > 8 {
+                    assert_ne!(ec.hash_to_curve(msg, b"ecc_rust-other"), Ok(c.clone()));
+                    assert!(!seen.contains(&c));
+                }
+                seen.push(c);
+            }
+        }
+
+        assert!(matches!(
+            EllipticCurve::secp256k1().hash_to_curve(b"abc", &[0; 256]),
+            Err(EccError::InvalidEncoding(_))
+        ));
+    }
+
+    #[test]
+    fn test_cubic_has_root() {
+        let p = BigUint::from(17u32);
+        // x^3 + 2x + 2 - Z mod 17 against a brute-force search for a root
+        for z in 0u32..17 {
+            let c0 = FiniteField::subtract(&BigUint::from(2u32), &BigUint::from(z), &p);
+            let brute = (0u32..17).any(|x| (x * x * x + 2 * x + 17 * 2 + 2 - z) % 17 == 0);
+            assert_eq!(cubic_has_root(&BigUint::from(2u32), &c0, &p), brute);
+        }
+    }
+}
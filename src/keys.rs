@@ -0,0 +1,233 @@
+use crate::{EccError, EllipticCurve, Point};
+use num_bigint::BigUint;
+
+// a secret value such as a private key or a nonce. With the zeroize feature its limbs
+// are overwritten with zeros when it is dropped, and it can be held in a
+// zeroize::Zeroizing like any other Zeroize type
+#[derive(PartialEq, Debug, Clone)]
+pub struct SecretScalar(BigUint);
+
+// d in [1, q)
+#[derive(PartialEq, Debug, Clone)]
+pub struct PrivateKey(SecretScalar);
+
+// a point on the curve other than the identity
+#[derive(PartialEq, Debug, Clone)]
+pub struct PublicKey(Point);
+
+impl SecretScalar {
+    pub fn new(val: BigUint) -> SecretScalar {
+        SecretScalar(val)
+    }
+    pub fn scalar(&self) -> &BigUint {
+        &self.0
+    }
+    // clears the bits from the lowest up: the top limb stays non-zero until the
+    // last one, so num-bigint never shrinks the buffer while secret limbs are in it
+    pub fn zeroize(&mut self) {
+        for i in 0..self.0.bits() {
+            self.0.set_bit(i, false);
+        }
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for SecretScalar {
+    fn zeroize(&mut self) {
+        SecretScalar::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SecretScalar {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for SecretScalar {}
+
+impl PrivateKey {
+    pub fn new(val: BigUint, order: &BigUint) -> Result<PrivateKey, EccError> {
+        let val = SecretScalar::new(val);
+        if !scalar_in_range(val.scalar(), order) {
+            return Err(EccError::InvalidScalar);
+        }
+        Ok(PrivateKey(val))
+    }
+    pub fn scalar(&self) -> &BigUint {
+        self.0.scalar()
+    }
+    // wipes d now instead of on drop, the key is 0 afterwards and signs nothing
+    pub fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for PrivateKey {
+    fn zeroize(&mut self) {
+        PrivateKey::zeroize(self);
+    }
+}
+
+// d is a SecretScalar, wiped when the key is dropped
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for PrivateKey {}
+
+impl PublicKey {
+    pub fn new(point: Point, ec: &EllipticCurve) -> Result<PublicKey, EccError> {
+        let pub_key = PublicKey(point);
+        if !pub_key.is_valid_on(ec) {
+            return Err(EccError::InvalidPoint);
+        }
+        Ok(pub_key)
+    }
+    pub fn point(&self) -> &Point {
+        &self.0
+    }
+    // reduced coordinates on ec, not the identity. A key built for one curve
+    // is not necessarily valid on another
+    pub fn is_valid_on(&self, ec: &EllipticCurve) -> bool {
+        match &self.0 {
+            Point::Identity => false,
+            Point::Coordinate(x, y) => x < &ec.p && y < &ec.p && ec.is_on_curve(&self.0),
+        }
+    }
+}
+
+// 1 <= d < q without branching on the limbs of d: d is padded to as many limbs as q,
+// the borrow of d - q is carried limb by limb, and the non-zero test and the limbs of
+// d above the width of q are ORed together, so the work done only depends on the
+// lengths of q and d and never stops early
+pub(crate) fn scalar_in_range(d: &BigUint, q: &BigUint) -> bool {
+    let q = q.to_u64_digits();
+    let mut d = d.to_u64_digits();
+    d.resize(d.len().max(q.len()), 0);
+
+    let mut borrow = 0u64;
+    let mut any = 0u64;
+    for (d, q) in d.iter().zip(&q) {
+        let (diff, b1) = d.overflowing_sub(*q);
+        let (_, b2) = diff.overflowing_sub(borrow);
+        borrow = (b1 | b2) as u64;
+        any |= *d;
+    }
+    let high = d.iter().skip(q.len()).fold(0u64, |acc, d| acc | d);
+    // 1 when any is non-zero, 1 when high is zero
+    let non_zero = (any | any.wrapping_neg()) >> 63;
+    let fits = 1 ^ ((high | high.wrapping_neg()) >> 63);
+    (borrow & non_zero & fits) == 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_scalar_in_range() {
+        let q = EllipticCurve::secp256k1_order();
+        let one = BigUint::from(1u32);
+        assert!(scalar_in_range(&one, &q));
+        assert!(scalar_in_range(&(&q - &one), &q));
+        assert!(!scalar_in_range(&BigUint::from(0u32), &q));
+        assert!(!scalar_in_range(&q, &q));
+        assert!(!scalar_in_range(&(&q + &one), &q));
+        assert!(!scalar_in_range(&(&q << 8), &q));
+        // limbs above the width of q, with the low limbs in range or all zero
+        assert!(!scalar_in_range(&((&one << 320) + &one), &q));
+        assert!(!scalar_in_range(&(&one << 256), &q));
+
+        let q = BigUint::from(19u32);
+        for k in 0u32..40 {
+            assert_eq!(scalar_in_range(&BigUint::from(k), &q), (1..19).contains(&k));
+        }
+    }
+
+    #[test]
+    fn test_private_key_new() {
+        let q = BigUint::from(19u32);
+        assert!(PrivateKey::new(BigUint::from(1u32), &q).is_ok());
+        assert_eq!(
+            PrivateKey::new(BigUint::from(18u32), &q).map(|d| d.scalar().clone()),
+            Ok(BigUint::from(18u32))
+        );
+        assert_eq!(
+            PrivateKey::new(BigUint::from(0u32), &q),
+            Err(EccError::InvalidScalar)
+        );
+        assert_eq!(
+            PrivateKey::new(BigUint::from(19u32), &q),
+            Err(EccError::InvalidScalar)
+        );
+    }
+
+    #[test]
+    fn test_secret_scalar_zeroize() {
+        let q = EllipticCurve::secp256k1_order();
+        let mut secret = SecretScalar::new(&q - BigUint::from(1u32));
+        assert_eq!(secret.scalar(), &(&q - BigUint::from(1u32)));
+        secret.zeroize();
+        assert_eq!(secret.scalar(), &BigUint::from(0u32));
+        secret.zeroize();
+        assert_eq!(secret.scalar(), &BigUint::from(0u32));
+    }
+
+    #[test]
+    fn test_private_key_zeroize() {
+        let q = EllipticCurve::secp256k1_order();
+        let mut priv_key = PrivateKey::new(&q - BigUint::from(2u32), &q).expect("d is in range");
+        priv_key.zeroize();
+        assert_eq!(priv_key.scalar(), &BigUint::from(0u32));
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_private_key_zeroizing() {
+        use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+        fn wiped_on_drop<T: ZeroizeOnDrop>() {}
+        wiped_on_drop::<SecretScalar>();
+        wiped_on_drop::<PrivateKey>();
+
+        let q = EllipticCurve::secp256k1_order();
+        let d = &q - BigUint::from(2u32);
+        let mut priv_key = Zeroizing::new(PrivateKey::new(d.clone(), &q).expect("d is in range"));
+        assert_eq!(priv_key.scalar(), &d);
+        // a clone has its own limbs, wiping one leaves the other
+        let copy = priv_key.clone();
+        priv_key.zeroize();
+        assert_eq!(priv_key.scalar(), &BigUint::from(0u32));
+        assert_eq!(copy.scalar(), &d);
+
+        let mut secret = SecretScalar::new(d);
+        Zeroize::zeroize(&mut secret);
+        assert_eq!(secret.scalar(), &BigUint::from(0u32));
+    }
+
+    #[test]
+    fn test_public_key_new() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let point = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let pub_key = PublicKey::new(point.clone(), &ec).expect("(5, 1) is on the curve");
+        assert_eq!(pub_key.point(), &point);
+
+        assert_eq!(
+            PublicKey::new(Point::Identity, &ec),
+            Err(EccError::InvalidPoint)
+        );
+        let off_curve = Point::Coordinate(BigUint::from(5u32), BigUint::from(2u32));
+        assert_eq!(PublicKey::new(off_curve, &ec), Err(EccError::InvalidPoint));
+        // (5 + 17, 1) is congruent to (5, 1) but not reduced
+        let unreduced = Point::Coordinate(BigUint::from(22u32), BigUint::from(1u32));
+        assert_eq!(PublicKey::new(unreduced, &ec), Err(EccError::InvalidPoint));
+    }
+}
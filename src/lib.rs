@@ -1,79 +1,822 @@
-use core::num;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+extern crate alloc;
+
+pub mod bip32;
+pub mod curve;
+pub mod curve_point;
+pub mod ecdh;
 pub mod ecdsa;
-use num_bigint::BigUint;
-#[derive(PartialEq, Debug, Clone)]
-enum Point {
+pub mod ecies;
+pub mod edwards;
+mod error;
+mod hash_to_curve;
+pub mod keys;
+pub mod montgomery;
+pub mod pairing;
+pub mod pedersen;
+mod rng;
+pub mod schnorr;
+pub mod secret_sharing;
+#[cfg(feature = "serde")]
+mod serialize;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::str::FromStr;
+use ecdsa::{der_read, der_read_integer};
+pub use error::EccError;
+use num_bigint::{BigInt, BigUint, Sign};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+// Hash and Eq compare the coordinates as stored, so (5, 1) and the unreduced
+// (22, 1) are different keys. EllipticCurve::points_equal compares them mod p
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+pub enum Point {
     Coordinate(BigUint, BigUint),
     Identity,
 }
-struct EllipticCurve {
+impl Point {
+    // x and y in hex, with or without a 0x prefix
+    pub fn from_hex(x: &str, y: &str) -> Result<Point, EccError> {
+        Ok(Point::Coordinate(parse_hex(x)?, parse_hex(y)?))
+    }
+    // == without stopping at the first differing limb, see ct_eq_biguint. Whether a
+    // point is the identity is not hidden
+    pub fn ct_eq(&self, other: &Point) -> bool {
+        match (self, other) {
+            (Point::Identity, Point::Identity) => true,
+            (Point::Coordinate(x1, y1), Point::Coordinate(x2, y2)) => {
+                // & rather than && so that y is compared even when x differs
+                ct_eq_biguint(x1, x2) & ct_eq_biguint(y1, y2)
+            }
+            _ => false,
+        }
+    }
+}
+// c == d with every limb XORed and ORed into one word, so the time taken only
+// depends on the number of limbs of the longer one, not on where c and d differ
+pub(crate) fn ct_eq_biguint(c: &BigUint, d: &BigUint) -> bool {
+    let c = c.to_u64_digits();
+    let d = d.to_u64_digits();
+    let mut diff = 0u64;
+    for i in 0..c.len().max(d.len()) {
+        diff |= c.get(i).copied().unwrap_or(0) ^ d.get(i).copied().unwrap_or(0);
+    }
+    diff == 0
+}
+// the identity first, then by x and by y
+impl Ord for Point {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Point::Identity, Point::Identity) => Ordering::Equal,
+            (Point::Identity, _) => Ordering::Less,
+            (_, Point::Identity) => Ordering::Greater,
+            (Point::Coordinate(x1, y1), Point::Coordinate(x2, y2)) => (x1, y1).cmp(&(x2, y2)),
+        }
+    }
+}
+impl PartialOrd for Point {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+// "(0x5, 0x1)" and "Identity", coordinates in hex
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Point::Coordinate(x, y) => write!(f, "({:#x}, {:#x})", x, y),
+            Point::Identity => write!(f, "Identity"),
+        }
+    }
+}
+impl FromStr for Point {
+    type Err = EccError;
+
+    // "x,y" with both coordinates in hex as in from_hex, or "identity", and the
+    // Display format
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "identity" || s == "Identity" {
+            return Ok(Point::Identity);
+        }
+        let inner = s
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .unwrap_or(s);
+        match inner.split_once(',') {
+            Some((x, y)) => Point::from_hex(x.trim(), y.trim()),
+            None => Err(EccError::InvalidEncoding(format!("invalid point: {}", s))),
+        }
+    }
+}
+// a scalar in hex, with or without a 0x prefix
+pub fn scalar_from_hex(s: &str) -> Result<BigUint, EccError> {
+    parse_hex(s)
+}
+// Miller-Rabin with the first 12 primes as bases, exact below 3.1 * 10^23. Above that
+// the bases are fixed and known, so a composite can be built to pass all of them: this
+// is fine for moduli the caller trusts, validate_parameters adds bases derived from n
+// for the ones that come from someone else
+// n - 1 = d 2^s with d odd
+// n passes for base a when a^d = 1 or a^(d 2^r) = -1 for some r < s
+pub(crate) fn is_probable_prime(n: &BigUint) -> bool {
+    is_probable_prime_with_rounds(n, 0)
+}
+// is_probable_prime, then rounds more bases taken from sha256(n || i). They depend
+// on n, so a composite cannot be built to pass them as it can for the fixed ones,
+// and it gets through all of them with odds below 4^-rounds
+pub(crate) fn is_probable_prime_with_rounds(n: &BigUint, rounds: u32) -> bool {
+    const BASES: [u32; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    let one = BigUint::from(1u32);
+    if n <= &one {
+        return false;
+    }
+    for base in BASES {
+        let base = BigUint::from(base);
+        if n == &base {
+            return true;
+        }
+        if n % &base == BigUint::from(0u32) {
+            return false;
+        }
+    }
+    let n_minus_one = n - &one;
+    let s = n_minus_one.trailing_zeros().expect("n - 1 is not zero");
+    let d = &n_minus_one >> s;
+    let passes = |base: &BigUint| {
+        let mut x = base.modpow(&d, n);
+        if x == one || x == n_minus_one {
+            return true;
+        }
+        for _ in 1..s {
+            x = FiniteField::mult(&x, &x, n);
+            if x == n_minus_one {
+                return true;
+            }
+        }
+        false
+    };
+    if !BASES.iter().all(|base| passes(&BigUint::from(*base))) {
+        return false;
+    }
+    // n > 37 here, bases in [2, n - 2]
+    let n_bytes = n.to_bytes_be();
+    let range = n - BigUint::from(3u32);
+    (0..rounds).all(|i| {
+        let digest = Sha256::new()
+            .chain_update(&n_bytes)
+            .chain_update(i.to_be_bytes())
+            .finalize();
+        passes(&(BigUint::from_bytes_be(&digest) % &range + 2u32))
+    })
+}
+// a and b are positive
+fn lcm(a: &BigUint, b: &BigUint) -> BigUint {
+    let (mut x, mut y) = (a.clone(), b.clone());
+    while y != BigUint::from(0u32) {
+        let r = &x % &y;
+        x = y;
+        y = r;
+    }
+    a / x * b
+}
+// hex digits only, an optional 0x prefix and nothing else, not even the underscores
+// that parse_bytes lets through
+pub(crate) fn parse_hex(s: &str) -> Result<BigUint, EccError> {
+    let digits = s
+        .strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s);
+    if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err(EccError::InvalidEncoding(format!(
+            "invalid hex number: {}",
+            s
+        )));
+    }
+    Ok(BigUint::parse_bytes(digits.as_bytes(), 16).expect("digits are hex"))
+}
+#[derive(PartialEq, Debug, Clone)]
+pub struct EllipticCurve {
     // y2 = x3 + ax + b
-    a: BigUint,
-    b: BigUint,
-    p: BigUint,
+    pub a: BigUint,
+    pub b: BigUint,
+    pub p: BigUint,
+    // #E / n, the number of points over the order of the subgroup in use
+    pub cofactor: BigUint,
 }
 impl EllipticCurve {
-    fn add(self: &Self, c: &Point, d: &Point) -> Point {
-        assert!(c != d, "Points must be different");
-        assert!(self.is_on_curve(&c), "Point is not on curve");
-        assert!(self.is_on_curve(&d), "Point is not on curve");
+    // fails with InvalidModulus when p is not a prime above 3 and with SingularCurve
+    // when 4a^3 + 27b^2 = 0 mod p
+    // cofactor 1, as for secp256k1 and P-256
+    pub fn new(a: BigUint, b: BigUint, p: BigUint) -> Result<Self, EccError> {
+        Self::with_cofactor(a, b, p, BigUint::from(1u32))
+    }
+    // a, b and p in hex, with or without a 0x prefix
+    pub fn from_hex(a: &str, b: &str, p: &str) -> Result<Self, EccError> {
+        EllipticCurve::new(parse_hex(a)?, parse_hex(b)?, parse_hex(p)?)
+    }
+    // SEC1 explicit ECParameters over a prime field, as written by
+    // openssl ecparam -param_enc explicit -outform DER
+    // SEQUENCE {
+    //     version INTEGER (1),
+    //     fieldID SEQUENCE { OID prime-field, INTEGER p },
+    //     curve SEQUENCE { OCTET STRING a, OCTET STRING b, BIT STRING seed OPTIONAL },
+    //     base OCTET STRING (04 || x || y),
+    //     order INTEGER,
+    //     cofactor INTEGER OPTIONAL
+    // }
+    // returns (curve, generator, order). Fails with InvalidEncoding on anything else,
+    // compressed base points included, and InvalidPoint when the base is off the curve
+    pub fn from_der(bytes: &[u8]) -> Result<(EllipticCurve, Point, BigUint), EccError> {
+        // 1.2.840.10045.1.1
+        const PRIME_FIELD: [u8; 7] = [0x2a, 0x86, 0x48, 0xce, 0x3d, 0x01, 0x01];
+        let invalid = |what: &str| EccError::InvalidEncoding(format!("ECParameters: {}", what));
+        let expect = |bytes, tag, what: &str| -> Result<(&[u8], &[u8]), EccError> {
+            match der_read(bytes)? {
+                (t, value, rest) if t == tag => Ok((value, rest)),
+                _ => Err(invalid(what)),
+            }
+        };
+
+        let (params, rest) = expect(bytes, 0x30, "expected a SEQUENCE")?;
+        if !rest.is_empty() {
+            return Err(invalid("trailing bytes"));
+        }
+        let (version, params) = der_read_integer(params)?;
+        if version != BigUint::from(1u32) {
+            return Err(invalid("unsupported version"));
+        }
+
+        let (field, params) = expect(params, 0x30, "expected the fieldID")?;
+        let (oid, field) = expect(field, 0x06, "expected the field type")?;
+        if oid != PRIME_FIELD {
+            return Err(invalid("not a prime field"));
+        }
+        let (p, field) = der_read_integer(field)?;
+        if !field.is_empty() {
+            return Err(invalid("trailing bytes in the fieldID"));
+        }
+
+        let (curve, params) = expect(params, 0x30, "expected the curve")?;
+        let (a, curve) = expect(curve, 0x04, "expected a")?;
+        let (b, curve) = expect(curve, 0x04, "expected b")?;
+        if !curve.is_empty() {
+            expect(curve, 0x03, "expected the seed")?;
+        }
+
+        let (base, params) = expect(params, 0x04, "expected the base point")?;
+        let width = (p.bits() as usize).div_ceil(8);
+        if base.len() != 1 + 2 * width || base[0] != 0x04 {
+            return Err(invalid("base point is not 04 || x || y"));
+        }
+        let generator = Point::Coordinate(
+            BigUint::from_bytes_be(&base[1..1 + width]),
+            BigUint::from_bytes_be(&base[1 + width..]),
+        );
+
+        let (order, params) = der_read_integer(params)?;
+        let cofactor = if params.is_empty() {
+            BigUint::from(1u32)
+        } else {
+            let (cofactor, params) = der_read_integer(params)?;
+            if !params.is_empty() {
+                return Err(invalid("trailing bytes"));
+            }
+            cofactor
+        };
+
+        let ec = EllipticCurve::with_cofactor(
+            BigUint::from_bytes_be(a),
+            BigUint::from_bytes_be(b),
+            p,
+            cofactor,
+        )?;
+        let reduced = matches!(&generator, Point::Coordinate(x, y) if x < &ec.p && y < &ec.p);
+        if !reduced || !ec.is_on_curve(&generator) {
+            return Err(EccError::InvalidPoint);
+        }
+        Ok((ec, generator, order))
+    }
+    pub fn with_cofactor(
+        a: BigUint,
+        b: BigUint,
+        p: BigUint,
+        cofactor: BigUint,
+    ) -> Result<Self, EccError> {
+        if cofactor == BigUint::from(0u32) {
+            return Err(EccError::InvalidScalar);
+        }
+        if p <= BigUint::from(3u32) || !is_probable_prime(&p) {
+            return Err(EccError::InvalidModulus);
+        }
+        let ec = EllipticCurve { a, b, p, cofactor };
+        if !ec.is_nonsingular() {
+            return Err(EccError::SingularCurve);
+        }
+        Ok(ec)
+    }
+    // p is a prime above 3 and the curve is non-singular, which new and with_cofactor
+    // check. The fields are public, so a curve can still be put together by hand
+    pub fn is_valid(&self) -> bool {
+        self.p > BigUint::from(3u32) && is_probable_prime(&self.p) && self.is_nonsingular()
+    }
+    // is_valid with a reason: InvalidModulus unless p is a prime above 3, SingularCurve
+    // unless the curve is non-singular. On top of the fixed bases of new, p goes
+    // through 128 more Miller-Rabin rounds, which a composite passes with odds below
+    // 2^-256, so this is the check for parameters that come from someone else
+    pub fn validate_parameters(&self) -> Result<(), EccError> {
+        if self.p <= BigUint::from(3u32) || !is_probable_prime_with_rounds(&self.p, 128) {
+            return Err(EccError::InvalidModulus);
+        }
+        if !self.is_nonsingular() {
+            return Err(EccError::SingularCurve);
+        }
+        Ok(())
+    }
+    // non-singular iff the discriminant 4a^3 + 27b^2 is non-zero mod p, otherwise the
+    // cubic has a double root and the chord-and-tangent law is not a group law
+    pub fn is_nonsingular(&self) -> bool {
+        let a3 = self.a.modpow(&BigUint::from(3u32), &self.p);
+        let b2 = self.b.modpow(&BigUint::from(2u32), &self.p);
+        let disc = (BigUint::from(4u32) * a3 + BigUint::from(27u32) * b2) % &self.p;
+        disc != BigUint::from(0u32)
+    }
+    // smallest k > 0 with kP = I
+    // curve_order_hint must be a multiple of that order, the number of points on the
+    // curve always is. Its prime factors are peeled off one at a time while the
+    // quotient still sends P to I. Trial division goes up to 2^16 and whatever is left
+    // is taken as prime: always true for small hints, and for the usual h * n of a
+    // cryptographic curve with prime n
+    // fails with InvalidOrder when the hint does not send p to I
+    pub fn point_order(&self, p: &Point, curve_order_hint: &BigUint) -> Result<BigUint, EccError> {
+        let p = &self.reduce_point(p)?;
+        let one = BigUint::from(1u32);
+        if *p == Point::Identity {
+            return Ok(one);
+        }
+        if self.scalar_mul(p, curve_order_hint)? != Point::Identity {
+            return Err(EccError::InvalidOrder);
+        }
+
+        let mut factors = Vec::new();
+        let mut rest = curve_order_hint.clone();
+        let mut f = BigUint::from(2u32);
+        let bound = BigUint::from(1u32 << 16);
+        while f <= bound && &f * &f <= rest {
+            while (&rest % &f) == BigUint::from(0u32) {
+                rest /= &f;
+                factors.push(f.clone());
+            }
+            f += if f == BigUint::from(2u32) { 1u32 } else { 2u32 };
+        }
+        if rest > one {
+            factors.push(rest);
+        }
+
+        let mut order = curve_order_hint.clone();
+        for f in factors {
+            let reduced = &order / &f;
+            if self.scalar_mul(p, &reduced)? == Point::Identity {
+                order = reduced;
+            }
+        }
+        Ok(order)
+    }
+    // k in [0, order) with kP = Q, by Pohlig-Hellman: for each l^e of order
+    // n = order / l^e, nP and nQ lie in a group of order l^e, where k mod l^e is found
+    // one base-l digit at a time
+    // gamma = l^(e-1) nP, x = 0
+    // for j in range(0 to e-1)
+    //     d = log_gamma(l^(e-1-j) (nQ - x nP)), by baby-step giant-step in [0, l)
+    //     x = x + d l^j
+    // the x mod l^e are then put together by the Chinese remainder theorem
+    // factorization lists the (prime, exponent) pairs of order, whose product must be
+    // order and must send P to I. The order of P may be a proper divisor of it. The
+    // primes are not checked, and the work grows with the square root of the largest
+    // None when Q is not a multiple of P, or when P, Q or factorization is invalid
+    pub fn pohlig_hellman_dlog(
+        &self,
+        p: &Point,
+        q: &Point,
+        order: &BigUint,
+        factorization: &[(BigUint, u32)],
+    ) -> Option<BigUint> {
+        let one = BigUint::from(1u32);
+        let product = factorization
+            .iter()
+            .fold(one.clone(), |acc, (l, e)| acc * l.pow(*e));
+        if order == &BigUint::from(0u32) || &product != order {
+            return None;
+        }
+        let p = &self.reduce_point(p).ok()?;
+        let q = &self.reduce_point(q).ok()?;
+        if self.scalar_mul(p, order).ok()? != Point::Identity {
+            return None;
+        }
+        // the digits need P of order exactly n: drop the factors of order that P
+        // does not need
+        let mut n = order.clone();
+        let mut factors = Vec::with_capacity(factorization.len());
+        for (l, e) in factorization {
+            let mut e = *e;
+            while e > 0 && self.scalar_mul(p, &(&n / l)).ok()? == Point::Identity {
+                n /= l;
+                e -= 1;
+            }
+            if e > 0 {
+                factors.push((l, e));
+            }
+        }
+
+        // k = x mod modulus so far
+        let mut k = BigUint::from(0u32);
+        let mut modulus = one.clone();
+        for (l, e) in factors {
+            let le = l.pow(e);
+            let cofactor = &n / &le;
+            let p_i = self.scalar_mul(p, &cofactor).ok()?;
+            let q_i = self.scalar_mul(q, &cofactor).ok()?;
+            let gamma = self.scalar_mul(&p_i, &l.pow(e - 1)).ok()?;
+            let mut x = BigUint::from(0u32);
+            let mut lj = one.clone();
+            for j in 0..e {
+                let rest = self
+                    .add_points(&q_i, &self.negate(&self.scalar_mul(&p_i, &x).ok()?))
+                    .ok()?;
+                let h = self.scalar_mul(&rest, &l.pow(e - 1 - j)).ok()?;
+                x += self.dlog_below(&gamma, &h, l)? * &lj;
+                lj *= l;
+            }
+            // k + modulus t = x mod l^e, t = (x - k) / modulus mod l^e, with the inverse
+            // modulus^(phi(l^e) - 1) as modulus is prime to l^e
+            let phi = l.pow(e - 1) * (l - &one);
+            let inv = modulus.modpow(&(phi - &one), &le);
+            let t = FiniteField::mult(&FiniteField::subtract(&x, &k, &le), &inv, &le);
+            k += &modulus * t;
+            modulus *= &le;
+        }
+        // Q outside <P> still gives some k, which does not send P to Q
+        if self.scalar_mul(p, &k).ok()? != *q {
+            return None;
+        }
+        Some(k)
+    }
+    // d in [0, bound) with d gamma = h, by baby-step giant-step
+    // baby steps j gamma for j in [0, m) with m^2 >= bound, then giant steps h - i m gamma
+    fn dlog_below(&self, gamma: &Point, h: &Point, bound: &BigUint) -> Option<BigUint> {
+        let m = bound.sqrt() + 1u32;
+        let mut baby = BTreeMap::new();
+        let mut jg = Point::Identity;
+        let mut j = BigUint::from(0u32);
+        while j < m {
+            baby.entry(jg.clone()).or_insert_with(|| j.clone());
+            jg = self.add_points(&jg, gamma).ok()?;
+            j += 1u32;
+        }
+        let minus_step = self.negate(&jg);
+        let mut giant = h.clone();
+        let mut base = BigUint::from(0u32);
+        while &base < bound {
+            if let Some(j) = baby.get(&giant) {
+                return Some(&base + j);
+            }
+            giant = self.add_points(&giant, &minus_step).ok()?;
+            base += &m;
+        }
+        None
+    }
+    // hP, which lies in the order-n subgroup, or is I when P only had a small-order part
+    pub fn clear_cofactor(&self, p: &Point) -> Result<Point, EccError> {
+        self.clear_cofactor_with(p, &self.cofactor)
+    }
+    // clear_cofactor with a cofactor other than the one the curve was built with
+    pub fn clear_cofactor_with(&self, p: &Point, cofactor: &BigUint) -> Result<Point, EccError> {
+        if !self.is_on_curve(p) {
+            return Err(EccError::InvalidPoint);
+        }
+        self.scalar_mul(p, cofactor)
+    }
+    // order P = I for a point of the curve, so P lies in the subgroup of that order.
+    // I is in every subgroup, points off the curve in none
+    pub fn is_in_subgroup(&self, p: &Point, order: &BigUint) -> bool {
+        self.scalar_mul(p, order) == Ok(Point::Identity)
+    }
+    // P is a point of the curve other than I and order P = I, so P has order exactly
+    // order when order is prime. Rejects the small-order points an attacker would
+    // pick to learn the private key modulo their order
+    pub fn is_in_prime_order_subgroup(&self, p: &Point, order: &BigUint) -> bool {
+        p != &Point::Identity && self.is_in_subgroup(p, order)
+    }
+    // every point of the curve, the identity first and then by increasing x
+    // only meant for toy curves, fails with PrimeTooLarge when p > 10^6
+    pub fn enumerate_points(&self) -> Result<Vec<Point>, EccError> {
+        if self.p > BigUint::from(1_000_000u32) {
+            return Err(EccError::PrimeTooLarge);
+        }
+        let mut points = vec![Point::Identity];
+        let mut x = BigUint::from(0u32);
+        while x < self.p {
+            // y^2 = x^3 + ax + b has 0, 1 (y = 0) or 2 solutions
+            if let Some(y) = FiniteField::sqrt(&self.rhs(&x), &self.p) {
+                let neg_y = FiniteField::neg(&y, &self.p);
+                if y != neg_y {
+                    points.push(Point::Coordinate(x.clone(), y.clone().min(neg_y.clone())));
+                    points.push(Point::Coordinate(x.clone(), y.max(neg_y)));
+                } else {
+                    points.push(Point::Coordinate(x.clone(), y));
+                }
+            }
+            x += 1u32;
+        }
+        Ok(points)
+    }
+    // #E(F_p) = p + 1 - t by the baby-step giant-step method of Mestre
+    // Hasse: |t| <= 2 sqrt(p), so #E lies in [lo, hi] = [p + 1 - 2 sqrt(p), p + 1 + 2 sqrt(p)]
+    // the points of E and of its quadratic twist E' (#E' = 2p + 2 - #E) are taken by
+    // increasing x, and for each one
+    //     L = lcm(L, order P) on E, L' = lcm(L', order P) on E'
+    // until a single N in [lo, hi] has L | N and L' | 2p + 2 - N. Mestre showed that
+    // E or E' has a point for which this happens once p > 229, smaller p are counted
+    // with enumerate_points
+    // fails with PrimeTooLarge when p >= 2^30
+    pub fn point_count_bsgs(&self) -> Result<BigUint, EccError> {
+        if self.p >= BigUint::from(1u32 << 30) {
+            return Err(EccError::PrimeTooLarge);
+        }
+        if self.p <= BigUint::from(229u32) {
+            return Ok(BigUint::from(self.enumerate_points()?.len()));
+        }
+        let zero = BigUint::from(0u32);
+        let one = BigUint::from(1u32);
+        let width = (BigUint::from(4u32) * &self.p).sqrt();
+        let lo = &self.p + &one - &width;
+        let hi = &self.p + &one + &width;
+        let twice = BigUint::from(2u32) * (&self.p + &one);
+
+        // E': y^2 = x^3 + a d^2 x + b d^3 with d a non-residue
+        let mut d = BigUint::from(2u32);
+        while FiniteField::legendre_symbol(&d, &self.p) != -1 {
+            d += 1u32;
+        }
+        let d2 = FiniteField::mult(&d, &d, &self.p);
+        let twist = EllipticCurve::new(
+            FiniteField::mult(&self.a, &d2, &self.p),
+            FiniteField::mult(&self.b, &FiniteField::mult(&d2, &d, &self.p), &self.p),
+            self.p.clone(),
+        )?;
+
+        let mut l = one.clone();
+        let mut l_twist = one.clone();
+        let mut x = zero.clone();
+        while x < self.p {
+            for (curve, multiple) in [(self, &mut l), (&twist, &mut l_twist)] {
+                if let Some(y) = FiniteField::sqrt(&curve.rhs(&x), &self.p) {
+                    let point = Point::Coordinate(x.clone(), y);
+                    let order = curve.order_in_interval(&point, &lo, &hi)?;
+                    *multiple = lcm(multiple, &order);
+                }
+            }
+            // N = lo rounded up to a multiple of L, then every L until hi
+            let mut found = None;
+            let mut n = (&lo + &l - &one) / &l * &l;
+            while n <= hi {
+                if ((&twice - &n) % &l_twist) == zero {
+                    if found.is_some() {
+                        found = None;
+                        break;
+                    }
+                    found = Some(n.clone());
+                }
+                n += &l;
+            }
+            if let Some(n) = found {
+                return Ok(n);
+            }
+            x += 1u32;
+        }
+        Err(EccError::InvalidOrder)
+    }
+    // t = p + 1 - #E(F_p), with the same limits as point_count_bsgs
+    pub fn trace_of_frobenius(&self) -> Result<BigInt, EccError> {
+        let count = self.point_count_bsgs()?;
+        Ok(BigInt::from(&self.p + 1u32) - BigInt::from(count))
+    }
+    // order of P, knowing that it has a multiple in [lo, hi]
+    // baby steps jP for j in [0, s) with s^2 > hi - lo, then giant steps (lo + is) P:
+    // (lo + is) P = -jP gives the multiple lo + is + j
+    fn order_in_interval(
+        &self,
+        c: &Point,
+        lo: &BigUint,
+        hi: &BigUint,
+    ) -> Result<BigUint, EccError> {
+        let s = (hi - lo).sqrt() + 1u32;
+        let mut baby = BTreeMap::new();
+        let mut jp = Point::Identity;
+        let mut j = BigUint::from(0u32);
+        while j < s {
+            // jP repeats when order P < s, any of its j gives a multiple
+            baby.entry(jp.clone()).or_insert_with(|| j.clone());
+            jp = self.add_points(&jp, c)?;
+            j += 1u32;
+        }
+        let step = jp;
+
+        let mut giant = self.scalar_mul(c, lo)?;
+        let mut base = lo.clone();
+        while &base <= hi {
+            if let Some(j) = baby.get(&self.negate(&giant)) {
+                return self.point_order(c, &(&base + j));
+            }
+            giant = self.add_points(&giant, &step)?;
+            base += &s;
+        }
+        Err(EccError::InvalidOrder)
+    }
+    // x uniform in [0, p) until x^3 + ax + b is a square, then either root with equal
+    // odds. Never the identity; p must be at least 5 so that the curve has affine points
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    pub fn random_point(&self) -> Point {
+        loop {
+            let x = rng::gen_biguint_range(&BigUint::from(0u32), &self.p);
+            if let Some(y) = FiniteField::sqrt(&self.rhs(&x), &self.p) {
+                let flip = rng::gen_biguint_range(&BigUint::from(0u32), &BigUint::from(2u32));
+                return self.pick_root(x, y, flip == BigUint::from(1u32));
+            }
+        }
+    }
+    // random_point drawing from rng
+    pub fn random_point_with<R: RngCore>(&self, rng: &mut R) -> Point {
+        loop {
+            let x = rng::gen_biguint_range_with(rng, &BigUint::from(0u32), &self.p);
+            if let Some(y) = FiniteField::sqrt(&self.rhs(&x), &self.p) {
+                return self.pick_root(x, y, rng.next_u32() & 1 == 1);
+            }
+        }
+    }
+    // SEC1 2.3.3: x, with 0x02 when y is even and 0x03 when it is odd
+    // fails with InvalidPoint for the identity, which has no compressed form, and with
+    // PointNotOnCurve unless p is a point of the curve
+    pub fn compress_point(&self, p: &Point) -> Result<(BigUint, u8), EccError> {
+        let p = &self.reduce_point(p)?;
+        match p {
+            Point::Coordinate(x, y) => Ok((x.clone(), if y.bit(0) { 0x03 } else { 0x02 })),
+            Point::Identity => Err(EccError::InvalidPoint),
+        }
+    }
+    // SEC1 2.3.4: y is the root of x^3 + ax + b whose parity the prefix gives
+    // fails with InvalidEncoding when parity is not 0x02 or 0x03 or x is not below p,
+    // and with DecompressionFailed when x^3 + ax + b is not a square or when its only
+    // root is y = 0 and parity asks for an odd one
+    pub fn decompress_point(&self, x: &BigUint, parity: u8) -> Result<Point, EccError> {
+        if parity != 0x02 && parity != 0x03 {
+            return Err(EccError::InvalidEncoding(format!(
+                "invalid compressed point prefix: {:#04x}",
+                parity
+            )));
+        }
+        if x >= &self.p {
+            return Err(EccError::InvalidEncoding("x is not below p".to_string()));
+        }
+        let y = FiniteField::sqrt(&self.rhs(x), &self.p).ok_or(EccError::DecompressionFailed)?;
+        let odd = parity == 0x03;
+        if y.bit(0) == odd {
+            return Ok(Point::Coordinate(x.clone(), y));
+        }
+        if y == BigUint::from(0u32) {
+            return Err(EccError::DecompressionFailed);
+        }
+        Ok(Point::Coordinate(x.clone(), FiniteField::neg(&y, &self.p)))
+    }
+    fn pick_root(&self, x: BigUint, y: BigUint, negate: bool) -> Point {
+        if negate {
+            Point::Coordinate(x, FiniteField::neg(&y, &self.p))
+        } else {
+            Point::Coordinate(x, y)
+        }
+    }
+    // x^3 + ax + b mod p
+    fn rhs(&self, x: &BigUint) -> BigUint {
+        let x3 = x.modpow(&BigUint::from(3u32), &self.p);
+        let ax = FiniteField::mult(&self.a, x, &self.p);
+        FiniteField::add(&FiniteField::add(&x3, &ax, &self.p), &self.b, &self.p)
+    }
+    // window used by scalar_mul_wnaf when there is no reason to pick another one
+    pub const WNAF_WINDOW: usize = 4;
+    // c with its coordinates brought below p, so that the group law takes (x, y + p)
+    // as (x, y) and hands back reduced points only
+    // fails with PointNotOnCurve unless c is a point of the curve
+    pub(crate) fn reduce_point(&self, c: &Point) -> Result<Point, EccError> {
+        let reduced = match c {
+            Point::Identity => Point::Identity,
+            Point::Coordinate(x, y) => Point::Coordinate(x % &self.p, y % &self.p),
+        };
+        if !self.is_on_curve(&reduced) {
+            return Err(EccError::PointNotOnCurve);
+        }
+        Ok(reduced)
+    }
+    pub fn add(&self, c: &Point, d: &Point) -> Result<Point, EccError> {
+        let c = self.reduce_point(c)?;
+        let d = self.reduce_point(d)?;
+        self.add_points(&c, &d)
+    }
+    // add without the curve checks, for operands that are already known to be valid
+    fn add_points(&self, c: &Point, d: &Point) -> Result<Point, EccError> {
+        // P + P is a doubling, and with y = 0 P is also -P: the tangent is vertical and
+        // double_point returns I before dividing by 2y
+        if self.points_equal(c, d) {
+            return self.double_point(c);
+        }
         // s= (y2-y1)/(x2-x1)
         // x3 = s^2 -x1 -x2 mod p
         // y3 = s(x1-x3)-y1 mod p
         match (c, d) {
-            (Point::Identity, _) => d.clone(),
-            (_, Point::Identity) => c.clone(),
+            (Point::Identity, _) => Ok(d.clone()),
+            (_, Point::Identity) => Ok(c.clone()),
             (Point::Coordinate(x1, y1), Point::Coordinate(x2, y2)) => {
-                if x1 == x2 && FiniteField::add(&y1, &y2, &self.p) == BigUint::from(0u32) {
-                    return Point::Identity;
+                let numerator = FiniteField::subtract(y2, y1, &self.p);
+                let denominator = FiniteField::subtract(x2, x1, &self.p);
+                let zero = BigUint::from(0u32);
+                if denominator == zero && FiniteField::add(y1, y2, &self.p) == zero {
+                    return Ok(Point::Identity);
                 }
-                let numerator = FiniteField::subtract(&y2, &y1, &self.p);
-                let denominator = FiniteField::subtract(&x2, &x1, &self.p);
-                let s = FiniteField::divide(&numerator, &denominator, &self.p);
+                let s = FiniteField::divide(&numerator, &denominator, &self.p)?;
 
-                self.compute_x3_y3(&s, x1, y1, x2)
+                Ok(self.compute_x3_y3(&s, x1, y1, x2))
             }
         }
     }
-    fn doubling(&self, c: &Point) -> Point {
-        assert!(self.is_on_curve(&c), "Point is not on curve");
+    pub fn doubling(&self, c: &Point) -> Result<Point, EccError> {
+        self.double_point(&self.reduce_point(c)?)
+    }
+    fn double_point(&self, c: &Point) -> Result<Point, EccError> {
         // s= (3 * x1^2 + a) / (2 * y1 ) mod p
         // x3 = s^2 - 2 *x1 mod p
         // y3 = s (x1 - x3) - y1 mod p
         match c {
-            Point::Identity => Point::Identity,
+            Point::Identity => Ok(Point::Identity),
             Point::Coordinate(x1, y1) => {
-                if y1 == &BigUint::from(0u32) {
-                    return Point::Identity;
+                if y1 % &self.p == BigUint::from(0u32) {
+                    return Ok(Point::Identity);
                 }
                 let numerator = x1.modpow(&BigUint::from(2u32), &self.p);
                 let numerator = FiniteField::mult(&BigUint::from(3u32), &numerator, &self.p);
                 let numerator = FiniteField::add(&numerator, &self.a, &self.p);
 
-                let denominator = FiniteField::mult(&BigUint::from(2u32), &y1, &self.p);
-                let s = FiniteField::divide(&numerator, &denominator, &self.p);
-                let x2 = &x1;
+                let denominator = FiniteField::mult(&BigUint::from(2u32), y1, &self.p);
+                let s = FiniteField::divide(&numerator, &denominator, &self.p)?;
 
-                self.compute_x3_y3(&s, x1, y1, x2)
+                Ok(self.double_coords(&s, x1, y1))
             }
         }
     }
+    // 2(x1, y1) from the tangent slope s
+    // x3 = s^2 - 2 x1 mod p
+    // y3 = s (x1 - x3) - y1 mod p
+    fn double_coords(&self, s: &BigUint, x1: &BigUint, y1: &BigUint) -> Point {
+        let s2 = s.modpow(&BigUint::from(2u32), &self.p);
+        let two_x1 = FiniteField::mult(&BigUint::from(2u32), x1, &self.p);
+        let x3 = FiniteField::subtract(&s2, &two_x1, &self.p);
+
+        let x1minusx3 = FiniteField::subtract(x1, &x3, &self.p);
+        let sx1minusx3 = FiniteField::mult(s, &x1minusx3, &self.p);
+        let y3 = FiniteField::subtract(&sx1minusx3, y1, &self.p);
+        Point::Coordinate(x3, y3)
+    }
+    // (x1, y1) + (x2, y2) from the chord slope s
     fn compute_x3_y3(&self, s: &BigUint, x1: &BigUint, y1: &BigUint, x2: &BigUint) -> Point {
         let s2 = s.modpow(&BigUint::from(2u32), &self.p);
-        let s2minusx1 = FiniteField::subtract(&s2, &x1, &self.p);
-        let x3 = FiniteField::subtract(&s2minusx1, &x2, &self.p);
+        let s2minusx1 = FiniteField::subtract(&s2, x1, &self.p);
+        let x3 = FiniteField::subtract(&s2minusx1, x2, &self.p);
 
-        let x1minusx3 = FiniteField::subtract(&x1, &x3, &self.p);
-        let sx1minusx3 = FiniteField::mult(&s, &x1minusx3, &self.p);
-        let y3 = FiniteField::subtract(&sx1minusx3, &y1, &self.p);
+        let x1minusx3 = FiniteField::subtract(x1, &x3, &self.p);
+        let sx1minusx3 = FiniteField::mult(s, &x1minusx3, &self.p);
+        let y3 = FiniteField::subtract(&sx1minusx3, y1, &self.p);
         Point::Coordinate(x3, y3)
     }
-    fn is_on_curve(self: &Self, c: &Point) -> bool {
+    // c = d as points of the curve: coordinates are compared mod p, so (x, y + p) is
+    // (x, y), where Point's own == compares them as stored
+    pub fn points_equal(&self, c: &Point, d: &Point) -> bool {
+        match (c, d) {
+            (Point::Identity, Point::Identity) => true,
+            (Point::Coordinate(x1, y1), Point::Coordinate(x2, y2)) => {
+                x1 % &self.p == x2 % &self.p && y1 % &self.p == y2 % &self.p
+            }
+            _ => false,
+        }
+    }
+    pub fn is_on_curve(&self, c: &Point) -> bool {
         match c {
             Point::Coordinate(x, y) => {
                 let y2 = y.modpow(&BigUint::from(2u32), &self.p);
                 let x3 = x.modpow(&BigUint::from(3u32), &self.p);
-                let ax = FiniteField::mult(&self.a, &x, &self.p);
+                let ax = FiniteField::mult(&self.a, x, &self.p);
                 let x3plusax = FiniteField::add(&x3, &ax, &self.p);
                 let x2plusaxplusb = FiniteField::add(&x3plusax, &self.b, &self.p);
                 y2 == x2plusaxplusb
@@ -81,119 +824,1846 @@ impl EllipticCurve {
             Point::Identity => true,
         }
     }
-    fn scalar_mul(&self, c: &Point, d: &BigUint) -> Point {
-        // a = c
-        // for i in range(i-1 to 0) of bits(d)
+    pub fn negate(&self, c: &Point) -> Point {
+        // -(x, y) = (x, p - y)
+        match c {
+            Point::Identity => Point::Identity,
+            Point::Coordinate(x, y) => Point::Coordinate(x.clone(), FiniteField::neg(y, &self.p)),
+        }
+    }
+    pub fn subtract(&self, c: &Point, d: &Point) -> Result<Point, EccError> {
+        // c - d = c + (-d)
+        let d = self.reduce_point(d)?;
+        self.add(c, &self.negate(&d))
+    }
+    // c + d for every pair, with a single inversion for the whole slice
+    // each pair gets the slope s = num / den of add: num = y2 - y1, den = x2 - x1, or
+    // num = 3 x1^2 + a, den = 2 y1 when c = d. An identity operand, c = -d and a
+    // doubling with y = 0 get den = 0, which batch_inverse leaves at 0, and need no
+    // slope. x3, y3 then follow from s with multiplications only
+    // fails with PointNotOnCurve unless every point is a point of the curve
+    pub fn batch_add(&self, pairs: &[(Point, Point)]) -> Result<Vec<Point>, EccError> {
+        let pairs = pairs
+            .iter()
+            .map(|(c, d)| Ok((self.reduce_point(c)?, self.reduce_point(d)?)))
+            .collect::<Result<Vec<_>, EccError>>()?;
+        let zero = BigUint::from(0u32);
+        let mut numerators = Vec::with_capacity(pairs.len());
+        let mut denominators = Vec::with_capacity(pairs.len());
+        for (c, d) in &pairs {
+            let (num, den) = match (c, d) {
+                (Point::Coordinate(x1, y1), _) if self.points_equal(c, d) => {
+                    let x1_2 = FiniteField::mult(x1, x1, &self.p);
+                    let num = FiniteField::mult(&BigUint::from(3u32), &x1_2, &self.p);
+                    let num = FiniteField::add(&num, &self.a, &self.p);
+                    (num, FiniteField::mult(&BigUint::from(2u32), y1, &self.p))
+                }
+                (Point::Coordinate(x1, y1), Point::Coordinate(x2, y2))
+                    if x1 % &self.p != x2 % &self.p =>
+                {
+                    (
+                        FiniteField::subtract(y2, y1, &self.p),
+                        FiniteField::subtract(x2, x1, &self.p),
+                    )
+                }
+                _ => (zero.clone(), zero.clone()),
+            };
+            numerators.push(num);
+            denominators.push(den);
+        }
+
+        let inverses = FiniteField::batch_inverse(&denominators, &self.p);
+        let sums = pairs
+            .iter()
+            .zip(numerators.iter().zip(&inverses))
+            .map(|((c, d), (num, inv))| match (c, d) {
+                (Point::Identity, _) => d.clone(),
+                (_, Point::Identity) => c.clone(),
+                (Point::Coordinate(x1, y1), Point::Coordinate(x2, _)) => {
+                    if inv == &zero {
+                        return Point::Identity;
+                    }
+                    let s = FiniteField::mult(num, inv, &self.p);
+                    self.compute_x3_y3(&s, x1, y1, x2)
+                }
+            })
+            .collect();
+        Ok(sums)
+    }
+    pub fn scalar_mul(&self, c: &Point, d: &BigUint) -> Result<Point, EccError> {
+        // a = I
+        // for i in range(bits(d)-1 to 0)
         //     a = 2a
         //     if bit(i)
         //          a = a + c
-        let mut a = c.clone();
-        for i in (0..d.bits() - 1).rev() {
-            a = self.doubling(&a);
+        // a is kept in jacobian coordinates, so the only inversion is the
+        // final conversion back to affine
+        let c = self.to_jacobian(&self.reduce_point(c)?);
+        let mut a = JacobianPoint::identity();
+        for i in (0..d.bits()).rev() {
+            a = self.double_jacobian(&a);
             if d.bit(i) {
-                a = self.add(&a, c);
+                a = self.add_jacobian(&a, &c);
             }
         }
-        a
+        self.to_affine(&a)
     }
-}
-struct FiniteField {}
-impl FiniteField {
-    fn add(c: &BigUint, d: &BigUint, p: &BigUint) -> BigUint {
-        (c + d).modpow(&BigUint::from(1u32), &p)
+    // scalar_mul for the small scalars of tests and proofs, 3G without a BigUint
+    pub fn scalar_mul_u64(&self, c: &Point, k: u64) -> Result<Point, EccError> {
+        self.scalar_mul(c, &BigUint::from(k))
     }
-    fn mult(c: &BigUint, d: &BigUint, p: &BigUint) -> BigUint {
-        (c * d).modpow(&BigUint::from(1u32), &p)
+    // d c for a signed d, e.g. s R - e G as s R + (-e) G: d < 0 gives |d| (-c), and
+    // d = 0 the identity
+    pub fn scalar_mul_signed(&self, c: &Point, d: &BigInt) -> Result<Point, EccError> {
+        // c is checked before it is negated
+        let c = &self.reduce_point(c)?;
+        match d.sign() {
+            Sign::Minus => self.scalar_mul(&self.negate(c), d.magnitude()),
+            Sign::NoSign => Ok(Point::Identity),
+            Sign::Plus => self.scalar_mul(c, d.magnitude()),
+        }
     }
-    fn inv_addition(c: &BigUint, p: &BigUint) -> BigUint {
-        assert!(c < p, "c is greater than p");
-        p - c
+    // k < 0 gives -(|k| c)
+    pub fn scalar_mul_i64(&self, c: &Point, k: i64) -> Result<Point, EccError> {
+        let product = self.scalar_mul_u64(c, k.unsigned_abs())?;
+        if k < 0 {
+            return Ok(self.negate(&product));
+        }
+        Ok(product)
     }
-    fn inv_multiplication(c: &BigUint, p: &BigUint) -> BigUint {
-        (c).modpow(&(p - BigUint::from(2u32)), p)
+    // d mod order first, order being the order of c (or a multiple of it), so that
+    // oversized scalars cost no more than reduced ones
+    // fails with InvalidOrder when order is 0
+    pub fn scalar_mul_reduced(
+        &self,
+        c: &Point,
+        d: &BigUint,
+        order: &BigUint,
+    ) -> Result<Point, EccError> {
+        if order == &BigUint::from(0u32) {
+            return Err(EccError::InvalidOrder);
+        }
+        self.scalar_mul(c, &(d % order))
     }
-    fn subtract(c: &BigUint, d: &BigUint, p: &BigUint) -> BigUint {
-        let d_neg = Self::inv_addition(d, p);
-        Self::add(c, &d_neg, p)
+    // the Montgomery ladder, kept under its first name: the same masked ladder over a
+    // fixed number of steps as scalar_mul_ct, see there for what it does and does not
+    // hide
+    pub fn scalar_mul_montgomery(&self, c: &Point, d: &BigUint) -> Result<Point, EccError> {
+        self.scalar_mul_ct(c, d)
     }
-    fn divide(c: &BigUint, d: &BigUint, p: &BigUint) -> BigUint {
-        let d_inv = Self::inv_multiplication(d, p);
-        Self::mult(c, &d_inv, p)
+    // Montgomery ladder meant to be constant-time in d
+    // r0 = I, r1 = c
+    // for i in range(n-1 to 0)
+    //     cswap(bit(i))
+    //     r1 = r0 + r1, r0 = 2r0
+    //     cswap(bit(i))
+    // the swaps are done with masks instead of branches, both points stay in jacobian
+    // coordinates and n = max(bits(d), bits(p) + 1) covers every d below the group
+    // order, so the number of steps does not depend on the leading zeros of d.
+    // Caveats: BigUint operations take time in the number of limbs of their operands,
+    // and the jacobian addition still branches on the identity while r0 is I and on
+    // r0 = r1, so this only removes the bit-dependent sequence of operations
+    pub fn scalar_mul_ct(&self, c: &Point, d: &BigUint) -> Result<Point, EccError> {
+        #[cfg(test)]
+        SCALAR_MUL_CT_CALLS.with(|count| count.set(count.get() + 1));
+        let mut r0 = JacobianPoint::identity();
+        let mut r1 = self.to_jacobian(&self.reduce_point(c)?);
+        let mut swap = BigUint::from(0u32);
+        let n = d.bits().max(self.p.bits() + 1);
+        for i in (0..n).rev() {
+            let bit = BigUint::from(d.bit(i) as u32);
+            swap ^= &bit;
+            JacobianPoint::cswap(&swap, &mut r0, &mut r1);
+            swap = bit;
+            r1 = self.add_jacobian(&r0, &r1);
+            r0 = self.double_jacobian(&r0);
+        }
+        JacobianPoint::cswap(&swap, &mut r0, &mut r1);
+        self.to_affine(&r0)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    #[test]
-    fn add() {
-        let c = BigUint::from(2u32);
-        let d = BigUint::from(2u32);
-        let p = BigUint::from(10u32);
-        assert_eq!(FiniteField::add(&c, &d, &p), BigUint::from(4u32));
+    // double-and-always-add
+    // a = I
+    // for i in range(n-1 to 0)
+    //     a = 2a
+    //     t = a + c
+    //     a = t if bit(i) else a      # select with a masked swap, never an if on the bit
+    // every step does one doubling and one addition whatever d is, and the unwanted sum
+    // is thrown away by the same masked swap as scalar_mul_ct, over the same n steps.
+    // Threat model: an attacker who times the call or reads a single power trace
+    // (simple power analysis) sees the same sequence of operations for every d. It does
+    // not protect against differential power analysis over many traces, which needs
+    // scalar or point blinding on top, nor against fault attacks, and the caveats of
+    // scalar_mul_ct about num-bigint and the identity branches of the addition remain
+    pub fn scalar_mul_protected(&self, c: &Point, d: &BigUint) -> Result<Point, EccError> {
+        let c = self.to_jacobian(&self.reduce_point(c)?);
+        let mut a = JacobianPoint::identity();
+        let n = d.bits().max(self.p.bits() + 1);
+        for i in (0..n).rev() {
+            a = self.double_jacobian(&a);
+            let mut t = self.add_jacobian(&a, &c);
+            let bit = BigUint::from(d.bit(i) as u32);
+            JacobianPoint::cswap(&bit, &mut a, &mut t);
+        }
+        self.to_affine(&a)
     }
-    #[test]
-    fn add1() {
-        let c = BigUint::from(2u32);
-        let d = BigUint::from(2u32);
-        let p = BigUint::from(3u32);
-        assert_eq!(FiniteField::add(&c, &d, &p), BigUint::from(1u32));
+    // rows[i][j] = (j + 1) 2^(w i) base for j in [0, 2^w - 1), enough rows for any
+    // scalar of bits(p) + 1 bits, which covers every d below the order of base
+    // every entry is built in jacobian coordinates and brought back to z = 1 with a
+    // single batch inversion
+    // fails with InvalidScalar when window is not in [1, 8]
+    pub fn precompute_base(&self, base: &Point, window: usize) -> Result<BaseTable, EccError> {
+        if !(1..=8).contains(&window) {
+            return Err(EccError::InvalidScalar);
+        }
+        let base = &self.reduce_point(base)?;
+        let row_count = (self.p.bits() as usize + 1).div_ceil(window);
+        let mut entries = Vec::with_capacity(row_count * ((1 << window) - 1));
+        let mut row_base = self.to_jacobian(base);
+        for _ in 0..row_count {
+            let mut multiple = row_base.clone();
+            for _ in 1..(1usize << window) {
+                let next = self.add_jacobian(&multiple, &row_base);
+                entries.push(multiple);
+                multiple = next;
+            }
+            // multiple is now 2^w row_base
+            row_base = multiple;
+        }
+
+        // the identity has z = 0, which batch_inverse leaves alone
+        let zs: Vec<BigUint> = entries.iter().map(|e| e.z.clone()).collect();
+        let z_invs = FiniteField::batch_inverse(&zs, &self.p);
+        let entries: Vec<JacobianPoint> = entries
+            .into_iter()
+            .zip(z_invs)
+            .map(|(e, z_inv)| {
+                if e.is_identity() {
+                    return e;
+                }
+                let z_inv2 = FiniteField::mult(&z_inv, &z_inv, &self.p);
+                let z_inv3 = FiniteField::mult(&z_inv2, &z_inv, &self.p);
+                JacobianPoint {
+                    x: FiniteField::mult(&e.x, &z_inv2, &self.p),
+                    y: FiniteField::mult(&e.y, &z_inv3, &self.p),
+                    z: BigUint::from(1u32),
+                }
+            })
+            .collect();
+        let rows = entries
+            .chunks((1 << window) - 1)
+            .map(|row| row.to_vec())
+            .collect();
+        Ok(BaseTable {
+            base: base.clone(),
+            window,
+            rows,
+        })
     }
-    #[test]
-    fn mult() {
-        let c = BigUint::from(2u32);
-        let d = BigUint::from(3u32);
-        let p = BigUint::from(4u32);
-        assert_eq!(FiniteField::mult(&c, &d, &p), BigUint::from(2u32));
+    // d base from a table of precompute_base, which has to come from this curve
+    // a = I
+    // for i in range(0 to rows)
+    //     digit = bits [w i, w i + w) of d
+    //     a = a + rows[i][digit - 1]      # nothing when digit is 0
+    // no doublings at all, one addition per window. Every entry of a row is looked at
+    // and the one needed is picked with masks, so the memory accessed does not depend
+    // on d; adding the identity for a zero digit still branches, as in scalar_mul_ct.
+    // A d too wide for the table goes through scalar_mul
+    pub fn scalar_mul_base(&self, table: &BaseTable, d: &BigUint) -> Result<Point, EccError> {
+        let w = table.window;
+        if d.bits() > (table.rows.len() * w) as u64 {
+            return self.scalar_mul(&table.base, d);
+        }
+        let mut a = JacobianPoint::identity();
+        for (i, row) in table.rows.iter().enumerate() {
+            let digit = (0..w).fold(0usize, |acc, b| {
+                acc | ((d.bit((i * w + b) as u64) as usize) << b)
+            });
+            let mut selected = JacobianPoint::identity();
+            for (j, entry) in row.iter().enumerate() {
+                let flag = BigUint::from((digit == j + 1) as u32);
+                let mut entry = entry.clone();
+                JacobianPoint::cswap(&flag, &mut selected, &mut entry);
+            }
+            a = self.add_jacobian(&a, &selected);
+        }
+        self.to_affine(&a)
     }
-    #[test]
-    #[should_panic]
-    fn mult1() {
-        let c = BigUint::from(2u32);
-        let d = BigUint::from(4u32);
-        let p = BigUint::from(3u32);
-        assert_eq!(FiniteField::add(&c, &d, &p), BigUint::from(1u32));
+    // fails with InvalidScalar when window is not in [2, 8]
+    pub fn scalar_mul_wnaf(
+        &self,
+        c: &Point,
+        d: &BigUint,
+        window: usize,
+    ) -> Result<Point, EccError> {
+        // table = [c, 3c, 5c, ..., (2^(w-1) - 1)c]
+        // a = I
+        // for digit in wnaf(d) from the most significant
+        //     a = 2a
+        //     if digit > 0  a = a + table[digit / 2]
+        //     if digit < 0  a = a - table[-digit / 2]
+        if !(2..=8).contains(&window) {
+            return Err(EccError::InvalidScalar);
+        }
+        let c = &self.reduce_point(c)?;
+        let double_c = self.double_point(c)?;
+        let mut table = vec![c.clone()];
+        for i in 1..(1usize << (window - 2)) {
+            table.push(self.add_points(&table[i - 1], &double_c)?);
+        }
+
+        let mut a = Point::Identity;
+        for digit in Self::wnaf_digits(d, window).iter().rev() {
+            a = self.double_point(&a)?;
+            if *digit > 0 {
+                a = self.add_points(&a, &table[(*digit / 2) as usize])?;
+            } else if *digit < 0 {
+                a = self.add_points(&a, &self.negate(&table[(-*digit / 2) as usize]))?;
+            }
+        }
+        Ok(a)
     }
-    #[test]
-    fn inv_addition() {
-        let c = BigUint::from(4u32);
-        let p = BigUint::from(7u32);
-        assert_eq!(FiniteField::inv_addition(&c, &p), BigUint::from(3u32));
+    fn wnaf_digits(d: &BigUint, window: usize) -> Vec<i32> {
+        // least significant digit first, every non-zero digit is odd and
+        // lies in (-2^(w-1), 2^(w-1)), and any w consecutive digits hold at
+        // most one non-zero digit
+        let modulus = 1i32 << window;
+        let mut d = d.clone();
+        let mut digits = Vec::new();
+        while d != BigUint::from(0u32) {
+            let mut digit = 0;
+            if d.bit(0) {
+                // d is odd, so d mod 2^w is non-zero and fits in one digit
+                digit = (&d % BigUint::from(modulus as u32)).to_u32_digits()[0] as i32;
+                if digit >= modulus / 2 {
+                    digit -= modulus;
+                }
+                if digit > 0 {
+                    d -= BigUint::from(digit as u32);
+                } else {
+                    d += BigUint::from((-digit) as u32);
+                }
+            }
+            digits.push(digit);
+            d >>= 1;
+        }
+        digits
     }
-    #[test]
-    fn inv_multiplication() {
-        let c = BigUint::from(4u32);
-        let p = BigUint::from(7u32);
-        assert_eq!(FiniteField::inv_multiplication(&c, &p), BigUint::from(2u32));
+    // k1 p1 + k2 p2 with Shamir's trick, a single chain of doublings
+    // a = I
+    // for i in range(max(bits(k1), bits(k2))-1 to 0)
+    //     a = 2a
+    //     a = a + bit(i, k1) p1 + bit(i, k2) p2      # p1 + p2 is precomputed
+    // a stays in jacobian coordinates, as in scalar_mul
+    // for n-bit scalars two scalar_mul calls cost 2n doublings and about n additions,
+    // the shared chain costs n doublings and 3n/4 additions (the bits are both zero a
+    // quarter of the time), and a single inversion back to affine instead of two,
+    // so roughly 40% less work than adding two separate products
+    pub fn mul_add(
+        &self,
+        k1: &BigUint,
+        p1: &Point,
+        k2: &BigUint,
+        p2: &Point,
+    ) -> Result<Point, EccError> {
+        let p1 = self.reduce_point(p1)?;
+        let p2 = self.reduce_point(p2)?;
+        let sum = self.to_jacobian(&self.add(&p1, &p2)?);
+        let j1 = self.to_jacobian(&p1);
+        let j2 = self.to_jacobian(&p2);
+        let mut a = JacobianPoint::identity();
+        for i in (0..k1.bits().max(k2.bits())).rev() {
+            a = self.double_jacobian(&a);
+            match (k1.bit(i), k2.bit(i)) {
+                (true, true) => a = self.add_jacobian(&a, &sum),
+                (true, false) => a = self.add_jacobian(&a, &j1),
+                (false, true) => a = self.add_jacobian(&a, &j2),
+                (false, false) => {}
+            }
+        }
+        self.to_affine(&a)
     }
-    #[test]
-    fn identity_addition() {
-        let c = BigUint::from(4u32);
-        let p = BigUint::from(7u32);
-        let d = FiniteField::inv_addition(&c, &p);
-        assert_eq!(FiniteField::add(&c, &d, &p), BigUint::from(0u32));
+    // mul_add with the points first
+    #[deprecated(note = "use EllipticCurve::mul_add")]
+    pub fn scalar_mul2(
+        &self,
+        c1: &Point,
+        d1: &BigUint,
+        c2: &Point,
+        d2: &BigUint,
+    ) -> Result<Point, EccError> {
+        self.mul_add(d1, c1, d2, c2)
     }
-    #[test]
-    fn identity_multiplication() {
-        let c = BigUint::from(4u32);
-        let p = BigUint::from(7u32);
-        let d = FiniteField::inv_multiplication(&c, &p);
-        assert_eq!(FiniteField::mult(&c, &d, &p), BigUint::from(1u32));
+    // d1 c1 + d2 c2 + ... + dn cn, Shamir's trick over n points (Straus)
+    // a = I
+    // for i in range(max(bits(di))-1 to 0)
+    //     a = 2a
+    //     for each j with bit(i, dj): a = a + cj
+    // one chain of doublings is shared by all the terms, so n products cost about
+    // max(bits(di)) doublings plus the additions instead of n full scalar_mul calls
+    // fails with PointNotOnCurve unless every cj is a point of the curve
+    pub fn multi_scalar_mul(&self, terms: &[(&Point, &BigUint)]) -> Result<Point, EccError> {
+        let points = terms
+            .iter()
+            .map(|(c, _)| Ok(self.to_jacobian(&self.reduce_point(c)?)))
+            .collect::<Result<Vec<JacobianPoint>, EccError>>()?;
+        let bits = terms.iter().map(|(_, d)| d.bits()).max().unwrap_or(0);
+        let mut a = JacobianPoint::identity();
+        for i in (0..bits).rev() {
+            a = self.double_jacobian(&a);
+            for ((_, d), c) in terms.iter().zip(&points) {
+                if d.bit(i) {
+                    a = self.add_jacobian(&a, c);
+                }
+            }
+        }
+        self.to_affine(&a)
     }
+    pub fn scalar_mul_glv(&self, c: &Point, d: &BigUint) -> Result<Point, EccError> {
+        // secp256k1 has the endomorphism phi(x, y) = (beta x, y) = lambda (x, y)
+        // d = d1 + d2 lambda mod n with d1, d2 about 128 bits
+        // d c = d1 c + d2 phi(c), which halves the doublings of scalar_mul
+        // other curves have no known endomorphism and go through scalar_mul
+        if !self.is_secp256k1() {
+            return self.scalar_mul(c, d);
+        }
+        let c = &self.reduce_point(c)?;
+        if c == &Point::Identity {
+            return Ok(Point::Identity);
+        }
+        let phi_c = Self::secp256k1_apply_endomorphism(c, &Self::secp256k1_beta(), &self.p);
 
-    #[test]
-    fn test_point_in_curve() {
-        // y^2 = x^3 + 2x + 2 mod 17
-        let ec = EllipticCurve {
-            a: BigUint::from(2u32),
-            b: BigUint::from(2u32),
-            p: BigUint::from(17u32),
+        let n = Self::secp256k1_order();
+        let (d1, d2) = Self::secp256k1_endomorphism_split(&(d % &n), &n);
+        let (d1, c1) = match d1.into_parts() {
+            (Sign::Minus, d1) => (d1, self.negate(c)),
+            (_, d1) => (d1, c.clone()),
         };
+        let (d2, c2) = match d2.into_parts() {
+            (Sign::Minus, d2) => (d2, self.negate(&phi_c)),
+            (_, d2) => (d2, phi_c),
+        };
+        self.mul_add(&d1, &c1, &d2, &c2)
+    }
+    // d = d1 + d2 lambda mod n with |d1|, |d2| below 2^129, for d in [0, n) and n the
+    // secp256k1 order (the lattice below only fits that curve)
+    // lattice basis (a1, b1), (a2, b2) with ai + bi lambda = 0 mod n
+    // c1 = round(b2 d / n), c2 = round(-b1 d / n)
+    // d1 = d - c1 a1 - c2 a2, d2 = -c1 b1 - c2 b2
+    pub fn secp256k1_endomorphism_split(d: &BigUint, n: &BigUint) -> (BigInt, BigInt) {
+        let a1 =
+            BigUint::parse_bytes(b"3086d221a7d46bcde86c90e49284eb15", 16).expect("Cannot parse a1");
+        let minus_b1 =
+            BigUint::parse_bytes(b"e4437ed6010e88286f547fa90abfe4c3", 16).expect("Cannot parse b1");
+        let a2 = BigUint::parse_bytes(b"114ca50f7a8e2f3f657c1108d9d44cfd8", 16)
+            .expect("Cannot parse a2");
+        let b2 = a1.clone();
 
-        // (6,3) + (5,1) = (10,6)
-        let p1 = Point::Coordinate(BigUint::from(6u32), BigUint::from(3u32));
-        let p2 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
-        let p3 = Point::Coordinate(BigUint::from(10u32), BigUint::from(6u32));
+        let half_n = n >> 1;
+        let c1 = (&b2 * d + &half_n) / n;
+        let c2 = (&minus_b1 * d + &half_n) / n;
+
+        let d1 = BigInt::from(d.clone()) - BigInt::from(&c1 * &a1) - BigInt::from(&c2 * &a2);
+        let d2 = BigInt::from(&c1 * &minus_b1) - BigInt::from(&c2 * &b2);
+        (d1, d2)
+    }
+    // phi(x, y) = (beta x, y), which is lambda (x, y) on secp256k1 when beta is
+    // secp256k1_beta and field_mod is p
+    pub fn secp256k1_apply_endomorphism(c: &Point, beta: &BigUint, field_mod: &BigUint) -> Point {
+        match c {
+            Point::Identity => Point::Identity,
+            Point::Coordinate(x, y) => {
+                Point::Coordinate(FiniteField::mult(beta, x, field_mod), y.clone())
+            }
+        }
+    }
+    // a cube root of unity mod p, beta^3 = 1
+    pub fn secp256k1_beta() -> BigUint {
+        BigUint::parse_bytes(
+            b"7ae96a2b657c07106e64479eac3434e99cf0497512f58995c1396c28719501ee",
+            16,
+        )
+        .expect("Cannot parse beta")
+    }
+    fn is_secp256k1(&self) -> bool {
+        let secp256k1 = Self::secp256k1();
+        self.a == secp256k1.a && self.b == secp256k1.b && self.p == secp256k1.p
+    }
+    fn to_jacobian(&self, c: &Point) -> JacobianPoint {
+        match c {
+            Point::Identity => JacobianPoint::identity(),
+            Point::Coordinate(x, y) => JacobianPoint {
+                x: x.clone(),
+                y: y.clone(),
+                z: BigUint::from(1u32),
+            },
+        }
+    }
+    fn to_affine(&self, c: &JacobianPoint) -> Result<Point, EccError> {
+        // x = X / Z^2, y = Y / Z^3
+        if c.is_identity() {
+            return Ok(Point::Identity);
+        }
+        let z_inv = FiniteField::inv(&c.z, &self.p)?;
+        let z_inv2 = FiniteField::mult(&z_inv, &z_inv, &self.p);
+        let z_inv3 = FiniteField::mult(&z_inv2, &z_inv, &self.p);
+        Ok(Point::Coordinate(
+            FiniteField::mult(&c.x, &z_inv2, &self.p),
+            FiniteField::mult(&c.y, &z_inv3, &self.p),
+        ))
+    }
+    fn double_jacobian(&self, c: &JacobianPoint) -> JacobianPoint {
+        // S = 4 X Y^2
+        // M = 3 X^2 + a Z^4
+        // X' = M^2 - 2 S
+        // Y' = M (S - X') - 8 Y^4
+        // Z' = 2 Y Z
+        if c.is_identity() || c.y == BigUint::from(0u32) {
+            return JacobianPoint::identity();
+        }
+        let p = &self.p;
+        let y2 = FiniteField::mult(&c.y, &c.y, p);
+        let s = FiniteField::mult(&BigUint::from(4u32), &FiniteField::mult(&c.x, &y2, p), p);
+        let x2 = FiniteField::mult(&c.x, &c.x, p);
+        let z2 = FiniteField::mult(&c.z, &c.z, p);
+        let z4 = FiniteField::mult(&z2, &z2, p);
+        let m = FiniteField::add(
+            &FiniteField::mult(&BigUint::from(3u32), &x2, p),
+            &FiniteField::mult(&self.a, &z4, p),
+            p,
+        );
+
+        let x3 = FiniteField::subtract(
+            &FiniteField::mult(&m, &m, p),
+            &FiniteField::mult(&BigUint::from(2u32), &s, p),
+            p,
+        );
+        let y4 = FiniteField::mult(&y2, &y2, p);
+        let y3 = FiniteField::subtract(
+            &FiniteField::mult(&m, &FiniteField::subtract(&s, &x3, p), p),
+            &FiniteField::mult(&BigUint::from(8u32), &y4, p),
+            p,
+        );
+        let z3 = FiniteField::mult(&BigUint::from(2u32), &FiniteField::mult(&c.y, &c.z, p), p);
+        JacobianPoint {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+    fn add_jacobian(&self, c: &JacobianPoint, d: &JacobianPoint) -> JacobianPoint {
+        // U1 = X1 Z2^2, U2 = X2 Z1^2
+        // S1 = Y1 Z2^3, S2 = Y2 Z1^3
+        // H = U2 - U1, R = S2 - S1
+        // X3 = R^2 - H^3 - 2 U1 H^2
+        // Y3 = R (U1 H^2 - X3) - S1 H^3
+        // Z3 = H Z1 Z2
+        if c.is_identity() {
+            return d.clone();
+        }
+        if d.is_identity() {
+            return c.clone();
+        }
+        let p = &self.p;
+        let z1z1 = FiniteField::mult(&c.z, &c.z, p);
+        let z2z2 = FiniteField::mult(&d.z, &d.z, p);
+        let u1 = FiniteField::mult(&c.x, &z2z2, p);
+        let u2 = FiniteField::mult(&d.x, &z1z1, p);
+        let s1 = FiniteField::mult(&c.y, &FiniteField::mult(&d.z, &z2z2, p), p);
+        let s2 = FiniteField::mult(&d.y, &FiniteField::mult(&c.z, &z1z1, p), p);
+
+        if u1 == u2 {
+            if s1 == s2 {
+                return self.double_jacobian(c);
+            }
+            return JacobianPoint::identity();
+        }
+
+        let h = FiniteField::subtract(&u2, &u1, p);
+        let r = FiniteField::subtract(&s2, &s1, p);
+        let h2 = FiniteField::mult(&h, &h, p);
+        let h3 = FiniteField::mult(&h2, &h, p);
+        let u1h2 = FiniteField::mult(&u1, &h2, p);
+
+        let x3 = FiniteField::subtract(
+            &FiniteField::subtract(&FiniteField::mult(&r, &r, p), &h3, p),
+            &FiniteField::mult(&BigUint::from(2u32), &u1h2, p),
+            p,
+        );
+        let y3 = FiniteField::subtract(
+            &FiniteField::mult(&r, &FiniteField::subtract(&u1h2, &x3, p), p),
+            &FiniteField::mult(&s1, &h3, p),
+            p,
+        );
+        let z3 = FiniteField::mult(&h, &FiniteField::mult(&c.z, &d.z, p), p);
+        JacobianPoint {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+    pub fn secp256k1() -> Self {
+        /*
+                Name	Value
+                p	0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f
+                a	0x0000000000000000000000000000000000000000000000000000000000000000
+                b	0x0000000000000000000000000000000000000000000000000000000000000007
+                g	(79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798, 483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8)
+                n	0xfffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141
+        */
+        let p = BigUint::parse_bytes(
+            b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+            16,
+        )
+        .expect("Cannot parse p");
+        EllipticCurve::new(BigUint::from(0u32), BigUint::from(7u32), p)
+            .expect("secp256k1 is non-singular")
+    }
+    pub fn secp256k1_generator() -> Point {
+        let gx = BigUint::parse_bytes(
+            b"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            16,
+        )
+        .expect("Cannot parse gx");
+        let gy = BigUint::parse_bytes(
+            b"483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+            16,
+        )
+        .expect("Cannot parse gy");
+        Point::Coordinate(gx, gy)
+    }
+    pub fn secp256k1_order() -> BigUint {
+        BigUint::parse_bytes(
+            b"fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+            16,
+        )
+        .expect("Cannot parse n")
+    }
+    pub fn p256() -> Self {
+        /*
+                NIST P-256 (secp256r1)
+                Name	Value
+                p	0xffffffff00000001000000000000000000000000ffffffffffffffffffffffff
+                a	0xffffffff00000001000000000000000000000000fffffffffffffffffffffffc (-3 mod p)
+                b	0x5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b
+                g	(6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296, 4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5)
+                n	0xffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551
+        */
+        let p = BigUint::parse_bytes(
+            b"ffffffff00000001000000000000000000000000ffffffffffffffffffffffff",
+            16,
+        )
+        .expect("Cannot parse p");
+        let a = BigUint::parse_bytes(
+            b"ffffffff00000001000000000000000000000000fffffffffffffffffffffffc",
+            16,
+        )
+        .expect("Cannot parse a");
+        let b = BigUint::parse_bytes(
+            b"5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b",
+            16,
+        )
+        .expect("Cannot parse b");
+        EllipticCurve::new(a, b, p).expect("P-256 is non-singular")
+    }
+    pub fn p256_generator() -> Point {
+        let gx = BigUint::parse_bytes(
+            b"6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296",
+            16,
+        )
+        .expect("Cannot parse gx");
+        let gy = BigUint::parse_bytes(
+            b"4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5",
+            16,
+        )
+        .expect("Cannot parse gy");
+        Point::Coordinate(gx, gy)
+    }
+    pub fn p256_order() -> BigUint {
+        BigUint::parse_bytes(
+            b"ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551",
+            16,
+        )
+        .expect("Cannot parse n")
+    }
+    #[cfg(test)]
+    fn scalar_mul_naive(&self, c: &Point, d: u32) -> Point {
+        // c + c + ... + c (d times), only usable for small d
+        let mut a = Point::Identity;
+        for _ in 0..d {
+            a = self.add(&a, c).expect("Point is not on curve");
+        }
+        a
+    }
+}
+// a cofactor other than 1 follows the equation, y² = x³ + 0x1x + 0x4 mod 0x11, h = 0x2
+impl fmt::Display for EllipticCurve {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "y² = x³ + {:#x}x + {:#x} mod {:#x}",
+            self.a, self.b, self.p
+        )?;
+        if self.cofactor != BigUint::from(1u32) {
+            write!(f, ", h = {:#x}", self.cofactor)?;
+        }
+        Ok(())
+    }
+}
+impl FromStr for EllipticCurve {
+    type Err = EccError;
+
+    // reads back the Display format, numbers are decimal or 0x-prefixed hex, and the
+    // cofactor is 1 unless given
+    // digits only, as in parse_hex: no sign and no underscores
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || EccError::InvalidEncoding(format!("invalid curve: {}", s));
+        let parse = |n: &str| {
+            if n.starts_with("0x") {
+                return parse_hex(n).map_err(|_| invalid());
+            }
+            if n.is_empty() || !n.bytes().all(|c| c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            Ok(BigUint::parse_bytes(n.as_bytes(), 10).expect("digits are decimal"))
+        };
+
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+        match tokens[..] {
+            ["y²", "=", "x³", "+", ax, "+", b, "mod", p] => {
+                let a = ax.strip_suffix('x').ok_or_else(invalid)?;
+                EllipticCurve::new(parse(a)?, parse(b)?, parse(p)?)
+            }
+            ["y²", "=", "x³", "+", ax, "+", b, "mod", p, "h", "=", h] => {
+                let a = ax.strip_suffix('x').ok_or_else(invalid)?;
+                let p = p.strip_suffix(',').ok_or_else(invalid)?;
+                EllipticCurve::with_cofactor(parse(a)?, parse(b)?, parse(p)?, parse(h)?)
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+// multiples of a fixed point, see EllipticCurve::precompute_base
+#[derive(PartialEq, Debug, Clone)]
+pub struct BaseTable {
+    base: Point,
+    window: usize,
+    rows: Vec<Vec<JacobianPoint>>,
+}
+impl BaseTable {
+    pub fn base(&self) -> &Point {
+        &self.base
+    }
+}
+// (X, Y, Z) stands for the affine point (X / Z^2, Y / Z^3), Z = 0 is the identity
+#[derive(PartialEq, Debug, Clone)]
+struct JacobianPoint {
+    x: BigUint,
+    y: BigUint,
+    z: BigUint,
+}
+impl JacobianPoint {
+    fn identity() -> Self {
+        JacobianPoint {
+            x: BigUint::from(1u32),
+            y: BigUint::from(1u32),
+            z: BigUint::from(0u32),
+        }
+    }
+    fn is_identity(&self) -> bool {
+        self.z == BigUint::from(0u32)
+    }
+    // swaps c and d when flag is 1
+    fn cswap(flag: &BigUint, c: &mut JacobianPoint, d: &mut JacobianPoint) {
+        FiniteField::cswap(flag, &mut c.x, &mut d.x);
+        FiniteField::cswap(flag, &mut c.y, &mut d.y);
+        FiniteField::cswap(flag, &mut c.z, &mut d.z);
+    }
+}
+#[cfg(test)]
+thread_local! {
+    // number of calls to FiniteField::inv (PrimeField::inv) on the current thread
+    static INVERSIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    // number of calls to EllipticCurve::scalar_mul_ct and
+    // TwistedEdwardsCurve::scalar_mul_ct on the current thread
+    pub(crate) static SCALAR_MUL_CT_CALLS: std::cell::Cell<usize> =
+        const { std::cell::Cell::new(0) };
+}
+pub struct FiniteField {}
+impl FiniteField {
+    // add, mult, neg, inv, subtract and divide are PrimeField's, over a borrowed p
+    pub fn add(c: &BigUint, d: &BigUint, p: &BigUint) -> BigUint {
+        PrimeField::over(p).add(c, d)
+    }
+    pub fn mult(c: &BigUint, d: &BigUint, p: &BigUint) -> BigUint {
+        PrimeField::over(p).mul(c, d)
+    }
+    // -c mod p
+    pub fn neg(c: &BigUint, p: &BigUint) -> BigUint {
+        PrimeField::over(p).neg(c)
+    }
+    // c^-1 mod p, c^(p-2) by Fermat so p must be prime
+    // fails with NotInvertible when c = 0 mod p
+    pub fn inv(c: &BigUint, p: &BigUint) -> Result<BigUint, EccError> {
+        PrimeField::over(p).inv(c)
+    }
+    #[deprecated(note = "use FiniteField::neg")]
+    pub fn inv_addition(c: &BigUint, p: &BigUint) -> BigUint {
+        Self::neg(c, p)
+    }
+    #[deprecated(note = "use FiniteField::inv")]
+    pub fn inv_multiplication(c: &BigUint, p: &BigUint) -> Result<BigUint, EccError> {
+        Self::inv(c, p)
+    }
+    // c^-1 mod p for a secret c, p must be an odd prime
+    // binary extended gcd run for a fixed 2 * bits(p) rounds, with a = u c and b = v c mod p
+    // a = c, b = p, u = 1, v = 0
+    // each round: if a is odd and a < b swap (a, u) with (b, v)
+    //             if a is odd a = a - b, u = u - v
+    //             a = a / 2, u = u / 2 mod p
+    // a reaches 0 with b = gcd(c, p) = 1 and v = c^-1
+    // every round runs the same operations whatever c is, the branches only pick
+    // between values, but num-bigint itself still takes time in the limb count
+    // fails with NotInvertible when c = 0 mod p
+    pub fn inv_multiplication_ct(c: &BigUint, p: &BigUint) -> Result<BigUint, EccError> {
+        let zero = BigUint::from(0u32);
+        let one = BigUint::from(1u32);
+        let mut a = c % p;
+        if a == zero {
+            return Err(EccError::NotInvertible);
+        }
+        let mut b = p.clone();
+        let mut u = one.clone();
+        let mut v = zero;
+        for _ in 0..2 * p.bits() {
+            let odd = BigUint::from(a.bit(0) as u32);
+            let swap = BigUint::from((a.bit(0) && a < b) as u32);
+            Self::cswap(&swap, &mut a, &mut b);
+            Self::cswap(&swap, &mut u, &mut v);
+            a -= &b * &odd;
+            u = (u + p * &odd - &v * &odd) % p;
+            a >>= 1;
+            let u_odd = BigUint::from(u.bit(0) as u32);
+            u = (u + p * u_odd) >> 1;
+        }
+        if b != one {
+            return Err(EccError::NotInvertible);
+        }
+        Ok(v)
+    }
+    // swaps x and y when flag is 1, leaves them when it is 0
+    pub(crate) fn cswap(flag: &BigUint, x: &mut BigUint, y: &mut BigUint) {
+        let t = (&*x ^ &*y) * flag;
+        *x ^= &t;
+        *y ^= &t;
+    }
+    // (c / p) for an odd prime p by Euler's criterion: c^((p-1)/2) is 1 when c is a
+    // non-zero square mod p, p - 1 when it is not and 0 when p divides c
+    pub fn legendre_symbol(c: &BigUint, p: &BigUint) -> i8 {
+        let euler = c.modpow(&((p - BigUint::from(1u32)) >> 1), p);
+        if euler == BigUint::from(0u32) {
+            0
+        } else if euler == BigUint::from(1u32) {
+            1
+        } else {
+            -1
+        }
+    }
+    // c = y^2 mod p for some y, 0 included
+    pub fn is_quadratic_residue(c: &BigUint, p: &BigUint) -> bool {
+        Self::legendre_symbol(c, p) >= 0
+    }
+    // one of the two roots of c mod p, the other one is p - root
+    // None when c is not a square mod p, which must be an odd prime
+    // p = 3 mod 4: c^((p+1)/4)
+    // otherwise Tonelli-Shanks, with p - 1 = q 2^s and z a non-residue
+    // m = s, e = z^q, t = c^q, r = c^((q+1)/2)
+    // while t != 1: least i with t^(2^i) = 1, b = e^(2^(m-i-1))
+    //               m = i, e = b^2, t = t e, r = r b
+    pub fn sqrt(c: &BigUint, p: &BigUint) -> Option<BigUint> {
+        let zero = BigUint::from(0u32);
+        let one = BigUint::from(1u32);
+        let c = c % p;
+        if c == zero {
+            return Some(zero);
+        }
+        if Self::legendre_symbol(&c, p) != 1 {
+            return None;
+        }
+        if p.bit(1) {
+            return Some(c.modpow(&((p + &one) >> 2), p));
+        }
+
+        let p_minus_one = p - &one;
+        let s = p_minus_one.trailing_zeros().expect("p - 1 is not zero");
+        let q = &p_minus_one >> s;
+        let mut z = BigUint::from(2u32);
+        while Self::legendre_symbol(&z, p) != -1 {
+            z += 1u32;
+        }
+
+        let mut m = s;
+        let mut e = z.modpow(&q, p);
+        let mut t = c.modpow(&q, p);
+        let mut r = c.modpow(&((&q + &one) >> 1), p);
+        while t != one {
+            let mut i = 0;
+            let mut t2i = t.clone();
+            while t2i != one {
+                t2i = Self::mult(&t2i, &t2i, p);
+                i += 1;
+            }
+            let b = e.modpow(&(BigUint::from(1u32) << (m - i - 1)), p);
+            m = i;
+            e = Self::mult(&b, &b, p);
+            t = Self::mult(&t, &e, p);
+            r = Self::mult(&r, &b, p);
+        }
+        Some(r)
+    }
+    // (c - d) mod p for any c and d, reduced or not
+    // the difference is taken as a BigInt, whose % keeps the sign of c - d, and a
+    // negative remainder is moved up by p into [0, p)
+    pub fn subtract(c: &BigUint, d: &BigUint, p: &BigUint) -> BigUint {
+        PrimeField::over(p).sub(c, d)
+    }
+    // fails with DivisionByZero when d = 0 mod p
+    pub fn divide(c: &BigUint, d: &BigUint, p: &BigUint) -> Result<BigUint, EccError> {
+        PrimeField::over(p).div(c, d)
+    }
+    // Montgomery's trick, one inversion for the whole slice
+    // fails with NotInvertible as soon as one value is 0 mod p, see batch_inverse for
+    // the version that lets zeros through
+    pub fn batch_invert(vals: &[BigUint], p: &BigUint) -> Result<Vec<BigUint>, EccError> {
+        let zero = BigUint::from(0u32);
+        if vals.iter().any(|val| val % p == zero) {
+            return Err(EccError::NotInvertible);
+        }
+        Self::montgomery_invert(vals, p)
+    }
+    // the inverse of every value with a single inversion and 3(n-1) multiplications,
+    // values that are 0 mod p have none and map to 0
+    // p must be prime, which is only checked in debug builds: with a composite p the
+    // product of the non-zero values can be 0 mod p, and this panics, or the results
+    // are not inverses
+    pub fn batch_inverse(elems: &[BigUint], p: &BigUint) -> Vec<BigUint> {
+        debug_assert!(is_probable_prime(p), "batch_inverse needs a prime p");
+        Self::montgomery_invert(elems, p).expect("p is prime and the zeros are skipped")
+    }
+    // prefix[i] = v0 * v1 * ... * vi, skipping the zeros
+    // inv = prefix[n-1]^-1
+    // for i in range(n-1 to 0): out[i] = inv * prefix[i-1], inv = inv * vi
+    fn montgomery_invert(vals: &[BigUint], p: &BigUint) -> Result<Vec<BigUint>, EccError> {
+        if vals.is_empty() {
+            return Ok(Vec::new());
+        }
+        let zero = BigUint::from(0u32);
+        let one = BigUint::from(1u32);
+        let mut prefix = Vec::with_capacity(vals.len());
+        let mut acc = one.clone();
+        for val in vals {
+            if val % p != zero {
+                acc = Self::mult(&acc, val, p);
+            }
+            prefix.push(acc.clone());
+        }
+
+        let mut inv = Self::inv(&acc, p)?;
+        let mut out = vec![zero.clone(); vals.len()];
+        for i in (0..vals.len()).rev() {
+            if &vals[i] % p == zero {
+                continue;
+            }
+            let before = if i == 0 { &one } else { &prefix[i - 1] };
+            out[i] = Self::mult(&inv, before, p);
+            inv = Self::mult(&inv, &vals[i], p);
+        }
+        Ok(out)
+    }
+}
+
+// field arithmetic mod p with the modulus kept alongside, so that it is set once
+// instead of at every call. The FiniteField statics of the same name forward here
+// over a borrowed p (PrimeField<&BigUint>), which spares them a clone of p and the
+// primality test of new
+#[derive(PartialEq, Debug, Clone)]
+pub struct PrimeField<P: Borrow<BigUint> = BigUint> {
+    p: P,
+}
+impl PrimeField {
+    // fails with InvalidModulus when p is not prime
+    pub fn new(p: BigUint) -> Result<Self, EccError> {
+        if !is_probable_prime(&p) {
+            return Err(EccError::InvalidModulus);
+        }
+        Ok(PrimeField { p })
+    }
+}
+impl<'a> PrimeField<&'a BigUint> {
+    // p is not checked, as with the FiniteField statics
+    fn over(p: &'a BigUint) -> Self {
+        PrimeField { p }
+    }
+}
+impl<P: Borrow<BigUint>> PrimeField<P> {
+    pub fn p(&self) -> &BigUint {
+        self.p.borrow()
+    }
+    pub fn add(&self, c: &BigUint, d: &BigUint) -> BigUint {
+        (c + d) % self.p()
+    }
+    pub fn sub(&self, c: &BigUint, d: &BigUint) -> BigUint {
+        let p = BigInt::from(self.p().clone());
+        let diff = (BigInt::from(c.clone()) - BigInt::from(d.clone())) % &p;
+        let diff = if diff.sign() == Sign::Minus {
+            diff + p
+        } else {
+            diff
+        };
+        diff.to_biguint().expect("diff is in [0, p)")
+    }
+    pub fn mul(&self, c: &BigUint, d: &BigUint) -> BigUint {
+        (c * d) % self.p()
+    }
+    // -c mod p
+    pub fn neg(&self, c: &BigUint) -> BigUint {
+        let c = c % self.p();
+        // -0 is 0, not p
+        if c == BigUint::from(0u32) {
+            return c;
+        }
+        self.p() - c
+    }
+    // c^-1 mod p, c^(p-2) by Fermat
+    // fails with NotInvertible when c = 0 mod p
+    pub fn inv(&self, c: &BigUint) -> Result<BigUint, EccError> {
+        #[cfg(test)]
+        INVERSIONS.with(|count| count.set(count.get() + 1));
+        let p = self.p();
+        if c % p == BigUint::from(0u32) {
+            return Err(EccError::NotInvertible);
+        }
+        Ok(c.modpow(&(p - BigUint::from(2u32)), p))
+    }
+    // fails with DivisionByZero when d = 0 mod p
+    pub fn div(&self, c: &BigUint, d: &BigUint) -> Result<BigUint, EccError> {
+        let d_inv = self.inv(d).map_err(|_| EccError::DivisionByZero)?;
+        Ok(self.mul(c, &d_inv))
+    }
+    // c^e mod p
+    pub fn pow(&self, c: &BigUint, e: &BigUint) -> BigUint {
+        c.modpow(e, self.p())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::RandBigInt;
+    use rand::Rng;
+    #[test]
+    fn add() {
+        let c = BigUint::from(2u32);
+        let d = BigUint::from(2u32);
+        let p = BigUint::from(10u32);
+        assert_eq!(FiniteField::add(&c, &d, &p), BigUint::from(4u32));
+    }
+    #[test]
+    fn add1() {
+        let c = BigUint::from(2u32);
+        let d = BigUint::from(2u32);
+        let p = BigUint::from(3u32);
+        assert_eq!(FiniteField::add(&c, &d, &p), BigUint::from(1u32));
+    }
+    #[test]
+    fn mult() {
+        let c = BigUint::from(2u32);
+        let d = BigUint::from(3u32);
+        let p = BigUint::from(4u32);
+        assert_eq!(FiniteField::mult(&c, &d, &p), BigUint::from(2u32));
+    }
+    #[test]
+    #[should_panic]
+    fn mult1() {
+        let c = BigUint::from(2u32);
+        let d = BigUint::from(4u32);
+        let p = BigUint::from(3u32);
+        assert_eq!(FiniteField::add(&c, &d, &p), BigUint::from(1u32));
+    }
+    #[test]
+    fn subtract() {
+        let p = BigUint::from(7u32);
+        let sub = |c: u32, d: u32| FiniteField::subtract(&BigUint::from(c), &BigUint::from(d), &p);
+        assert_eq!(sub(5, 3), BigUint::from(2u32));
+        // d > c wraps around
+        assert_eq!(sub(3, 5), BigUint::from(5u32));
+        assert_eq!(sub(0, 6), BigUint::from(1u32));
+        assert_eq!(sub(4, 4), BigUint::from(0u32));
+        // operands at or above p
+        assert_eq!(sub(7, 0), BigUint::from(0u32));
+        assert_eq!(sub(30, 1), BigUint::from(1u32));
+        assert_eq!(sub(1, 30), BigUint::from(6u32));
+        assert_eq!(sub(100, 200), BigUint::from(5u32));
+        for c in 0u32..30 {
+            for d in 0u32..30 {
+                let expected = (c as i64 - d as i64).rem_euclid(7) as u32;
+                assert_eq!(sub(c, d), BigUint::from(expected), "{} - {}", c, d);
+            }
+        }
+
+        let p = EllipticCurve::secp256k1().p;
+        let big = &p * 3u32 + 5u32;
+        assert_eq!(
+            FiniteField::subtract(&BigUint::from(2u32), &big, &p),
+            &p - 3u32
+        );
+    }
+    #[test]
+    fn neg() {
+        let c = BigUint::from(4u32);
+        let p = BigUint::from(7u32);
+        assert_eq!(FiniteField::neg(&c, &p), BigUint::from(3u32));
+    }
+    #[test]
+    fn inv() {
+        let c = BigUint::from(4u32);
+        let p = BigUint::from(7u32);
+        assert_eq!(FiniteField::inv(&c, &p), Ok(BigUint::from(2u32)));
+    }
+    #[test]
+    fn identity_addition() {
+        let c = BigUint::from(4u32);
+        let p = BigUint::from(7u32);
+        let d = FiniteField::neg(&c, &p);
+        assert_eq!(FiniteField::add(&c, &d, &p), BigUint::from(0u32));
+    }
+    #[test]
+    fn neg_zero() {
+        let p = BigUint::from(7u32);
+        assert_eq!(
+            FiniteField::neg(&BigUint::from(0u32), &p),
+            BigUint::from(0u32)
+        );
+        for c in [0u32, 1, 6] {
+            let c = BigUint::from(c);
+            let d = FiniteField::neg(&c, &p);
+            assert!(d < p);
+            assert_eq!(FiniteField::add(&c, &d, &p), BigUint::from(0u32));
+        }
+    }
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_aliases() {
+        let p = BigUint::from(7u32);
+        for c in 0u32..7 {
+            let c = BigUint::from(c);
+            assert_eq!(FiniteField::inv_addition(&c, &p), FiniteField::neg(&c, &p));
+        }
+        for c in 1u32..7 {
+            let c = BigUint::from(c);
+            assert_eq!(
+                FiniteField::inv_multiplication(&c, &p),
+                FiniteField::inv(&c, &p)
+            );
+        }
+
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let h = ec.doubling(&g).expect("G is on the curve");
+        let (d1, d2) = (BigUint::from(12345u32), BigUint::from(678u32));
+        assert_eq!(
+            ec.scalar_mul2(&g, &d1, &h, &d2),
+            ec.mul_add(&d1, &g, &d2, &h)
+        );
+    }
+    #[test]
+    fn inv_multiplication_ct() {
+        let p = BigUint::from(7u32);
+        for c in 1u32..7 {
+            let c = BigUint::from(c);
+            assert_eq!(
+                FiniteField::inv_multiplication_ct(&c, &p),
+                FiniteField::inv(&c, &p)
+            );
+        }
+        assert_eq!(
+            FiniteField::inv_multiplication_ct(&BigUint::from(14u32), &p),
+            Err(EccError::NotInvertible)
+        );
+
+        let mut rng = rand::thread_rng();
+        for q in [
+            EllipticCurve::secp256k1().p,
+            EllipticCurve::secp256k1_order(),
+        ] {
+            for _ in 0..50 {
+                let c = rng.gen_biguint_range(&BigUint::from(1u32), &q);
+                assert_eq!(
+                    FiniteField::inv_multiplication_ct(&c, &q),
+                    FiniteField::inv(&c, &q)
+                );
+            }
+        }
+        // inputs above p are reduced first
+        let q = EllipticCurve::secp256k1_order();
+        let c = &q + BigUint::from(5u32);
+        assert_eq!(
+            FiniteField::inv_multiplication_ct(&c, &q),
+            FiniteField::inv(&BigUint::from(5u32), &q)
+        );
+    }
+    #[test]
+    fn identity_multiplication() {
+        let c = BigUint::from(4u32);
+        let p = BigUint::from(7u32);
+        let d = FiniteField::inv(&c, &p).expect("4 is invertible mod 7");
+        assert_eq!(FiniteField::mult(&c, &d, &p), BigUint::from(1u32));
+    }
+    #[test]
+    fn batch_invert() {
+        let p = EllipticCurve::secp256k1().p;
+        let mut rng = rand::thread_rng();
+        let vals: Vec<BigUint> = (0..16)
+            .map(|_| rng.gen_biguint_range(&BigUint::from(1u32), &(BigUint::from(1u32) << 128)))
+            .collect();
+
+        INVERSIONS.with(|count| count.set(0));
+        let inverses = FiniteField::batch_invert(&vals, &p).expect("Could not invert");
+        assert_eq!(INVERSIONS.with(|count| count.get()), 1);
+
+        assert_eq!(inverses.len(), vals.len());
+        for (val, inv) in vals.iter().zip(&inverses) {
+            assert_eq!(*inv, FiniteField::inv(val, &p).expect("val is not zero"));
+        }
+
+        assert_eq!(FiniteField::batch_invert(&[], &p), Ok(Vec::new()));
+        let with_zero = [BigUint::from(3u32), BigUint::from(0u32)];
+        assert_eq!(
+            FiniteField::batch_invert(&with_zero, &p),
+            Err(EccError::NotInvertible)
+        );
+    }
+    #[test]
+    fn batch_inverse() {
+        let p = EllipticCurve::secp256k1().p;
+        let mut rng = rand::thread_rng();
+        let mut vals: Vec<BigUint> = (0..16).map(|_| rng.gen_biguint_below(&p)).collect();
+        // zeros anywhere, p itself included, come out as zeros
+        vals[0] = BigUint::from(0u32);
+        vals[7] = p.clone();
+        vals[15] = BigUint::from(0u32);
+
+        INVERSIONS.with(|count| count.set(0));
+        let inverses = FiniteField::batch_inverse(&vals, &p);
+        assert_eq!(INVERSIONS.with(|count| count.get()), 1);
+
+        assert_eq!(inverses.len(), vals.len());
+        for (val, inv) in vals.iter().zip(&inverses) {
+            if val % &p == BigUint::from(0u32) {
+                assert_eq!(*inv, BigUint::from(0u32));
+            } else {
+                assert_eq!(FiniteField::mult(val, inv, &p), BigUint::from(1u32));
+            }
+        }
+
+        assert_eq!(FiniteField::batch_inverse(&[], &p), Vec::<BigUint>::new());
+        assert_eq!(
+            FiniteField::batch_inverse(&[BigUint::from(0u32)], &p),
+            vec![BigUint::from(0u32)]
+        );
+    }
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "batch_inverse needs a prime p")]
+    fn batch_inverse_composite() {
+        // 2 * 3 = 0 mod 6
+        FiniteField::batch_inverse(
+            &[BigUint::from(2u32), BigUint::from(3u32)],
+            &BigUint::from(6u32),
+        );
+    }
+    #[test]
+    fn errors() {
+        let p = BigUint::from(7u32);
+        assert_eq!(
+            FiniteField::inv(&BigUint::from(0u32), &p),
+            Err(EccError::NotInvertible)
+        );
+        assert_eq!(
+            FiniteField::inv(&BigUint::from(14u32), &p),
+            Err(EccError::NotInvertible)
+        );
+        assert_eq!(
+            FiniteField::divide(&BigUint::from(3u32), &BigUint::from(0u32), &p),
+            Err(EccError::DivisionByZero)
+        );
+        assert_eq!(
+            FiniteField::divide(&BigUint::from(3u32), &BigUint::from(4u32), &p),
+            Ok(BigUint::from(6u32))
+        );
+
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let off_curve = Point::Coordinate(BigUint::from(5u32), BigUint::from(2u32));
+        // (5 + 17, 2) is off the curve whether reduced or not
+        let unreduced = Point::Coordinate(BigUint::from(22u32), BigUint::from(2u32));
+        for bad in [&off_curve, &unreduced] {
+            assert_eq!(ec.add(&p1, bad), Err(EccError::PointNotOnCurve));
+            assert_eq!(ec.add(bad, &p1), Err(EccError::PointNotOnCurve));
+            assert_eq!(ec.subtract(&p1, bad), Err(EccError::PointNotOnCurve));
+            assert_eq!(ec.doubling(bad), Err(EccError::PointNotOnCurve));
+            let d = BigUint::from(3u32);
+            assert_eq!(ec.scalar_mul(bad, &d), Err(EccError::PointNotOnCurve));
+            assert_eq!(
+                ec.scalar_mul_montgomery(bad, &d),
+                Err(EccError::PointNotOnCurve)
+            );
+            assert_eq!(
+                ec.scalar_mul_wnaf(bad, &d, 4),
+                Err(EccError::PointNotOnCurve)
+            );
+            assert_eq!(ec.scalar_mul_ct(bad, &d), Err(EccError::PointNotOnCurve));
+        }
+        assert_eq!(
+            ec.scalar_mul_wnaf(&p1, &BigUint::from(3u32), 9),
+            Err(EccError::InvalidScalar)
+        );
+        assert_eq!(
+            ec.point_order(&p1, &BigUint::from(20u32)),
+            Err(EccError::InvalidOrder)
+        );
+
+        let err: Box<dyn std::error::Error> = Box::new(EccError::PointNotOnCurve);
+        assert_eq!(err.to_string(), "point is not on the curve");
+        assert_eq!(
+            EccError::InvalidEncoding("bad".to_string()).to_string(),
+            "invalid encoding: bad"
+        );
+    }
+    #[test]
+    fn sqrt() {
+        let p = BigUint::from(7u32);
+        assert_eq!(
+            FiniteField::sqrt(&BigUint::from(4u32), &p),
+            Some(BigUint::from(2u32))
+        );
+        // the non-zero squares mod 7 are 1, 2 = 3^2 and 4, 3 is not one of them
+        let root = FiniteField::sqrt(&BigUint::from(2u32), &p).expect("2 is a square mod 7");
+        assert!(root == BigUint::from(3u32) || root == BigUint::from(4u32));
+        assert_eq!(FiniteField::sqrt(&BigUint::from(3u32), &p), None);
+        assert_eq!(
+            FiniteField::sqrt(&BigUint::from(0u32), &p),
+            Some(BigUint::from(0u32))
+        );
+
+        // 17 and 41 are 1 mod 4 and go through Tonelli-Shanks
+        for p in [7u32, 17, 41] {
+            let p = BigUint::from(p);
+            let mut squares = 0;
+            for c in 0u32..p.to_u32_digits()[0] {
+                let c = BigUint::from(c);
+                if let Some(root) = FiniteField::sqrt(&c, &p) {
+                    assert_eq!(FiniteField::mult(&root, &root, &p), c);
+                    squares += 1;
+                }
+            }
+            // 0 and the (p - 1) / 2 quadratic residues
+            assert_eq!(squares, p.to_u32_digits()[0].div_ceil(2));
+        }
+
+        // y of the secp256k1 generator from its x
+        let ec = EllipticCurve::secp256k1();
+        let (gx, gy) = match EllipticCurve::secp256k1_generator() {
+            Point::Coordinate(x, y) => (x, y),
+            Point::Identity => unreachable!(),
+        };
+        let rhs = FiniteField::add(&gx.modpow(&BigUint::from(3u32), &ec.p), &ec.b, &ec.p);
+        let y = FiniteField::sqrt(&rhs, &ec.p).expect("x(G) is on the curve");
+        assert!(y == gy || y == FiniteField::neg(&gy, &ec.p));
+    }
+    #[test]
+    fn prime_field() {
+        // every pair mod 17 against u32 arithmetic
+        let field = PrimeField::new(BigUint::from(17u32)).expect("17 is prime");
+        for c in 0u32..17 {
+            let big_c = BigUint::from(c);
+            for d in 0u32..17 {
+                let big_d = BigUint::from(d);
+                assert_eq!(field.add(&big_c, &big_d), BigUint::from((c + d) % 17));
+                assert_eq!(field.sub(&big_c, &big_d), BigUint::from((c + 17 - d) % 17));
+                assert_eq!(field.mul(&big_c, &big_d), BigUint::from(c * d % 17));
+                let pow = (0..d).fold(1u32, |acc, _| acc * c % 17);
+                assert_eq!(field.pow(&big_c, &big_d), BigUint::from(pow));
+                match field.div(&big_c, &big_d) {
+                    Ok(q) => assert_eq!(BigUint::from(d) * q % 17u32, big_c),
+                    Err(e) => assert!(d == 0 && e == EccError::DivisionByZero),
+                }
+            }
+            assert_eq!(field.neg(&big_c), BigUint::from((17 - c) % 17));
+        }
+
+        // field identities over secp256k1, and the static API gives the same results
+        let secp_p = EllipticCurve::secp256k1().p;
+        let field = PrimeField::new(secp_p.clone()).expect("p is prime");
+        assert_eq!(field.p(), &secp_p);
+        let p = &secp_p;
+        let one = BigUint::from(1u32);
+        let zero = BigUint::from(0u32);
+        let mut rng = rand::thread_rng();
+        for _ in 0..20 {
+            let c = rng.gen_biguint_range(&one, p);
+            let d = rng.gen_biguint_range(&one, p);
+            let e = rng.gen_biguint(64);
+            assert_eq!(field.sub(&field.add(&c, &d), &d), c);
+            assert_eq!(field.add(&c, &field.neg(&c)), zero);
+            let c_inv = field.inv(&c).expect("c is not 0");
+            assert_eq!(field.mul(&c, &c_inv), one);
+            let q = field.div(&c, &d).expect("d is not 0");
+            assert_eq!(field.mul(&q, &d), c);
+            assert_eq!(
+                field.pow(&c, &(&e + 1u32)),
+                field.mul(&field.pow(&c, &e), &c)
+            );
+
+            assert_eq!(field.add(&c, &d), FiniteField::add(&c, &d, p));
+            assert_eq!(field.sub(&c, &d), FiniteField::subtract(&c, &d, p));
+            assert_eq!(field.mul(&c, &d), FiniteField::mult(&c, &d, p));
+            assert_eq!(field.neg(&c), FiniteField::neg(&c, p));
+            assert_eq!(field.inv(&c), FiniteField::inv(&c, p));
+            assert_eq!(field.div(&c, &d), FiniteField::divide(&c, &d, p));
+        }
+        assert_eq!(field.inv(&zero), Err(EccError::NotInvertible));
+        assert_eq!(field.div(&zero, &zero), Err(EccError::DivisionByZero));
+        assert_eq!(
+            PrimeField::new(BigUint::from(15u32)),
+            Err(EccError::InvalidModulus)
+        );
+    }
+    #[test]
+    fn legendre_symbol() {
+        let p = BigUint::from(7u32);
+        assert_eq!(FiniteField::legendre_symbol(&BigUint::from(1u32), &p), 1);
+        assert_eq!(FiniteField::legendre_symbol(&BigUint::from(2u32), &p), 1);
+        assert_eq!(FiniteField::legendre_symbol(&BigUint::from(3u32), &p), -1);
+        assert_eq!(FiniteField::legendre_symbol(&BigUint::from(0u32), &p), 0);
+        assert_eq!(FiniteField::legendre_symbol(&BigUint::from(14u32), &p), 0);
+        assert!(FiniteField::is_quadratic_residue(&BigUint::from(0u32), &p));
+        assert!(FiniteField::is_quadratic_residue(&BigUint::from(9u32), &p));
+        assert!(!FiniteField::is_quadratic_residue(&BigUint::from(3u32), &p));
+
+        // y^2 = x^3 + 2x + 2 mod 17: x = 1 gives 5, not a square, so no point has x = 1
+        // and (1, y) is off the curve for every y
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let rhs = BigUint::from(5u32);
+        assert!(!FiniteField::is_quadratic_residue(&rhs, &ec.p));
+        for y in 0u32..17 {
+            assert!(!ec.is_on_curve(&Point::Coordinate(BigUint::from(1u32), BigUint::from(y))));
+        }
+        // (5, 1) is on it, 1 = 5^3 + 2 5 + 2 mod 17
+        assert!(FiniteField::is_quadratic_residue(
+            &BigUint::from(1u32),
+            &ec.p
+        ));
+    }
+
+    #[test]
+    fn test_is_valid() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        assert!(ec.is_valid());
+        assert!(EllipticCurve::secp256k1().is_valid());
+        assert!(EllipticCurve::p256().is_valid());
+
+        // y^2 = x^3 mod 7 has a cusp at (0, 0)
+        assert_eq!(
+            EllipticCurve::new(
+                BigUint::from(0u32),
+                BigUint::from(0u32),
+                BigUint::from(7u32)
+            ),
+            Err(EccError::SingularCurve)
+        );
+        // y^2 = x^3 - 3x + 2 mod 7 = (x - 1)^2 (x + 2), a node at (1, 0)
+        assert_eq!(
+            EllipticCurve::new(
+                BigUint::from(4u32),
+                BigUint::from(2u32),
+                BigUint::from(7u32)
+            ),
+            Err(EccError::SingularCurve)
+        );
+        assert!(EllipticCurve::from_str("y² = x³ + 0x + 0 mod 7").is_err());
+        // a = b = 0 is singular whatever p is
+        assert!(!EllipticCurve {
+            a: BigUint::from(0u32),
+            b: BigUint::from(0u32),
+            p: EllipticCurve::secp256k1().p,
+            cofactor: BigUint::from(1u32),
+        }
+        .is_nonsingular());
+
+        // the field has to be a prime field of characteristic above 3
+        for p in [0u32, 1, 2, 3, 15, 561, 7 * 17] {
+            assert_eq!(
+                EllipticCurve::new(BigUint::from(2u32), BigUint::from(3u32), BigUint::from(p)),
+                Err(EccError::InvalidModulus),
+                "p = {}",
+                p
+            );
+        }
+        let composite = EllipticCurve::secp256k1().p * BigUint::from(3u32);
+        assert_eq!(
+            EllipticCurve::new(BigUint::from(0u32), BigUint::from(7u32), composite.clone()),
+            Err(EccError::InvalidModulus)
+        );
+        let by_hand = EllipticCurve {
+            a: BigUint::from(0u32),
+            b: BigUint::from(7u32),
+            p: composite,
+            cofactor: BigUint::from(1u32),
+        };
+        assert!(by_hand.is_nonsingular());
+        assert!(!by_hand.is_valid());
+    }
+    #[test]
+    fn test_is_probable_prime() {
+        let primes: Vec<u32> = (0u32..200)
+            .filter(|n| *n > 1 && (2..*n).all(|d| n % d != 0))
+            .collect();
+        for n in 0u32..200 {
+            assert_eq!(is_probable_prime(&BigUint::from(n)), primes.contains(&n));
+        }
+        // Carmichael numbers and strong pseudoprimes to base 2
+        for n in [561u32, 1105, 1729, 2047, 3277, 4033, 4681, 3215031751] {
+            assert!(!is_probable_prime(&BigUint::from(n)), "{}", n);
+        }
+        assert!(is_probable_prime(&EllipticCurve::secp256k1().p));
+        assert!(is_probable_prime(&EllipticCurve::secp256k1_order()));
+        assert!(is_probable_prime(&EllipticCurve::p256().p));
+        assert!(!is_probable_prime(
+            &(EllipticCurve::p256().p + BigUint::from(2u32))
+        ));
+    }
+    #[test]
+    fn test_validate_parameters() {
+        let toy = |p: u32| EllipticCurve {
+            a: BigUint::from(2u32),
+            b: BigUint::from(2u32),
+            p: BigUint::from(p),
+            cofactor: BigUint::from(1u32),
+        };
+        assert_eq!(toy(17).validate_parameters(), Ok(()));
+        assert_eq!(toy(15).validate_parameters(), Err(EccError::InvalidModulus));
+        assert_eq!(toy(3).validate_parameters(), Err(EccError::InvalidModulus));
+        assert_eq!(EllipticCurve::secp256k1().validate_parameters(), Ok(()));
+        assert_eq!(EllipticCurve::p256().validate_parameters(), Ok(()));
+
+        // y^2 = x^3 - 3x + 2 = (x - 1)^2 (x + 2) mod 17
+        let singular = EllipticCurve {
+            a: BigUint::from(14u32),
+            b: BigUint::from(2u32),
+            p: BigUint::from(17u32),
+            cofactor: BigUint::from(1u32),
+        };
+        assert_eq!(singular.validate_parameters(), Err(EccError::SingularCurve));
+
+        // 399165290221 * 798330580441 is a strong pseudoprime to the 12 fixed bases,
+        // only the extra rounds see that it is composite
+        let pseudoprime = EllipticCurve {
+            p: BigUint::parse_bytes(b"318665857834031151167461", 10).expect("Cannot parse"),
+            ..toy(17)
+        };
+        assert!(is_probable_prime(&pseudoprime.p));
+        assert_eq!(
+            pseudoprime.validate_parameters(),
+            Err(EccError::InvalidModulus)
+        );
+    }
+    #[test]
+    fn test_clear_cofactor() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        assert_eq!(ec.cofactor, BigUint::from(1u32));
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        assert_eq!(ec.clear_cofactor(&p1), Ok(p1));
+        assert_eq!(ec.clear_cofactor(&Point::Identity), Ok(Point::Identity));
+        let off_curve = Point::Coordinate(BigUint::from(5u32), BigUint::from(2u32));
+        assert_eq!(ec.clear_cofactor(&off_curve), Err(EccError::InvalidPoint));
+
+        let g = EllipticCurve::secp256k1_generator();
+        assert_eq!(EllipticCurve::secp256k1().clear_cofactor(&g), Ok(g));
+
+        // y^2 = x^3 + 1 mod 5 has 6 points, (2, 2) has order 6 and (2, 3) = 5 (2, 2)
+        // so with n = 3 and h = 2, 2 (2, 2) lands in the order-3 subgroup
+        let ec = EllipticCurve::with_cofactor(
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            BigUint::from(5u32),
+            BigUint::from(2u32),
+        )
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(2u32), BigUint::from(2u32));
+        let cleared = ec.clear_cofactor(&p1).expect("Could not clear cofactor");
+        assert_eq!(
+            cleared,
+            ec.scalar_mul(&p1, &BigUint::from(2u32))
+                .expect("Point is not on curve")
+        );
+        assert_eq!(
+            ec.scalar_mul(&cleared, &BigUint::from(3u32)),
+            Ok(Point::Identity)
+        );
+        // (4, 0) has order 2 and is cleared to the identity
+        let small = Point::Coordinate(BigUint::from(4u32), BigUint::from(0u32));
+        assert_eq!(ec.clear_cofactor(&small), Ok(Point::Identity));
+
+        assert_eq!(
+            EllipticCurve::with_cofactor(
+                BigUint::from(0u32),
+                BigUint::from(1u32),
+                BigUint::from(5u32),
+                BigUint::from(0u32),
+            ),
+            Err(EccError::InvalidScalar)
+        );
+    }
+    #[test]
+    fn test_point_order() {
+        // y^2 = x^3 + 2x + 2 mod 17 has 19 points
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        assert_eq!(
+            ec.point_order(&p1, &BigUint::from(19u32)),
+            Ok(BigUint::from(19u32))
+        );
+        // any multiple of the order is a valid hint
+        assert_eq!(
+            ec.point_order(&p1, &BigUint::from(38u32)),
+            Ok(BigUint::from(19u32))
+        );
+        assert_eq!(
+            ec.point_order(&Point::Identity, &BigUint::from(19u32)),
+            Ok(BigUint::from(1u32))
+        );
+
+        // y^2 = x^3 + 1 mod 5 has 6 points: (2, 2) has order 6, (0, 4) 3 and (4, 0) 2
+        let ec = EllipticCurve::new(
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            BigUint::from(5u32),
+        )
+        .expect("Cannot build curve");
+        let six = BigUint::from(6u32);
+        let order = |x: u32, y: u32| {
+            ec.point_order(&Point::Coordinate(BigUint::from(x), BigUint::from(y)), &six)
+        };
+        assert_eq!(order(2, 2), Ok(BigUint::from(6u32)));
+        assert_eq!(order(0, 4), Ok(BigUint::from(3u32)));
+        assert_eq!(order(4, 0), Ok(BigUint::from(2u32)));
+
+        let ec = EllipticCurve::secp256k1();
+        let n = EllipticCurve::secp256k1_order();
+        assert_eq!(
+            ec.point_order(&EllipticCurve::secp256k1_generator(), &n),
+            Ok(n)
+        );
+    }
+    #[test]
+    fn test_enumerate_points() {
+        // y^2 = x^3 + 2x + 2 mod 17 has 19 points
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let points = ec.enumerate_points().expect("Could not enumerate");
+        assert_eq!(points.len(), 19);
+        assert_eq!(points[0], Point::Identity);
+        assert!(points.iter().all(|c| ec.is_on_curve(c)));
+        // the group is cyclic of order 19, so (5, 1) generates all of it
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        for k in 0..19u32 {
+            let c = ec
+                .scalar_mul(&p1, &BigUint::from(k))
+                .expect("Point is not on curve");
+            assert!(points.contains(&c));
+        }
+
+        // y^2 = x^3 + 1 mod 5 has 6 points, (4, 0) only counts once
+        let ec = EllipticCurve::new(
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            BigUint::from(5u32),
+        )
+        .expect("Cannot build curve");
+        let points = ec.enumerate_points().expect("Could not enumerate");
+        assert_eq!(points.len(), 6);
+        assert!(points.contains(&Point::Coordinate(BigUint::from(4u32), BigUint::from(0u32))));
+
+        assert_eq!(
+            EllipticCurve::secp256k1().enumerate_points(),
+            Err(EccError::PrimeTooLarge)
+        );
+    }
+    #[test]
+    fn test_point_count_bsgs() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        // y^2 = x^3 + 2x + 2 mod 17, the order 19 of (5, 1) in test_enumerate_points
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        assert_eq!(ec.point_count_bsgs(), Ok(BigUint::from(19u32)));
+        assert_eq!(ec.trace_of_frobenius(), Ok(BigInt::from(-1)));
+
+        // past 229 the count comes from the baby-step giant-step search, a = 0 with
+        // p = 2 mod 3 is supersingular with p + 1 points
+        for p in [233u32, 1009, 1019] {
+            for a in 0u32..4 {
+                for b in 0u32..4 {
+                    let Ok(ec) =
+                        EllipticCurve::new(BigUint::from(a), BigUint::from(b), BigUint::from(p))
+                    else {
+                        continue;
+                    };
+                    let count = ec.enumerate_points().expect("Could not enumerate").len();
+                    assert_eq!(ec.point_count_bsgs(), Ok(BigUint::from(count)));
+                }
+            }
+        }
+
+        // 2^30 - 35 is the largest prime below 2^30
+        let mut rng = StdRng::seed_from_u64(5);
+        let ec = EllipticCurve::new(
+            BigUint::from(3u32),
+            BigUint::from(7u32),
+            BigUint::from(1073741789u32),
+        )
+        .expect("Cannot build curve");
+        let count = ec.point_count_bsgs().expect("p is below 2^30");
+        let t = ec.trace_of_frobenius().expect("p is below 2^30");
+        assert!(&t * &t <= BigInt::from(4u32) * BigInt::from(1073741789u32));
+        for _ in 0..5 {
+            let c = ec.random_point_with(&mut rng);
+            assert_eq!(ec.scalar_mul(&c, &count), Ok(Point::Identity));
+        }
+
+        assert_eq!(
+            EllipticCurve::secp256k1().point_count_bsgs(),
+            Err(EccError::PrimeTooLarge)
+        );
+    }
+    #[test]
+    fn test_random_point() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        // y^2 = x^3 + 2x + 2 mod 17, all 18 affine points show up
+        let toy = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let mut seen = Vec::new();
+        for _ in 0..500 {
+            let c = toy.random_point();
+            assert!(toy.is_on_curve(&c));
+            assert_ne!(c, Point::Identity);
+            if !seen.contains(&c) {
+                seen.push(c);
+            }
+        }
+        assert_eq!(seen.len(), 18);
+
+        for ec in [EllipticCurve::secp256k1(), EllipticCurve::p256()] {
+            for _ in 0..10 {
+                let c = ec.random_point();
+                assert!(ec.is_on_curve(&c));
+                assert!(matches!(&c, Point::Coordinate(x, y) if x < &ec.p && y < &ec.p));
+            }
+            // the same seed gives the same points
+            let mut rng = StdRng::seed_from_u64(7);
+            let c = ec.random_point_with(&mut rng);
+            let d = ec.random_point_with(&mut rng);
+            assert!(ec.is_on_curve(&c) && ec.is_on_curve(&d));
+            assert_ne!(c, d);
+            let mut rng = StdRng::seed_from_u64(7);
+            assert_eq!(ec.random_point_with(&mut rng), c);
+            assert_eq!(ec.random_point_with(&mut rng), d);
+        }
+    }
+    #[test]
+    fn test_hash_to_curve() {
+        let toy = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let dst = b"ecc_rust-V01-CS01-hash_to_curve";
+        let msgs: [&[u8]; 5] = [b"", b"a", b"abc", b"Hello World!", &[0xff; 100]];
+        for ec in [toy, EllipticCurve::secp256k1(), EllipticCurve::p256()] {
+            let mut seen = Vec::new();
+            for msg in msgs {
+                let c = ec.hash_to_curve(msg, dst).expect("Could not hash");
+                assert!(ec.is_on_curve(&c));
+                assert!(matches!(&c, Point::Coordinate(x, y) if x < &ec.p && y < &ec.p));
+                assert_eq!(ec.hash_to_curve(msg, dst), Ok(c.clone()));
+                seen.push(c);
+            }
+            // no two messages land on the same point, except by chance on the toy curve
+            if ec.p.bits() > 8 {
+                for i in 1..seen.len() {
+                    assert!(!seen[..i].contains(&seen[i]));
+                }
+            }
+        }
+    }
+    #[test]
+    fn test_point_in_curve() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+
+        // (6,3) + (5,1) = (10,6)
+        let p1 = Point::Coordinate(BigUint::from(6u32), BigUint::from(3u32));
+        let p2 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let p3 = Point::Coordinate(BigUint::from(10u32), BigUint::from(6u32));
 
         assert!(ec.is_on_curve(&p1));
         assert!(ec.is_on_curve(&p2));
@@ -203,58 +2673,491 @@ mod tests {
         let p5 = Point::Coordinate(BigUint::from(1u32), BigUint::from(1u32));
         let p6 = Point::Coordinate(BigUint::from(0u32), BigUint::from(1u32));
 
-        assert!(!ec.is_on_curve(&p4));
-        assert!(!ec.is_on_curve(&p5));
-        assert!(!ec.is_on_curve(&p6));
+        assert!(!ec.is_on_curve(&p4));
+        assert!(!ec.is_on_curve(&p5));
+        assert!(!ec.is_on_curve(&p6));
+    }
+    #[test]
+    fn test_point_addition() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+
+        // (6,3) + (5,1) = (10,6)
+        let p1 = Point::Coordinate(BigUint::from(6u32), BigUint::from(3u32));
+        let p2 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let pr = Point::Coordinate(BigUint::from(10u32), BigUint::from(6u32));
+
+        let res = ec.add(&p1, &p2);
+        assert_eq!(res, Ok(pr.clone()));
+
+        let res = ec.add(&p2, &p1);
+        assert_eq!(res, Ok(pr));
+    }
+    #[test]
+    fn test_point_addition_reflection() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+
+        // (5,16) + (5,1) = Identity
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(16u32));
+        let p2 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let pr = Point::Identity;
+
+        let res = ec.add(&p1, &p2);
+        assert_eq!(res, Ok(pr.clone()));
+
+        let res = ec.add(&p2, &p1);
+        assert_eq!(res, Ok(pr));
+    }
+    #[test]
+    fn test_point_ct_eq() {
+        let g = EllipticCurve::secp256k1_generator();
+        let ec = EllipticCurve::secp256k1();
+        let points = [
+            g.clone(),
+            ec.negate(&g),
+            ec.doubling(&g).expect("G is on the curve"),
+            Point::Identity,
+            Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32)),
+            Point::Coordinate(BigUint::from(5u32), BigUint::from(0u32)),
+            Point::Coordinate(BigUint::from(0u32), BigUint::from(1u32)),
+        ];
+        for c in &points {
+            for d in &points {
+                assert_eq!(c.ct_eq(d), c == d);
+            }
+        }
+        assert!(ct_eq_biguint(
+            &EllipticCurve::secp256k1_order(),
+            &EllipticCurve::secp256k1_order()
+        ));
+        // limbs past the end of the shorter one count as zero
+        let one = BigUint::from(1u32);
+        assert!(!ct_eq_biguint(&one, &((&one << 64) + &one)));
+        assert!(!ct_eq_biguint(&BigUint::from(0u32), &one));
+        assert!(ct_eq_biguint(&BigUint::from(0u32), &BigUint::from(0u32)));
+    }
+    #[test]
+    fn test_scalar_mul_signed() {
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+        let k = BigUint::from(0xdead_beefu32);
+        let kg = ec.scalar_mul(&g, &k).expect("G is on the curve");
+        let minus_k = -BigInt::from(k.clone());
+        assert_eq!(ec.scalar_mul_signed(&g, &minus_k), Ok(ec.negate(&kg)));
+        assert_eq!(ec.scalar_mul_signed(&g, &BigInt::from(k)), Ok(kg.clone()));
+        assert_eq!(
+            ec.scalar_mul_signed(&g, &BigInt::from(0u32)),
+            Ok(Point::Identity)
+        );
+        // -k G = (n - k) G
+        let n_minus_k = BigInt::from(n.clone()) + &minus_k;
+        assert_eq!(
+            ec.scalar_mul_signed(&g, &minus_k),
+            ec.scalar_mul_signed(&g, &n_minus_k)
+        );
+        // s R - e G
+        let r = kg;
+        let (s, e) = (BigInt::from(7u32), BigInt::from(3u32));
+        let sr = ec.scalar_mul_signed(&r, &s).expect("R is on the curve");
+        let minus_eg = ec.scalar_mul_signed(&g, &-e).expect("G is on the curve");
+        assert_eq!(
+            ec.add(&sr, &minus_eg),
+            ec.subtract(
+                &ec.scalar_mul_u64(&r, 7).expect("R is on the curve"),
+                &ec.scalar_mul_u64(&g, 3).expect("G is on the curve")
+            )
+        );
+
+        // y^2 = x^3 + 2x + 2 mod 17, (5, 1 + 17) is reduced to (5, 1) before it is
+        // negated, -2 (5, 1) = -(6, 3)
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let unreduced = Point::Coordinate(BigUint::from(5u32), BigUint::from(18u32));
+        assert_eq!(
+            ec.scalar_mul_signed(&unreduced, &BigInt::from(-2)),
+            Ok(Point::Coordinate(BigUint::from(6u32), BigUint::from(14u32)))
+        );
+        assert_eq!(
+            ec.scalar_mul_signed(&unreduced, &BigInt::from(0)),
+            Ok(Point::Identity)
+        );
+        let off_curve = Point::Coordinate(BigUint::from(5u32), BigUint::from(2u32));
+        assert_eq!(
+            ec.scalar_mul_signed(&off_curve, &BigInt::from(-2)),
+            Err(EccError::PointNotOnCurve)
+        );
+    }
+    #[test]
+    fn test_pohlig_hellman_dlog() {
+        // y^2 = x^3 + 2x + 2 mod 17, of prime order 19
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let g = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let order = BigUint::from(19u32);
+        let factorization = [(BigUint::from(19u32), 1)];
+        for k in 0u32..19 {
+            let q = ec.scalar_mul_u64(&g, k as u64).expect("G is on the curve");
+            assert_eq!(
+                ec.pohlig_hellman_dlog(&g, &q, &order, &factorization),
+                Some(BigUint::from(k))
+            );
+        }
+
+        // y^2 = x^3 + x + 5 mod 1013, cyclic of order 980 = 2^2 * 5 * 7^2
+        let ec = EllipticCurve::new(
+            BigUint::from(1u32),
+            BigUint::from(5u32),
+            BigUint::from(1013u32),
+        )
+        .expect("Cannot build curve");
+        let g = Point::Coordinate(BigUint::from(2u32), BigUint::from(415u32));
+        let order = BigUint::from(980u32);
+        let factorization = [
+            (BigUint::from(2u32), 2),
+            (BigUint::from(5u32), 1),
+            (BigUint::from(7u32), 2),
+        ];
+        assert_eq!(ec.point_order(&g, &order), Ok(order.clone()));
+        for k in (0u64..980).step_by(13).chain([979]) {
+            let q = ec.scalar_mul_u64(&g, k).expect("G is on the curve");
+            assert_eq!(
+                ec.pohlig_hellman_dlog(&g, &q, &order, &factorization),
+                Some(BigUint::from(k))
+            );
+        }
+
+        // 5G has order 196, and G is not one of its multiples
+        let g5 = ec.scalar_mul_u64(&g, 5).expect("G is on the curve");
+        let q = ec.scalar_mul_u64(&g5, 200).expect("5G is on the curve");
+        assert_eq!(
+            ec.pohlig_hellman_dlog(&g5, &q, &order, &factorization),
+            Some(BigUint::from(4u32))
+        );
+        assert_eq!(
+            ec.pohlig_hellman_dlog(&g5, &g, &order, &factorization),
+            None
+        );
+        // the factorization must multiply out to order
+        assert_eq!(
+            ec.pohlig_hellman_dlog(&g, &q, &order, &factorization[1..]),
+            None
+        );
+        assert_eq!(
+            ec.pohlig_hellman_dlog(&g, &q, &BigUint::from(490u32), &factorization),
+            None
+        );
+    }
+    #[test]
+    fn test_points_equal() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        // (5, 1 + 17) and (5 + 17, 1) are p1 with unreduced coordinates
+        let y_plus_p = Point::Coordinate(BigUint::from(5u32), BigUint::from(18u32));
+        let x_plus_p = Point::Coordinate(BigUint::from(22u32), BigUint::from(1u32));
+        assert_ne!(p1, y_plus_p);
+        assert!(ec.points_equal(&p1, &y_plus_p));
+        assert!(ec.points_equal(&x_plus_p, &p1));
+        assert!(ec.points_equal(&Point::Identity, &Point::Identity));
+        assert!(!ec.points_equal(&p1, &ec.negate(&p1)));
+        assert!(!ec.points_equal(&p1, &Point::Identity));
+
+        // P + (x, y + p) is a doubling, not a division by x - x = 0
+        let double = Point::Coordinate(BigUint::from(6u32), BigUint::from(3u32));
+        assert_eq!(ec.add_points(&p1, &y_plus_p), Ok(double.clone()));
+        assert_eq!(ec.add_points(&y_plus_p, &x_plus_p), Ok(double.clone()));
+        assert_eq!(ec.double_point(&y_plus_p), Ok(double.clone()));
+        // (5, 16 + 17) is -P
+        let minus_p = Point::Coordinate(BigUint::from(5u32), BigUint::from(33u32));
+        assert_eq!(ec.add_points(&y_plus_p, &minus_p), Ok(Point::Identity));
+
+        // the same through the public group law, which reduces its operands first
+        assert_eq!(ec.add(&p1, &y_plus_p), Ok(double.clone()));
+        assert_eq!(ec.add(&y_plus_p, &x_plus_p), Ok(double.clone()));
+        assert_eq!(ec.doubling(&x_plus_p), Ok(double.clone()));
+        assert_eq!(ec.add(&y_plus_p, &minus_p), Ok(Point::Identity));
+        let d = BigUint::from(2u32);
+        assert_eq!(ec.scalar_mul(&y_plus_p, &d), Ok(double.clone()));
+        assert_eq!(ec.scalar_mul_ct(&y_plus_p, &d), Ok(double.clone()));
+        for (k1, k2) in [(1u32, 0u32), (0, 1), (1, 1), (2, 0), (0, 3), (5, 6)] {
+            let (k1, k2) = (BigUint::from(k1), BigUint::from(k2));
+            let expected = ec.scalar_mul(&p1, &(&k1 + &k2));
+            assert_eq!(ec.mul_add(&k1, &y_plus_p, &k2, &x_plus_p), expected);
+            assert_eq!(ec.mul_add(&k1, &p1, &k2, &y_plus_p), expected);
+        }
+        let one = BigUint::from(1u32);
+        assert_eq!(
+            ec.mul_add(&one, &y_plus_p, &one, &minus_p),
+            Ok(Point::Identity)
+        );
+        // P + I hands back P reduced
+        assert_eq!(ec.add(&y_plus_p, &Point::Identity), Ok(p1.clone()));
+    }
+    #[test]
+    fn test_point_addition_order_two() {
+        // y^2 = x^3 + 1 mod 5, (4, 0) has order 2
+        let ec = EllipticCurve::new(
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            BigUint::from(5u32),
+        )
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(4u32), BigUint::from(0u32));
+        assert_eq!(ec.negate(&p1), p1);
+        assert_eq!(ec.add(&p1, &p1), Ok(Point::Identity));
+        assert_eq!(ec.doubling(&p1), Ok(Point::Identity));
+        // (2, 2) has order 6, 3 (2, 2) = (4, 0)
+        let p2 = Point::Coordinate(BigUint::from(2u32), BigUint::from(2u32));
+        assert_eq!(ec.scalar_mul(&p2, &BigUint::from(3u32)), Ok(p1.clone()));
+        assert_eq!(ec.add(&p1, &p2), ec.scalar_mul(&p2, &BigUint::from(4u32)));
+
+        // y^2 = x^3 + x + 4 mod 17, (3, 0) has order 2
+        let ec = EllipticCurve::new(
+            BigUint::from(1u32),
+            BigUint::from(4u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(3u32), BigUint::from(0u32));
+        assert_eq!(ec.add(&p1, &p1), Ok(Point::Identity));
+        for k in [2u32, 4, 6] {
+            let k = BigUint::from(k);
+            assert_eq!(ec.scalar_mul(&p1, &k), Ok(Point::Identity));
+            assert_eq!(ec.scalar_mul_montgomery(&p1, &k), Ok(Point::Identity));
+            assert_eq!(ec.scalar_mul_ct(&p1, &k), Ok(Point::Identity));
+            assert_eq!(ec.scalar_mul_wnaf(&p1, &k, 4), Ok(Point::Identity));
+        }
+        assert_eq!(ec.scalar_mul(&p1, &BigUint::from(3u32)), Ok(p1));
+    }
+    #[test]
+    fn test_scalar_mul_small() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let g = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+
+        // 3G = G + G + G
+        let mut sum = Point::Identity;
+        for _ in 0..3 {
+            sum = ec.add(&sum, &g).expect("Point is not on curve");
+        }
+        assert_eq!(ec.scalar_mul_u64(&g, 3), Ok(sum.clone()));
+        assert_eq!(ec.scalar_mul_i64(&g, 3), Ok(sum.clone()));
+        assert_eq!(ec.scalar_mul_i64(&g, -3), Ok(ec.negate(&sum)));
+        assert_eq!(ec.scalar_mul_i64(&g, -1), Ok(ec.negate(&g)));
+        assert_eq!(ec.scalar_mul_i64(&g, 0), Ok(Point::Identity));
+        assert_eq!(ec.scalar_mul_u64(&g, 19), Ok(Point::Identity));
+        // -16G = 3G in a group of order 19, and i64::MIN has no positive counterpart
+        assert_eq!(ec.scalar_mul_i64(&g, -16), Ok(sum));
+        assert_eq!(
+            ec.scalar_mul_i64(&g, i64::MIN),
+            Ok(ec.negate(
+                &ec.scalar_mul(&g, &(BigUint::from(1u32) << 63))
+                    .expect("Point is not on curve")
+            ))
+        );
+
+        let off_curve = Point::Coordinate(BigUint::from(5u32), BigUint::from(2u32));
+        assert_eq!(
+            ec.scalar_mul_i64(&off_curve, -2),
+            Err(EccError::PointNotOnCurve)
+        );
     }
     #[test]
-    fn test_point_addition() {
+    fn test_batch_add() {
         // y^2 = x^3 + 2x + 2 mod 17
-        let ec = EllipticCurve {
-            a: BigUint::from(2u32),
-            b: BigUint::from(2u32),
-            p: BigUint::from(17u32),
-        };
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let g = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let multiple = |k: u32| ec.scalar_mul_u64(&g, k as u64).expect("G is on the curve");
 
-        // (6,3) + (5,1) = (10,6)
-        let p1 = Point::Coordinate(BigUint::from(6u32), BigUint::from(3u32));
-        let p2 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
-        let pr = Point::Coordinate(BigUint::from(10u32), BigUint::from(6u32));
+        // general additions, a doubling, P + (-P) and both identity operands
+        let pairs: Vec<(Point, Point)> = [(1, 2), (3, 7), (4, 11), (5, 5), (6, 13), (8, 9)]
+            .iter()
+            .map(|(i, j)| (multiple(*i), multiple(*j)))
+            .chain([
+                (multiple(2), multiple(10)),
+                (multiple(9), multiple(12)),
+                (Point::Identity, multiple(3)),
+                (multiple(4), Point::Identity),
+            ])
+            .collect();
+        assert_eq!(pairs.len(), 10);
 
-        let res = ec.add(&p1, &p2);
-        assert_eq!(res, pr);
+        INVERSIONS.with(|count| count.set(0));
+        let sums = ec.batch_add(&pairs).expect("Points are on the curve");
+        let batch_inversions = INVERSIONS.with(|count| count.get());
 
-        let res = ec.add(&p2, &p1);
-        assert_eq!(res, pr);
+        INVERSIONS.with(|count| count.set(0));
+        let expected: Vec<Point> = pairs
+            .iter()
+            .map(|(c, d)| ec.add(c, d).expect("Points are on the curve"))
+            .collect();
+        let single_inversions = INVERSIONS.with(|count| count.get());
+
+        assert_eq!(sums, expected);
+        assert_eq!(sums[4], Point::Identity);
+        // one inversion against one per addition that needs a slope
+        assert_eq!(batch_inversions, 1);
+        assert_eq!(single_inversions, 7);
+
+        assert_eq!(ec.batch_add(&[]), Ok(Vec::new()));
+        let off_curve = Point::Coordinate(BigUint::from(5u32), BigUint::from(2u32));
+        assert_eq!(
+            ec.batch_add(&[(g.clone(), g), (off_curve, Point::Identity)]),
+            Err(EccError::PointNotOnCurve)
+        );
     }
     #[test]
-    fn test_point_addition_reflection() {
+    fn test_compress_point() {
         // y^2 = x^3 + 2x + 2 mod 17
-        let ec = EllipticCurve {
-            a: BigUint::from(2u32),
-            b: BigUint::from(2u32),
-            p: BigUint::from(17u32),
-        };
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        for c in ec.enumerate_points().expect("Could not enumerate") {
+            if c == Point::Identity {
+                assert_eq!(ec.compress_point(&c), Err(EccError::InvalidPoint));
+                continue;
+            }
+            let (x, parity) = ec.compress_point(&c).expect("Could not compress");
+            assert_eq!(ec.decompress_point(&x, parity), Ok(c));
+        }
+        // (5, 1) and (5, 16)
+        assert_eq!(
+            ec.compress_point(&Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32))),
+            Ok((BigUint::from(5u32), 0x03))
+        );
+        assert_eq!(
+            ec.decompress_point(&BigUint::from(5u32), 0x02),
+            Ok(Point::Coordinate(BigUint::from(5u32), BigUint::from(16u32)))
+        );
+        // 1 + 2 + 2 = 5 is not a square mod 17
+        assert_eq!(
+            ec.decompress_point(&BigUint::from(1u32), 0x02),
+            Err(EccError::DecompressionFailed)
+        );
+        assert!(matches!(
+            ec.decompress_point(&BigUint::from(5u32), 0x04),
+            Err(EccError::InvalidEncoding(_))
+        ));
+        assert!(matches!(
+            ec.decompress_point(&BigUint::from(22u32), 0x02),
+            Err(EccError::InvalidEncoding(_))
+        ));
+        let off_curve = Point::Coordinate(BigUint::from(5u32), BigUint::from(2u32));
+        assert_eq!(
+            ec.compress_point(&off_curve),
+            Err(EccError::PointNotOnCurve)
+        );
 
-        // (5,16) + (5,1) = Identity
-        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(16u32));
-        let p2 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
-        let pr = Point::Identity;
+        // y^2 = x^3 + 1 mod 5, (4, 0) is even and has no odd twin
+        let ec = EllipticCurve::new(
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            BigUint::from(5u32),
+        )
+        .expect("Cannot build curve");
+        let x = BigUint::from(4u32);
+        assert_eq!(
+            ec.decompress_point(&x, 0x02),
+            Ok(Point::Coordinate(x.clone(), BigUint::from(0u32)))
+        );
+        assert_eq!(
+            ec.decompress_point(&x, 0x03),
+            Err(EccError::DecompressionFailed)
+        );
 
-        let res = ec.add(&p1, &p2);
-        assert_eq!(res, pr);
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let (x, parity) = ec.compress_point(&g).expect("Could not compress");
+        // G is 0279be667e...
+        assert_eq!(parity, 0x02);
+        assert_eq!(ec.decompress_point(&x, parity), Ok(g.clone()));
+        assert_eq!(ec.decompress_point(&x, 0x03), Ok(ec.negate(&g)));
+    }
+    #[test]
+    fn test_scalar_mul_protected() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        for k in 0u32..=40 {
+            let k = BigUint::from(k);
+            assert_eq!(ec.scalar_mul_protected(&p1, &k), ec.scalar_mul(&p1, &k));
+        }
+        assert_eq!(
+            ec.scalar_mul_protected(&Point::Identity, &BigUint::from(7u32)),
+            Ok(Point::Identity)
+        );
+        let off_curve = Point::Coordinate(BigUint::from(5u32), BigUint::from(2u32));
+        assert_eq!(
+            ec.scalar_mul_protected(&off_curve, &BigUint::from(3u32)),
+            Err(EccError::PointNotOnCurve)
+        );
 
-        let res = ec.add(&p2, &p1);
-        assert_eq!(res, pr);
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+        let mut rng = rand::thread_rng();
+        for _ in 0..3 {
+            let k = rng.gen_biguint_below(&n);
+            assert_eq!(ec.scalar_mul_protected(&g, &k), ec.scalar_mul(&g, &k));
+        }
+        // n - 1 and a scalar wider than n
+        let k = &n - 1u32;
+        assert_eq!(ec.scalar_mul_protected(&g, &k), Ok(ec.negate(&g)));
+        let k = &n * 3u32 + 2u32;
+        assert_eq!(ec.scalar_mul_protected(&g, &k), ec.scalar_mul(&g, &k));
     }
     #[test]
     fn test_doubling() {
         // y^2 = x^3 + 2x + 2 mod 17
-        let ec = EllipticCurve {
-            a: BigUint::from(2u32),
-            b: BigUint::from(2u32),
-            p: BigUint::from(17u32),
-        };
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
 
         // (5,1) + (5,1) = 2* (5,1) = (6, 3)
         let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
@@ -262,16 +3165,28 @@ mod tests {
         // let pr = Point::Identity;
 
         let res = ec.doubling(&p1);
-        assert_eq!(res, pr);
+        assert_eq!(res, Ok(pr.clone()));
+
+        // the tangent slope at (5, 1) is (3 * 5^2 + 2) / 2 = 13 mod 17, and
+        // x3 = 13^2 - 2 * 5 = 6, y3 = 13 (5 - 6) - 1 = 3
+        let (x1, y1, s) = (
+            BigUint::from(5u32),
+            BigUint::from(1u32),
+            BigUint::from(13u32),
+        );
+        assert_eq!(ec.double_coords(&s, &x1, &y1), pr);
+        assert_eq!(ec.compute_x3_y3(&s, &x1, &y1, &x1), pr);
+        assert_eq!(ec.add(&p1, &p1), Ok(pr));
     }
     #[test]
     fn test_scalar_mul() {
         // y^2 = x^3 + 2x + 2 mod 17
-        let ec = EllipticCurve {
-            a: BigUint::from(2u32),
-            b: BigUint::from(2u32),
-            p: BigUint::from(17u32),
-        };
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
 
         // 16 (5,1) = (10, 11)
         let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
@@ -279,64 +3194,1031 @@ mod tests {
         // let pr = Point::Identity;
 
         let res = ec.scalar_mul(&p1, &BigUint::from(16u32));
-        assert_eq!(res, pr);
+        assert_eq!(res, Ok(pr));
 
         // 17 (5,1) = (6, 14)
         let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
         let pr = Point::Coordinate(BigUint::from(6u32), BigUint::from(14u32));
         let res = ec.scalar_mul(&p1, &BigUint::from(17u32));
-        assert_eq!(res, pr);
+        assert_eq!(res, Ok(pr));
 
         // 18 (5,1) = (5, 16)
         let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
         let pr = Point::Coordinate(BigUint::from(5u32), BigUint::from(16u32));
         let res = ec.scalar_mul(&p1, &BigUint::from(18u32));
-        assert_eq!(res, pr);
+        assert_eq!(res, Ok(pr));
 
         // 19 (5,1) = (10, 11)
         let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
         let pr = Point::Identity;
 
         let res = ec.scalar_mul(&p1, &BigUint::from(19u32));
-        assert_eq!(res, pr);
+        assert_eq!(res, Ok(pr));
+
+        // k (5,1) against repeated addition over the whole group
+        for k in 1..=19u32 {
+            let expected = ec.scalar_mul_naive(&p1, k);
+            assert_eq!(
+                ec.scalar_mul(&p1, &BigUint::from(k)),
+                Ok(expected.clone()),
+                "k = {}",
+                k
+            );
+            assert_eq!(
+                ec.scalar_mul_montgomery(&p1, &BigUint::from(k)),
+                Ok(expected.clone()),
+                "k = {}",
+                k
+            );
+            assert_eq!(
+                ec.scalar_mul_ct(&p1, &BigUint::from(k)),
+                Ok(expected.clone()),
+                "k = {}",
+                k
+            );
+            assert_eq!(
+                ec.scalar_mul_wnaf(&p1, &BigUint::from(k), 4),
+                Ok(expected),
+                "k = {}",
+                k
+            );
+        }
+        assert_eq!(ec.scalar_mul_naive(&p1, 0), Point::Identity);
     }
-    fn test_sec256k1() {
-        /*
-                Name	Value
-                p	0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f
-                a	0x0000000000000000000000000000000000000000000000000000000000000000
-                b	0x0000000000000000000000000000000000000000000000000000000000000007
-                G	(79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798, 483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8)
-                n	0xfffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141
-        */
-        let p = BigUint::parse_bytes(
-            b"fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
-            16,
+    #[test]
+    fn test_doubling_matches_add() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
         )
-        .expect("Cannot parse p");
-        let a = BigUint::from(0u32);
-        let b = BigUint::from(7u32);
-        let Gx = BigUint::parse_bytes(
-            b"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
-            16,
+        .expect("Cannot build curve");
+
+        let mut points = vec![Point::Identity];
+        for x in 0..17u32 {
+            for y in 0..17u32 {
+                let c = Point::Coordinate(BigUint::from(x), BigUint::from(y));
+                if ec.is_on_curve(&c) {
+                    points.push(c);
+                }
+            }
+        }
+        assert_eq!(points.len(), 19);
+
+        // any fixed point other than +-P works as Q
+        let q = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        for c in &points {
+            let doubled = ec.doubling(c);
+            assert_eq!(ec.add(c, c), doubled);
+
+            // 2P = (P + Q) + (P - Q) only goes through the chord formula
+            if *c != q && *c != ec.negate(&q) {
+                let sum = ec.add(c, &q).expect("Point is not on curve");
+                let difference = ec.subtract(c, &q).expect("Point is not on curve");
+                let chord = ec.add(&sum, &difference);
+                assert_eq!(chord, doubled);
+            }
+        }
+    }
+    #[test]
+    fn test_scalar_mul_montgomery() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
         )
-        .expect("Cannot parse Gx");
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
 
-        let Gy = BigUint::parse_bytes(
-            b"483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
-            16,
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let d = rng.gen_biguint_range(&BigUint::from(1u32), &BigUint::from(1000u32));
+            assert_eq!(ec.scalar_mul_montgomery(&p1, &d), ec.scalar_mul(&p1, &d));
+        }
+        assert_eq!(
+            ec.scalar_mul_montgomery(&p1, &BigUint::from(0u32)),
+            Ok(Point::Identity)
+        );
+
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+        for _ in 0..10 {
+            let d = rng.gen_biguint_range(&BigUint::from(1u32), &n);
+            assert_eq!(ec.scalar_mul_montgomery(&g, &d), ec.scalar_mul(&g, &d));
+        }
+
+        // the masked ladder, not a branch on each bit
+        SCALAR_MUL_CT_CALLS.with(|count| count.set(0));
+        ec.scalar_mul_montgomery(&g, &n).expect("G is on the curve");
+        assert_eq!(SCALAR_MUL_CT_CALLS.with(|count| count.get()), 1);
+    }
+    #[test]
+    fn test_is_in_prime_order_subgroup() {
+        // y^2 = x^3 + x + 4 mod 17 has 14 points, (4, 2) has order 7, (3, 0) order 2
+        // and (0, 2) order 14
+        let ec = EllipticCurve::with_cofactor(
+            BigUint::from(1u32),
+            BigUint::from(4u32),
+            BigUint::from(17u32),
+            BigUint::from(2u32),
         )
-        .expect("Cannot parse Gy");
+        .expect("Cannot build curve");
+        let q = BigUint::from(7u32);
+        let g = Point::Coordinate(BigUint::from(4u32), BigUint::from(2u32));
+        assert!(ec.is_in_prime_order_subgroup(&g, &q));
+        for k in 1u32..7 {
+            let kg = ec
+                .scalar_mul(&g, &BigUint::from(k))
+                .expect("G is on the curve");
+            assert!(ec.is_in_prime_order_subgroup(&kg, &q));
+        }
 
-        let n = BigUint::parse_bytes(
-            b"fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+        let order_two = Point::Coordinate(BigUint::from(3u32), BigUint::from(0u32));
+        let order_fourteen = Point::Coordinate(BigUint::from(0u32), BigUint::from(2u32));
+        let off_curve = Point::Coordinate(BigUint::from(3u32), BigUint::from(1u32));
+        for bad in [&Point::Identity, &order_two, &order_fourteen, &off_curve] {
+            assert!(!ec.is_in_prime_order_subgroup(bad, &q), "{:?}", bad);
+        }
+    }
+    #[test]
+    fn test_is_in_subgroup() {
+        // y^2 = x^3 + x + 4 mod 17, (4, 2) has order 7, (3, 0) order 2 and
+        // (14, 12) = (4, 2) + (3, 0) order 14
+        let ec = EllipticCurve::with_cofactor(
+            BigUint::from(1u32),
+            BigUint::from(4u32),
+            BigUint::from(17u32),
+            BigUint::from(2u32),
+        )
+        .expect("Cannot build curve");
+        let q = BigUint::from(7u32);
+        let g = Point::Coordinate(BigUint::from(4u32), BigUint::from(2u32));
+        let order_two = Point::Coordinate(BigUint::from(3u32), BigUint::from(0u32));
+        let mixed = ec.add(&g, &order_two).expect("Points are on the curve");
+        assert_eq!(
+            mixed,
+            Point::Coordinate(BigUint::from(14u32), BigUint::from(12u32))
+        );
+
+        assert!(ec.is_in_subgroup(&g, &q));
+        assert!(ec.is_in_subgroup(&Point::Identity, &q));
+        assert!(!ec.is_in_subgroup(&order_two, &q));
+        assert!(!ec.is_in_subgroup(&mixed, &q));
+        assert!(ec.is_in_subgroup(&order_two, &BigUint::from(2u32)));
+        assert!(ec.is_in_subgroup(&mixed, &BigUint::from(14u32)));
+        let off_curve = Point::Coordinate(BigUint::from(3u32), BigUint::from(1u32));
+        assert!(!ec.is_in_subgroup(&off_curve, &q));
+
+        // 2 (G + T) = 2G, back in the order-7 subgroup, while T alone is cleared to I
+        let two = BigUint::from(2u32);
+        let cleared = ec
+            .clear_cofactor_with(&mixed, &two)
+            .expect("Could not clear cofactor");
+        assert_eq!(cleared, ec.scalar_mul(&g, &two).expect("G is on the curve"));
+        assert!(ec.is_in_subgroup(&cleared, &q));
+        assert_eq!(
+            ec.clear_cofactor_with(&order_two, &two),
+            Ok(Point::Identity)
+        );
+        assert_eq!(ec.clear_cofactor(&mixed), Ok(cleared));
+        assert_eq!(
+            ec.clear_cofactor_with(&off_curve, &two),
+            Err(EccError::InvalidPoint)
+        );
+    }
+    #[test]
+    fn test_scalar_mul_reduced() {
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+        let five = BigUint::from(5u32);
+        let g5 = ec.scalar_mul(&g, &five);
+
+        let n_plus_five = &n + &five;
+        assert_eq!(ec.scalar_mul(&g, &n_plus_five), g5);
+        assert_eq!(ec.scalar_mul_reduced(&g, &n_plus_five, &n), g5);
+        let big = &n * BigUint::from(1000u32) + &five;
+        assert_eq!(ec.scalar_mul_reduced(&g, &big, &n), g5);
+
+        // multiples of the order give the identity either way
+        for k in [1u32, 2, 7] {
+            let kn = &n * BigUint::from(k);
+            assert_eq!(ec.scalar_mul(&g, &kn), Ok(Point::Identity));
+            assert_eq!(ec.scalar_mul_reduced(&g, &kn, &n), Ok(Point::Identity));
+        }
+        assert_eq!(
+            ec.scalar_mul_reduced(&g, &five, &BigUint::from(0u32)),
+            Err(EccError::InvalidOrder)
+        );
+    }
+    #[test]
+    fn test_scalar_mul_ct() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let d = rng.gen_biguint_range(&BigUint::from(1u32), &BigUint::from(1000u32));
+            assert_eq!(ec.scalar_mul_ct(&p1, &d), ec.scalar_mul(&p1, &d));
+        }
+        assert_eq!(
+            ec.scalar_mul_ct(&p1, &BigUint::from(0u32)),
+            Ok(Point::Identity)
+        );
+        assert_eq!(
+            ec.scalar_mul_ct(&Point::Identity, &BigUint::from(5u32)),
+            Ok(Point::Identity)
+        );
+
+        for (ec, g, n) in [
+            (
+                EllipticCurve::secp256k1(),
+                EllipticCurve::secp256k1_generator(),
+                EllipticCurve::secp256k1_order(),
+            ),
+            (
+                EllipticCurve::p256(),
+                EllipticCurve::p256_generator(),
+                EllipticCurve::p256_order(),
+            ),
+        ] {
+            for _ in 0..10 {
+                let d = rng.gen_biguint_range(&BigUint::from(1u32), &n);
+                assert_eq!(ec.scalar_mul_ct(&g, &d), ec.scalar_mul(&g, &d));
+            }
+            assert_eq!(ec.scalar_mul_ct(&g, &n), Ok(Point::Identity));
+            let n_minus_one = &n - BigUint::from(1u32);
+            assert_eq!(ec.scalar_mul_ct(&g, &n_minus_one), Ok(ec.negate(&g)));
+        }
+    }
+    // every multiplication by a private key, a nonce or a commitment opening goes
+    // through a constant-time ladder
+    #[test]
+    fn test_secret_paths_use_scalar_mul_ct() {
+        fn ct_calls<T>(f: impl FnOnce() -> T) -> usize {
+            SCALAR_MUL_CT_CALLS.with(|count| count.set(0));
+            f();
+            SCALAR_MUL_CT_CALLS.with(|count| count.get())
+        }
+        // y^2 = x^3 + 2x + 2 mod 17, (5, 1) has order 19
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let g = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let n = BigUint::from(19u32);
+        let d = BigUint::from(7u32);
+        let pub_key = ec.scalar_mul(&g, &d).expect("G is on the curve");
+        let hash = BigUint::from(10u32);
+        let mut rng = rand::thread_rng();
+
+        let shared = || ecdh::ecdh_shared_secret(&ec, &d, &pub_key, &n).expect("Cannot agree");
+        assert_eq!(ct_calls(shared), 1);
+        #[cfg(any(feature = "std", feature = "getrandom"))]
+        {
+            let encrypt =
+                || ecies::ECIES::encrypt(&ec, &g, &n, &pub_key, b"hi").expect("Cannot encrypt");
+            assert_eq!(ct_calls(encrypt), 2);
+        }
+
+        let schnorr = schnorr::Schnorr {
+            ec: ec.clone(),
+            a_gen: g.clone(),
+            q_order: n.clone(),
+        };
+        assert_eq!(ct_calls(|| schnorr.generate_pub_key(&d)), 1);
+        // R = k A and P = d A
+        let sign = || schnorr.sign_with_rng(&d, &hash, &mut rng);
+        assert_eq!(ct_calls(sign), 2);
+
+        let priv_key = keys::PrivateKey::new(d.clone(), &n).expect("d is in [1, q)");
+        // on secp256k1 the first k always gives a signature (on the toy curve k A can
+        // be (0, 6), whose r = 0 sends sign to another k), so R = k A is one call
+        let ecdsa = ecdsa::ECDSA::new(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+            EllipticCurve::secp256k1_order(),
+        )
+        .expect("Cannot build ECDSA");
+        assert_eq!(ct_calls(|| ecdsa.generate_pub_key(&priv_key)), 1);
+        #[cfg(any(feature = "std", feature = "getrandom"))]
+        {
+            let sign = || ecdsa.sign(&priv_key, &hash).expect("Could not sign");
+            assert_eq!(ct_calls(sign), 1);
+        }
+        let sign = || {
+            ecdsa
+                .sign_deterministic(&priv_key, &hash)
+                .expect("Could not sign")
+        };
+        assert_eq!(ct_calls(sign), 1);
+        // the first RFC 6979 k for this key and hash gives r != 0 on the toy curve
+        let sign = || ecdsa::sign_generic(&ec, &g, &n, &priv_key, &hash);
+        assert_eq!(ct_calls(sign), 1);
+
+        let g2 = ec.doubling(&g).expect("G is on the curve");
+        let pedersen =
+            pedersen::PedersenCommitment::new(ec.clone(), g.clone(), g2).expect("Cannot build");
+        let commit = || pedersen.commit(&BigUint::from(3u32), &BigUint::from(5u32));
+        assert_eq!(ct_calls(commit), 2);
+
+        let master = bip32::ExtendedPrivKey::from_seed(&[7u8; 32]).expect("Cannot derive");
+        assert_eq!(ct_calls(|| master.child_private_key(0)), 1);
+        assert_eq!(ct_calls(|| master.to_public(&ec, &g, &n)), 1);
+
+        let ed25519 = edwards::Ed25519::new();
+        assert_eq!(ct_calls(|| ed25519.public_key(&[7u8; 32])), 1);
+    }
+    #[test]
+    fn test_scalar_mul_base() {
+        // y^2 = x^3 + 2x + 2 mod 17, (5, 1) has order 19 so some entries are I
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        for window in 1..=4 {
+            let table = ec.precompute_base(&p1, window).expect("p1 is on the curve");
+            assert_eq!(table.base(), &p1);
+            // past 6 bits the table runs out and scalar_mul takes over
+            for d in 0u32..200 {
+                let d = BigUint::from(d);
+                assert_eq!(ec.scalar_mul_base(&table, &d), ec.scalar_mul(&p1, &d));
+            }
+        }
+        assert_eq!(ec.precompute_base(&p1, 0), Err(EccError::InvalidScalar));
+        assert_eq!(ec.precompute_base(&p1, 9), Err(EccError::InvalidScalar));
+        let off_curve = Point::Coordinate(BigUint::from(5u32), BigUint::from(2u32));
+        assert_eq!(
+            ec.precompute_base(&off_curve, 4),
+            Err(EccError::PointNotOnCurve)
+        );
+
+        let mut rng = rand::thread_rng();
+        for (ec, g, n) in [
+            (
+                EllipticCurve::secp256k1(),
+                EllipticCurve::secp256k1_generator(),
+                EllipticCurve::secp256k1_order(),
+            ),
+            (
+                EllipticCurve::p256(),
+                EllipticCurve::p256_generator(),
+                EllipticCurve::p256_order(),
+            ),
+        ] {
+            let table = ec.precompute_base(&g, 4).expect("G is on the curve");
+            for _ in 0..50 {
+                let d = rng.gen_biguint_range(&BigUint::from(1u32), &n);
+                assert_eq!(ec.scalar_mul_base(&table, &d), ec.scalar_mul(&g, &d));
+            }
+            assert_eq!(ec.scalar_mul_base(&table, &n), Ok(Point::Identity));
+            let n_minus_one = &n - BigUint::from(1u32);
+            assert_eq!(ec.scalar_mul_base(&table, &n_minus_one), Ok(ec.negate(&g)));
+            let wide = &n << 8;
+            assert_eq!(ec.scalar_mul_base(&table, &wide), Ok(Point::Identity));
+        }
+    }
+    #[test]
+    fn test_scalar_mul_wnaf() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let d = rng.gen_biguint_range(&BigUint::from(1u32), &BigUint::from(1000u32));
+            let expected = ec.scalar_mul(&p1, &d);
+            for window in 2..=4 {
+                assert_eq!(ec.scalar_mul_wnaf(&p1, &d, window), expected);
+            }
+        }
+
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+        for _ in 0..5 {
+            let d = rng.gen_biguint_range(&BigUint::from(1u32), &n);
+            let expected = ec.scalar_mul(&g, &d);
+            for window in 2..=4 {
+                assert_eq!(ec.scalar_mul_wnaf(&g, &d, window), expected);
+            }
+        }
+    }
+    #[test]
+    fn test_scalar_mul_wnaf_additions() {
+        // double-and-add: one addition per set bit below the top one
+        // wnaf: one addition per non-zero digit plus the table precomputation
+        let window = EllipticCurve::WNAF_WINDOW;
+        let mut rng = rand::thread_rng();
+        let mut binary_additions = 0;
+        let mut wnaf_additions = 0;
+        for _ in 0..20 {
+            let d = rng.gen_biguint(256);
+            binary_additions += (0..d.bits() - 1).filter(|i| d.bit(*i)).count();
+            let digits = EllipticCurve::wnaf_digits(&d, window);
+            wnaf_additions += digits.iter().filter(|digit| **digit != 0).count();
+            wnaf_additions += (1 << (window - 2)) - 1;
+        }
+        // about 128 against 256 / (w + 1) + 3 ~ 54 per 256-bit scalar
+        assert!(wnaf_additions * 2 < binary_additions);
+    }
+    #[test]
+    fn test_wnaf_digits() {
+        // 7 = 8 - 1 with w = 2
+        assert_eq!(
+            EllipticCurve::wnaf_digits(&BigUint::from(7u32), 2),
+            vec![-1, 0, 0, 1]
+        );
+        // 7 fits in a single digit with w = 4
+        assert_eq!(EllipticCurve::wnaf_digits(&BigUint::from(7u32), 4), vec![7]);
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let d = rng.gen_biguint(256);
+            for window in 2..=6 {
+                let digits = EllipticCurve::wnaf_digits(&d, window);
+                let mut value = num_bigint::BigInt::from(0);
+                for digit in digits.iter().rev() {
+                    value = value * 2 + digit;
+                }
+                assert_eq!(value, num_bigint::BigInt::from(d.clone()));
+            }
+        }
+    }
+    #[test]
+    fn test_mul_add_shared_chain() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let p2 = Point::Coordinate(BigUint::from(6u32), BigUint::from(3u32));
+
+        for d1 in 1..40u32 {
+            for d2 in 1..40u32 {
+                let d1 = BigUint::from(d1);
+                let d2 = BigUint::from(d2);
+                let d1p1 = ec.scalar_mul(&p1, &d1).expect("Point is not on curve");
+                let d2p2 = ec.scalar_mul(&p2, &d2).expect("Point is not on curve");
+                let expected = ec.add(&d1p1, &d2p2);
+                assert_eq!(ec.mul_add(&d1, &p1, &d2, &p2), expected);
+            }
+        }
+
+        // one inversion for c1 + c2 and one back to affine, whatever the scalars
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+        let mut rng = rand::thread_rng();
+        let h = ec
+            .scalar_mul(&g, &rng.gen_biguint_range(&BigUint::from(1u32), &n))
+            .expect("G is on the curve");
+        for _ in 0..5 {
+            let d1 = rng.gen_biguint_range(&BigUint::from(1u32), &n);
+            let d2 = rng.gen_biguint_range(&BigUint::from(1u32), &n);
+            let d1g = ec.scalar_mul(&g, &d1).expect("G is on the curve");
+            let d2h = ec.scalar_mul(&h, &d2).expect("H is on the curve");
+            INVERSIONS.with(|count| count.set(0));
+            let res = ec.mul_add(&d1, &g, &d2, &h);
+            assert_eq!(INVERSIONS.with(|count| count.get()), 2);
+            assert_eq!(res, ec.add(&d1g, &d2h));
+        }
+    }
+    #[test]
+    fn test_mul_add() {
+        // y^2 = x^3 + 2x + 2 mod 17, 100 random scalars and points
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let points = ec.enumerate_points().expect("Could not enumerate");
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let k1 = rng.gen_biguint_range(&BigUint::from(0u32), &BigUint::from(1000u32));
+            let k2 = rng.gen_biguint_range(&BigUint::from(0u32), &BigUint::from(1000u32));
+            let p1 = &points[rng.gen_range(0..points.len())];
+            let p2 = &points[rng.gen_range(0..points.len())];
+            let k1p1 = ec.scalar_mul(p1, &k1).expect("p1 is on the curve");
+            let k2p2 = ec.scalar_mul(p2, &k2).expect("p2 is on the curve");
+            assert_eq!(ec.mul_add(&k1, p1, &k2, p2), ec.add(&k1p1, &k2p2));
+        }
+
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+        for _ in 0..5 {
+            let k1 = rng.gen_biguint_range(&BigUint::from(0u32), &n);
+            let k2 = rng.gen_biguint_range(&BigUint::from(0u32), &n);
+            let q = ec
+                .scalar_mul(&g, &rng.gen_biguint_range(&BigUint::from(1u32), &n))
+                .expect("G is on the curve");
+            let k1g = ec.scalar_mul(&g, &k1).expect("G is on the curve");
+            let k2q = ec.scalar_mul(&q, &k2).expect("Q is on the curve");
+            assert_eq!(ec.mul_add(&k1, &g, &k2, &q), ec.add(&k1g, &k2q));
+        }
+        // G - G and a zero scalar
+        let n_minus_one = &n - BigUint::from(1u32);
+        let one = BigUint::from(1u32);
+        assert_eq!(ec.mul_add(&one, &g, &n_minus_one, &g), Ok(Point::Identity));
+        assert_eq!(
+            ec.mul_add(&BigUint::from(0u32), &g, &one, &Point::Identity),
+            Ok(Point::Identity)
+        );
+    }
+    #[test]
+    fn test_multi_scalar_mul() {
+        // y^2 = x^3 + 2x + 2 mod 17, sums of up to 4 random products
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let points = ec.enumerate_points().expect("Could not enumerate");
+        let mut rng = rand::thread_rng();
+        for n in 0..5 {
+            let terms: Vec<(Point, BigUint)> = (0..n)
+                .map(|_| {
+                    (
+                        points[rng.gen_range(0..points.len())].clone(),
+                        rng.gen_biguint_range(&BigUint::from(0u32), &BigUint::from(1000u32)),
+                    )
+                })
+                .collect();
+            let mut expected = Point::Identity;
+            for (c, d) in &terms {
+                let dc = ec.scalar_mul(c, d).expect("c is on the curve");
+                expected = ec.add(&expected, &dc).expect("dc is on the curve");
+            }
+            let terms: Vec<_> = terms.iter().map(|(c, d)| (c, d)).collect();
+            assert_eq!(ec.multi_scalar_mul(&terms), Ok(expected));
+        }
+
+        let off_curve = Point::Coordinate(BigUint::from(5u32), BigUint::from(2u32));
+        assert_eq!(
+            ec.multi_scalar_mul(&[(&off_curve, &BigUint::from(1u32))]),
+            Err(EccError::PointNotOnCurve)
+        );
+    }
+    #[test]
+    fn test_scalar_mul_glv() {
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+
+        let lambda = BigUint::parse_bytes(
+            b"5363ad4cc05c30e0a5261c028812645a122e22ea20816678df02967c1b23bd72",
             16,
         )
-        .expect("Cannot parse n");
+        .expect("Cannot parse lambda");
+        let n_int = BigInt::from(n.clone());
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let d = rng.gen_biguint_range(&BigUint::from(1u32), &n);
+            let (d1, d2) = EllipticCurve::secp256k1_endomorphism_split(&d, &n);
+            assert!(d1.bits() <= 129 && d2.bits() <= 129);
+            let recombined = (d1 + d2 * BigInt::from(lambda.clone())) % &n_int;
+            let recombined = (recombined + &n_int) % &n_int;
+            assert_eq!(recombined, BigInt::from(d.clone()));
+            assert_eq!(ec.scalar_mul_glv(&g, &d), ec.scalar_mul(&g, &d));
+        }
+
+        // lambda G = (beta gx, gy), and beta is a cube root of unity
+        let beta = EllipticCurve::secp256k1_beta();
+        assert_eq!(
+            beta.modpow(&BigUint::from(3u32), &ec.p),
+            BigUint::from(1u32)
+        );
+        let phi_g = EllipticCurve::secp256k1_apply_endomorphism(&g, &beta, &ec.p);
+        assert_eq!(ec.scalar_mul(&g, &lambda), Ok(phi_g));
+        assert_eq!(
+            EllipticCurve::secp256k1_apply_endomorphism(&Point::Identity, &beta, &ec.p),
+            Point::Identity
+        );
+        assert_eq!(ec.scalar_mul_glv(&g, &lambda), ec.scalar_mul(&g, &lambda));
+        assert_eq!(ec.scalar_mul_glv(&g, &n), Ok(Point::Identity));
+
+        // other curves fall back to scalar_mul
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let d = BigUint::from(16u32);
+        assert_eq!(ec.scalar_mul_glv(&p1, &d), ec.scalar_mul(&p1, &d));
+    }
+    #[test]
+    fn test_scalar_mul_jacobian() {
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..5 {
+            let d = rng.gen_biguint_range(&BigUint::from(1u32), &n);
+
+            INVERSIONS.with(|count| count.set(0));
+            let res = ec.scalar_mul(&g, &d);
+            assert_eq!(INVERSIONS.with(|count| count.get()), 1);
+
+            // the ladder stays in jacobian coordinates as well
+            INVERSIONS.with(|count| count.set(0));
+            let ladder = ec.scalar_mul_montgomery(&g, &d);
+            assert_eq!(INVERSIONS.with(|count| count.get()), 1);
+
+            assert_eq!(res, ladder);
+        }
+
+        // P-256 exercises the a Z^4 term of the jacobian doubling
+        let ec = EllipticCurve::p256();
+        let g = EllipticCurve::p256_generator();
+        let n = EllipticCurve::p256_order();
+        for _ in 0..5 {
+            let d = rng.gen_biguint_range(&BigUint::from(1u32), &n);
+            assert_eq!(ec.scalar_mul(&g, &d), ec.scalar_mul_montgomery(&g, &d));
+        }
+        assert_eq!(ec.scalar_mul(&g, &BigUint::from(0u32)), Ok(Point::Identity));
+    }
+    #[test]
+    fn test_from_hex() {
+        let ec = EllipticCurve::from_hex(
+            "0",
+            "7",
+            "0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f",
+        );
+        assert_eq!(ec, Ok(EllipticCurve::secp256k1()));
+        let g = Point::from_hex(
+            "79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+            "0X483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+        );
+        assert_eq!(g, Ok(EllipticCurve::secp256k1_generator()));
+        assert_eq!(
+            EllipticCurve::from_hex("2", "2", "11"),
+            EllipticCurve::new(
+                BigUint::from(2u32),
+                BigUint::from(2u32),
+                BigUint::from(17u32)
+            )
+        );
+
+        for bad in ["", "0x", "xyz", "12g4", "1_0", "-5", "0x 5", "0x0x5"] {
+            assert!(
+                matches!(parse_hex(bad), Err(EccError::InvalidEncoding(_))),
+                "{:?} parsed",
+                bad
+            );
+            assert!(EllipticCurve::from_hex("2", bad, "11").is_err());
+            assert!(Point::from_hex(bad, "1").is_err());
+        }
+        // parses fine but the curve itself is singular
+        assert_eq!(
+            EllipticCurve::from_hex("0", "0", "11"),
+            Err(EccError::SingularCurve)
+        );
+    }
+    #[test]
+    fn test_point_hash_ord() {
+        use std::collections::{BTreeSet, HashSet};
+        let point = |x: u32, y: u32| Point::Coordinate(BigUint::from(x), BigUint::from(y));
+
+        let mut set = HashSet::new();
+        assert!(set.insert(point(5, 1)));
+        assert!(set.insert(point(5, 16)));
+        assert!(set.insert(Point::Identity));
+        assert!(!set.insert(point(5, 1)));
+        assert!(!set.insert(Point::Identity));
+        // (0, 0) is a point like any other, not a stand-in for the identity
+        assert!(set.insert(point(0, 0)));
+        assert_eq!(set.len(), 4);
+        assert!(set.contains(&point(5, 16)));
+
+        assert!(Point::Identity < point(0, 0));
+        assert!(point(0, 16) < point(5, 1));
+        assert!(point(5, 1) < point(5, 16));
+        assert_eq!(point(5, 1).cmp(&point(5, 1)), Ordering::Equal);
+        let sorted: Vec<Point> = [point(6, 3), Point::Identity, point(5, 16), point(5, 1)]
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            sorted,
+            vec![Point::Identity, point(5, 1), point(5, 16), point(6, 3)]
+        );
+
+        // enumerate_points lists the identity and then increasing x, which is this order
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let points = ec.enumerate_points().expect("Could not enumerate");
+        assert!(points.windows(2).all(|w| w[0] < w[1]));
+    }
+    #[test]
+    fn test_point_from_str() {
+        let g = EllipticCurve::secp256k1_generator();
+        assert_eq!(
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798,\
+             0x483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"
+                .parse::<Point>(),
+            Ok(g.clone())
+        );
+        assert_eq!(
+            " 5 , 1 ".parse::<Point>(),
+            Ok(Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32)))
+        );
+        assert_eq!("identity".parse::<Point>(), Ok(Point::Identity));
+
+        // Display is hex and parses back
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(26u32));
+        assert_eq!(p1.to_string(), "(0x5, 0x1a)");
+        assert_eq!(Point::Identity.to_string(), "Identity");
+        for c in [p1, g.clone(), Point::Identity] {
+            assert_eq!(c.to_string().parse::<Point>(), Ok(c));
+        }
+        assert_eq!(
+            g.to_string(),
+            "(0x79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798, \
+             0x483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8)"
+        );
+        for bad in [
+            "", "5", "5,", ",1", "5,1,2", "5;1", "x,1", "5,0x", "IDENTITY", "(5,1", "0",
+        ] {
+            assert!(
+                matches!(bad.parse::<Point>(), Err(EccError::InvalidEncoding(_))),
+                "{:?} parsed",
+                bad
+            );
+        }
+
+        // odd length is fine, it is a number and not a byte string
+        assert_eq!(scalar_from_hex("abc"), Ok(BigUint::from(0xabcu32)));
+        assert_eq!(
+            scalar_from_hex("0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141"),
+            Ok(EllipticCurve::secp256k1_order())
+        );
+        assert!(matches!(
+            scalar_from_hex("12 34"),
+            Err(EccError::InvalidEncoding(_))
+        ));
+    }
+    #[test]
+    fn test_from_der() {
+        // openssl ecparam -name secp256k1 -param_enc explicit -outform DER
+        let secp256k1 = hex::decode(
+            "3081e0020101302c06072a8648ce3d0101022100ffffffffffffffffffffffffffffffffffff\
+             fffffffffffffffffffefffffc2f304404200000000000000000000000000000000000000000\
+             0000000000000000000000000420000000000000000000000000000000000000000000000000\
+             000000000000000704410479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f281\
+             5b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b802\
+             2100fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141020101",
+        )
+        .expect("Cannot decode hex");
+        assert_eq!(
+            EllipticCurve::from_der(&secp256k1),
+            Ok((
+                EllipticCurve::secp256k1(),
+                EllipticCurve::secp256k1_generator(),
+                EllipticCurve::secp256k1_order()
+            ))
+        );
+        // prime256v1 carries the seed in the curve sequence
+        let p256 = hex::decode(
+            "3081f7020101302c06072a8648ce3d0101022100ffffffff0000000100000000000000000000\
+             0000ffffffffffffffffffffffff305b0420ffffffff00000001000000000000000000000000\
+             fffffffffffffffffffffffc04205ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f6\
+             3bce3c3e27d2604b031500c49d360886e704936a6678e1139d26b7819f7e900441046b17d1f2\
+             e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c2964fe342e2fe1a7f9b8ee7\
+             eb4a7c0f9e162bce33576b315ececbb6406837bf51f5022100ffffffff00000000ffffffffff\
+             ffffffbce6faada7179e84f3b9cac2fc632551020101",
+        )
+        .expect("Cannot decode hex");
+        assert_eq!(
+            EllipticCurve::from_der(&p256),
+            Ok((
+                EllipticCurve::p256(),
+                EllipticCurve::p256_generator(),
+                EllipticCurve::p256_order()
+            ))
+        );
+
+        let invalid = |der: &[u8]| {
+            matches!(
+                EllipticCurve::from_der(der),
+                Err(EccError::InvalidEncoding(_))
+            )
+        };
+        assert!(invalid(&secp256k1[..secp256k1.len() - 1]));
+        assert!(invalid(&[secp256k1.as_slice(), &[0]].concat()));
+        // the cofactor is optional, dropping it and fixing the outer length still parses
+        let mut no_cofactor = secp256k1[..secp256k1.len() - 3].to_vec();
+        no_cofactor[2] -= 3;
+        assert!(EllipticCurve::from_der(&no_cofactor).is_ok());
+        // version 2
+        let mut bad = secp256k1.clone();
+        bad[5] = 2;
+        assert!(invalid(&bad));
+        // characteristic-two field OID 1.2.840.10045.1.2
+        let mut bad = secp256k1.clone();
+        bad[16] = 2;
+        assert!(invalid(&bad));
+        // compressed base point
+        let mut bad = secp256k1.clone();
+        // past the outer header, version, fieldID, curve and the OCTET STRING header
+        let base = 3 + 3 + 46 + 70 + 2;
+        assert_eq!(bad[base], 0x04);
+        bad[base] = 0x02;
+        assert!(invalid(&bad));
+        // base point off the curve
+        let mut bad = secp256k1.clone();
+        bad[base + 1] ^= 1;
+        assert_eq!(EllipticCurve::from_der(&bad), Err(EccError::InvalidPoint));
+    }
+    #[test]
+    fn test_display_from_str() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        assert_eq!(ec.to_string(), "y² = x³ + 0x2x + 0x2 mod 0x11");
+        assert_eq!(ec.to_string().parse::<EllipticCurve>(), Ok(ec));
+
+        let ec = EllipticCurve::secp256k1();
+        let parsed: EllipticCurve =
+            "y² = x³ + 0x + 7 mod 0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f"
+                .parse()
+                .expect("Cannot parse secp256k1");
+        assert_eq!(parsed, ec);
+        assert_eq!(ec.to_string().parse::<EllipticCurve>(), Ok(ec));
+
+        // y^2 = x^3 + x + 4 mod 17 has 14 points, h = 2
+        let ec = EllipticCurve::with_cofactor(
+            BigUint::from(1u32),
+            BigUint::from(4u32),
+            BigUint::from(17u32),
+            BigUint::from(2u32),
+        )
+        .expect("Cannot build curve");
+        assert_eq!(ec.to_string(), "y² = x³ + 0x1x + 0x4 mod 0x11, h = 0x2");
+        assert_eq!(ec.to_string().parse::<EllipticCurve>(), Ok(ec));
+        let parsed: EllipticCurve = "y² = x³ + 1x + 4 mod 17, h = 2"
+            .parse()
+            .expect("Cannot parse curve");
+        assert_eq!(parsed.cofactor, BigUint::from(2u32));
+
+        assert!("y² = x³ + 2 + 2 mod 17".parse::<EllipticCurve>().is_err());
+        assert!("y² = x³ + 2x + 2 mod 17 h = 2"
+            .parse::<EllipticCurve>()
+            .is_err());
+        assert!("y² = x³ + 2x + 2 mod".parse::<EllipticCurve>().is_err());
+        assert!("y² = x³ + 2x + 0xzz mod 17"
+            .parse::<EllipticCurve>()
+            .is_err());
+        // parse_bytes would take the underscores and the sign
+        for p in ["0x1_1", "1_7", "+17", "0x+11", "0x", "-17"] {
+            let s = format!("y² = x³ + 2x + 2 mod {}", p);
+            assert!(matches!(
+                s.parse::<EllipticCurve>(),
+                Err(EccError::InvalidEncoding(_))
+            ));
+        }
+        assert!("y² = x³ + +2x + 2 mod 17".parse::<EllipticCurve>().is_err());
+    }
+    #[test]
+    fn test_negate() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+
+        // -(5,1) = (5,16)
+        let p1 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let pr = Point::Coordinate(BigUint::from(5u32), BigUint::from(16u32));
+        assert_eq!(ec.negate(&p1), pr);
+        assert!(ec.is_on_curve(&ec.negate(&p1)));
+
+        // P + (-P) = Identity
+        assert_eq!(ec.add(&p1, &ec.negate(&p1)), Ok(Point::Identity));
+
+        // -Identity = Identity
+        assert_eq!(ec.negate(&Point::Identity), Point::Identity);
+    }
+    #[test]
+    fn test_subtract() {
+        // y^2 = x^3 + 2x + 2 mod 17
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
 
-        let ec = EllipticCurve { a: a, b: b, p: p };
-        let G = Point::Coordinate(Gx, Gy);
+        // (10,6) - (5,1) = (6,3)
+        let p1 = Point::Coordinate(BigUint::from(10u32), BigUint::from(6u32));
+        let p2 = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let pr = Point::Coordinate(BigUint::from(6u32), BigUint::from(3u32));
+        assert_eq!(ec.subtract(&p1, &p2), Ok(pr));
+
+        // (10,6) - (6,3) = (5,1)
+        let p2 = Point::Coordinate(BigUint::from(6u32), BigUint::from(3u32));
+        let pr = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        assert_eq!(ec.subtract(&p1, &p2), Ok(pr));
+
+        // (10,6) - I = (10,6)
+        assert_eq!(ec.subtract(&p1, &Point::Identity), Ok(p1.clone()));
+
+        // P - P = I
+        assert_eq!(ec.subtract(&p1, &p1), Ok(Point::Identity));
+        assert_eq!(ec.add(&p1, &ec.negate(&p1)), Ok(Point::Identity));
+
+        // I - P = -P, I - I = I
+        assert_eq!(ec.subtract(&Point::Identity, &p1), Ok(ec.negate(&p1)));
+        assert_eq!(
+            ec.subtract(&Point::Identity, &Point::Identity),
+            Ok(Point::Identity)
+        );
+
+        // P - (-P) = 2P
+        assert_eq!(ec.subtract(&p1, &ec.negate(&p1)), ec.doubling(&p1));
+    }
+    #[test]
+    fn test_sec256k1() {
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        let n = EllipticCurve::secp256k1_order();
+
+        assert!(ec.is_on_curve(&g));
+        assert_eq!(ec.scalar_mul(&g, &n), Ok(Point::Identity));
+    }
+    #[test]
+    fn test_p256() {
+        let ec = EllipticCurve::p256();
+        let g = EllipticCurve::p256_generator();
+        let n = EllipticCurve::p256_order();
+
+        assert!(ec.is_on_curve(&g));
+        assert_eq!(ec.scalar_mul(&g, &n), Ok(Point::Identity));
 
-        assert_eq!(ec.scalar_mul(&G, &n), Point::Identity);
+        // known answers for 2G and 3G: the tangent slope (3x^2 + a) / 2y and the
+        // jacobian a Z^4 term both pick up a = -3
+        let g2 = Point::from_hex(
+            "7CF27B188D034F7E8A52380304B51AC3C08969E277F21B35A60B48FC47669978",
+            "07775510DB8ED040293D9AC69F7430DBBA7DADE63CE982299E04B79D227873D1",
+        )
+        .expect("Cannot parse 2G");
+        let g3 = Point::from_hex(
+            "5ECBE4D1A6330A44C8F7EF951D4BF165E6C6B721EFADA985FB41661BC6E7FD6C",
+            "8734640C4998FF7E374B06CE1A64A2ECD82AB036384FB83D9A79B127A27D5032",
+        )
+        .expect("Cannot parse 3G");
+        assert!(ec.is_on_curve(&g2));
+        assert_eq!(ec.doubling(&g), Ok(g2.clone()));
+        assert_eq!(ec.add(&g, &g), Ok(g2.clone()));
+        assert_eq!(ec.scalar_mul(&g, &BigUint::from(2u32)), Ok(g2.clone()));
+        assert_eq!(ec.add(&g2, &g), Ok(g3.clone()));
+        assert_eq!(ec.scalar_mul(&g, &BigUint::from(3u32)), Ok(g3.clone()));
+        // n - 1 runs every doubling of the ladder with a full-width scalar
+        assert_eq!(
+            ec.scalar_mul(&g, &(&n - BigUint::from(1u32))),
+            Ok(ec.negate(&g))
+        );
+        // with a = 0 the same points are off the curve
+        let a_zero = EllipticCurve::new(BigUint::from(0u32), ec.b.clone(), ec.p.clone())
+            .expect("Cannot build curve");
+        assert!(!a_zero.is_on_curve(&g2));
     }
 }
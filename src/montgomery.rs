@@ -0,0 +1,204 @@
+use crate::{EccError, FiniteField, Point};
+use num_bigint::BigUint;
+
+// B y^2 = x^3 + A x^2 + x mod p
+// the ladder below only ever looks at x (called u in RFC 7748), so a point and its
+// negation give the same results and there is no y to recover
+#[derive(PartialEq, Debug, Clone)]
+pub struct MontgomeryCurve {
+    pub a: BigUint,
+    pub b: BigUint,
+    pub p: BigUint,
+}
+
+impl MontgomeryCurve {
+    pub fn new(a: BigUint, b: BigUint, p: BigUint) -> Result<Self, EccError> {
+        // B (A^2 - 4) != 0
+        let a2 = FiniteField::mult(&a, &a, &p);
+        let a2_minus_4 = FiniteField::subtract(&a2, &BigUint::from(4u32), &p);
+        if FiniteField::mult(&b, &a2_minus_4, &p) == BigUint::from(0u32) {
+            return Err(EccError::SingularCurve);
+        }
+        Ok(MontgomeryCurve { a, b, p })
+    }
+    pub fn is_on_curve(&self, c: &Point) -> bool {
+        match c {
+            Point::Coordinate(x, y) => {
+                let by2 = FiniteField::mult(&self.b, &FiniteField::mult(y, y, &self.p), &self.p);
+                let x2 = FiniteField::mult(x, x, &self.p);
+                let x3 = FiniteField::mult(&x2, x, &self.p);
+                let ax2 = FiniteField::mult(&self.a, &x2, &self.p);
+                let rhs = FiniteField::add(&FiniteField::add(&x3, &ax2, &self.p), x, &self.p);
+                by2 == rhs
+            }
+            Point::Identity => true,
+        }
+    }
+    // x(k P) from x(P) = u, RFC 7748 section 5
+    // x2 = 1, z2 = 0, x3 = u, z3 = 1, a24 = (A - 2) / 4
+    // for i in range(bits(p)-1 to 0)
+    //     cswap(bit(i))
+    //     x3, z3 = (DA + CB)^2, u (DA - CB)^2
+    //     x2, z2 = AA BB, E (AA + a24 E)
+    //     cswap(bit(i))
+    // with A = x2 + z2, B = x2 - z2, C = x3 + z3, D = x3 - z3, E = AA - BB
+    // every step does the same work and the swaps only move values, whatever the bits
+    // of k are. The identity comes out as 0
+    pub fn ladder(&self, k: &BigUint, u: &BigUint) -> BigUint {
+        let p = &self.p;
+        let u = u % p;
+        let a24 = FiniteField::divide(
+            &FiniteField::subtract(&self.a, &BigUint::from(2u32), p),
+            &BigUint::from(4u32),
+            p,
+        )
+        .expect("p is an odd prime");
+
+        let mut x2 = BigUint::from(1u32);
+        let mut z2 = BigUint::from(0u32);
+        let mut x3 = u.clone();
+        let mut z3 = BigUint::from(1u32);
+        let mut swap = BigUint::from(0u32);
+        for i in (0..p.bits()).rev() {
+            let bit = BigUint::from(k.bit(i) as u32);
+            swap ^= &bit;
+            FiniteField::cswap(&swap, &mut x2, &mut x3);
+            FiniteField::cswap(&swap, &mut z2, &mut z3);
+            swap = bit;
+
+            let aa = FiniteField::add(&x2, &z2, p);
+            let aa2 = FiniteField::mult(&aa, &aa, p);
+            let bb = FiniteField::subtract(&x2, &z2, p);
+            let bb2 = FiniteField::mult(&bb, &bb, p);
+            let e = FiniteField::subtract(&aa2, &bb2, p);
+            let c = FiniteField::add(&x3, &z3, p);
+            let d = FiniteField::subtract(&x3, &z3, p);
+            let da = FiniteField::mult(&d, &aa, p);
+            let cb = FiniteField::mult(&c, &bb, p);
+
+            let sum = FiniteField::add(&da, &cb, p);
+            let diff = FiniteField::subtract(&da, &cb, p);
+            x3 = FiniteField::mult(&sum, &sum, p);
+            z3 = FiniteField::mult(&u, &FiniteField::mult(&diff, &diff, p), p);
+            x2 = FiniteField::mult(&aa2, &bb2, p);
+            let a24e = FiniteField::mult(&a24, &e, p);
+            z2 = FiniteField::mult(&e, &FiniteField::add(&aa2, &a24e, p), p);
+        }
+        FiniteField::cswap(&swap, &mut x2, &mut x3);
+        FiniteField::cswap(&swap, &mut z2, &mut z3);
+
+        match FiniteField::inv_multiplication_ct(&z2, p) {
+            Ok(z2_inv) => FiniteField::mult(&x2, &z2_inv, p),
+            Err(_) => BigUint::from(0u32),
+        }
+    }
+    pub fn curve25519() -> Self {
+        /*
+                Curve25519, RFC 7748 section 4.1
+                Name	Value
+                p	0x7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed (2^255 - 19)
+                A	486662
+                B	1
+                u	9
+        */
+        let p = BigUint::parse_bytes(
+            b"7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffed",
+            16,
+        )
+        .expect("Cannot parse p");
+        MontgomeryCurve::new(BigUint::from(486662u32), BigUint::from(1u32), p)
+            .expect("Curve25519 is non-singular")
+    }
+    pub fn curve25519_base_u() -> BigUint {
+        BigUint::from(9u32)
+    }
+}
+
+// X25519(k, u), RFC 7748 section 5: both inputs and the output are 32 bytes little-endian.
+// k is clamped (the low 3 bits and bit 255 cleared, bit 254 set) and bit 255 of u is
+// ignored. The result is all zeros when u is of small order
+pub fn x25519(scalar: &[u8; 32], u: &[u8; 32]) -> [u8; 32] {
+    let mut k = *scalar;
+    k[0] &= 248;
+    k[31] &= 127;
+    k[31] |= 64;
+    let mut u = *u;
+    u[31] &= 127;
+
+    let curve = MontgomeryCurve::curve25519();
+    let out = curve.ladder(&BigUint::from_bytes_le(&k), &BigUint::from_bytes_le(&u));
+
+    let mut bytes = [0u8; 32];
+    let out = out.to_bytes_le();
+    bytes[..out.len()].copy_from_slice(&out);
+    bytes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bytes32(s: &str) -> [u8; 32] {
+        hex::decode(s)
+            .expect("Cannot decode hex")
+            .try_into()
+            .expect("Not 32 bytes")
+    }
+
+    #[test]
+    fn test_x25519_rfc7748() {
+        // section 5.2
+        let k = bytes32("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4");
+        let u = bytes32("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c");
+        let out = bytes32("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552");
+        assert_eq!(x25519(&k, &u), out);
+
+        // section 6.1
+        let base = bytes32("0900000000000000000000000000000000000000000000000000000000000000");
+        let alice = bytes32("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let alice_pub = bytes32("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a");
+        let bob = bytes32("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+        let bob_pub = bytes32("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f");
+        let shared = bytes32("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742");
+        assert_eq!(x25519(&alice, &base), alice_pub);
+        assert_eq!(x25519(&bob, &base), bob_pub);
+        assert_eq!(x25519(&alice, &bob_pub), shared);
+        assert_eq!(x25519(&bob, &alice_pub), shared);
+    }
+
+    #[test]
+    fn test_ladder_small_order() {
+        let curve = MontgomeryCurve::curve25519();
+        // u = 0 is the point of order 2, k (0, 0) is either it or the identity
+        let zero = BigUint::from(0u32);
+        assert_eq!(curve.ladder(&BigUint::from(5u32), &zero), zero);
+        assert_eq!(x25519(&[0x42; 32], &[0; 32]), [0; 32]);
+        // 0 P is the identity
+        assert_eq!(
+            curve.ladder(&zero, &MontgomeryCurve::curve25519_base_u()),
+            zero
+        );
+    }
+
+    #[test]
+    fn test_new_singular() {
+        // A = 2 makes A^2 - 4 vanish
+        assert_eq!(
+            MontgomeryCurve::new(
+                BigUint::from(2u32),
+                BigUint::from(1u32),
+                BigUint::from(13u32)
+            ),
+            Err(EccError::SingularCurve)
+        );
+        let curve = MontgomeryCurve::new(
+            BigUint::from(3u32),
+            BigUint::from(1u32),
+            BigUint::from(13u32),
+        )
+        .expect("Cannot build curve");
+        // 3^2 = 2^3 + 3 2^2 + 2 mod 13
+        assert!(curve.is_on_curve(&Point::Coordinate(BigUint::from(2u32), BigUint::from(3u32))));
+        assert!(!curve.is_on_curve(&Point::Coordinate(BigUint::from(2u32), BigUint::from(4u32))));
+    }
+}
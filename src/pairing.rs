@@ -0,0 +1,203 @@
+use crate::{EccError, EllipticCurve, FiniteField, Point};
+use num_bigint::BigUint;
+
+// Weil pairing e_r: E[r] x E[r] -> mu_r, for toy curves only
+// points are pairs of elements of F_p, so both P and Q must be defined over F_p: the
+// whole r-torsion lies in E(F_p) and r divides p - 1 (embedding degree 1), as on
+// y^2 = x^3 + 11 mod 31 with E(F_31) = Z/5 x Z/5. Curves with a larger embedding
+// degree would need points over F_p^k, which Point cannot hold
+
+// Miller's algorithm: f(Q) for the f of divisor r(P) - r(O), normalized at O
+// f = 1, T = P
+// for i in range(bits(r)-2 to 0)
+//     f = f^2 l_T,T(Q) / v_2T(Q), T = 2T
+//     if bit(i)
+//         f = f l_T,P(Q) / v_T+P(Q), T = T + P
+// l_T,U is the line through T and U (the tangent when T = U) and v_V the vertical
+// line through V, v_O = 1
+// fails with InvalidOrder unless r >= 2 and r P = I, with PointNotOnCurve unless P and
+// Q are points of ec, with InvalidPoint when either is the identity, and with
+// DivisionByZero when Q is a zero or a pole of one of the lines, i.e. Q = mP for some m
+pub fn miller_loop(
+    ec: &EllipticCurve,
+    p: &Point,
+    q: &Point,
+    r: &BigUint,
+) -> Result<BigUint, EccError> {
+    let p = &ec.reduce_point(p)?;
+    let q = &ec.reduce_point(q)?;
+    if r < &BigUint::from(2u32) || ec.scalar_mul(p, r)? != Point::Identity {
+        return Err(EccError::InvalidOrder);
+    }
+    if p == &Point::Identity || q == &Point::Identity {
+        return Err(EccError::InvalidPoint);
+    }
+    let mut f = BigUint::from(1u32);
+    let mut t = p.clone();
+    for i in (0..r.bits() - 1).rev() {
+        let l = line(ec, &t, &t, q)?;
+        f = FiniteField::mult(&FiniteField::mult(&f, &f, &ec.p), &l, &ec.p);
+        t = ec.add_points(&t, &t)?;
+        if r.bit(i) {
+            let l = line(ec, &t, p, q)?;
+            f = FiniteField::mult(&f, &l, &ec.p);
+            t = ec.add_points(&t, p)?;
+        }
+    }
+    Ok(f)
+}
+
+// e_r(P, Q) = (-1)^r f_P(Q) / f_Q(P), an r-th root of unity in F_p
+// bilinear, e(aP, bQ) = e(P, Q)^ab, alternating, e(P, P) = 1, and not 1 when P and Q
+// generate E[r]
+// e(P, Q) = 1 when either point is the identity or Q lies in <P>, the cases where
+// the Miller functions hit a zero or a pole at the other point
+// fails with InvalidOrder unless r >= 2, r P = I and r Q = I, and with PointNotOnCurve
+// unless P and Q are points of ec
+pub fn weil_pairing(
+    ec: &EllipticCurve,
+    p: &Point,
+    q: &Point,
+    r: &BigUint,
+) -> Result<BigUint, EccError> {
+    let one = BigUint::from(1u32);
+    if r < &BigUint::from(2u32) {
+        return Err(EccError::InvalidOrder);
+    }
+    for c in [p, q] {
+        if ec.scalar_mul(c, r)? != Point::Identity {
+            return Err(EccError::InvalidOrder);
+        }
+    }
+    if p == &Point::Identity || q == &Point::Identity {
+        return Ok(one);
+    }
+    let (f_p, f_q) = match (miller_loop(ec, p, q, r), miller_loop(ec, q, p, r)) {
+        (Ok(f_p), Ok(f_q)) => (f_p, f_q),
+        (Err(EccError::DivisionByZero), _) | (_, Err(EccError::DivisionByZero)) => return Ok(one),
+        (Err(e), _) | (_, Err(e)) => return Err(e),
+    };
+    let Ok(e) = FiniteField::divide(&f_p, &f_q, &ec.p) else {
+        return Ok(one);
+    };
+    if r.bit(0) {
+        return Ok(FiniteField::neg(&e, &ec.p));
+    }
+    Ok(e)
+}
+
+// l_T,U(Q) / v_T+U(Q)
+// with s the slope of l, l(x, y) = y - y_T - s (x - x_T) and v(x, y) = x - x_T+U, or
+// when U = -T, l(x, y) = x - x_T and v = 1
+// fails with DivisionByZero when l or v vanishes at Q
+fn line(ec: &EllipticCurve, t: &Point, u: &Point, q: &Point) -> Result<BigUint, EccError> {
+    let (Point::Coordinate(x1, y1), Point::Coordinate(x2, y2), Point::Coordinate(xq, yq)) =
+        (t, u, q)
+    else {
+        return Err(EccError::InvalidPoint);
+    };
+    let zero = BigUint::from(0u32);
+    let value = if x1 == x2 && FiniteField::add(y1, y2, &ec.p) == zero {
+        FiniteField::subtract(xq, x1, &ec.p)
+    } else {
+        let s = if t == u {
+            // 3 x^2 + a / 2y
+            let x2 = FiniteField::mult(x1, x1, &ec.p);
+            let num = FiniteField::mult(&BigUint::from(3u32), &x2, &ec.p);
+            let num = FiniteField::add(&num, &ec.a, &ec.p);
+            FiniteField::divide(&num, &FiniteField::add(y1, y1, &ec.p), &ec.p)?
+        } else {
+            let num = FiniteField::subtract(y2, y1, &ec.p);
+            FiniteField::divide(&num, &FiniteField::subtract(x2, x1, &ec.p), &ec.p)?
+        };
+        let Point::Coordinate(x3, _) = ec.add_points(t, u)? else {
+            unreachable!("t + u is only I when u = -t")
+        };
+        let dx = FiniteField::subtract(xq, x1, &ec.p);
+        let l = FiniteField::subtract(
+            &FiniteField::subtract(yq, y1, &ec.p),
+            &FiniteField::mult(&s, &dx, &ec.p),
+            &ec.p,
+        );
+        FiniteField::divide(&l, &FiniteField::subtract(xq, &x3, &ec.p), &ec.p)?
+    };
+    if value == zero {
+        return Err(EccError::DivisionByZero);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // y^2 = x^3 + 11 mod 31, E(F_31) = Z/5 x Z/5 and 5 divides 30
+    fn setup() -> (EllipticCurve, Point, Point, BigUint) {
+        let ec = EllipticCurve::new(
+            BigUint::from(0u32),
+            BigUint::from(11u32),
+            BigUint::from(31u32),
+        )
+        .expect("Cannot build curve");
+        let p = Point::Coordinate(BigUint::from(2u32), BigUint::from(9u32));
+        // (3, 10) is not a multiple of (2, 9)
+        let q = Point::Coordinate(BigUint::from(3u32), BigUint::from(10u32));
+        (ec, p, q, BigUint::from(5u32))
+    }
+
+    #[test]
+    fn test_bilinear() {
+        let (ec, p, q, r) = setup();
+        let e = weil_pairing(&ec, &p, &q, &r).expect("Could not pair");
+        let one = BigUint::from(1u32);
+        assert_ne!(e, one);
+        assert_eq!(e.modpow(&r, &ec.p), one);
+        for a in 1u32..5 {
+            for b in 1u32..5 {
+                let ap = ec
+                    .scalar_mul(&p, &BigUint::from(a))
+                    .expect("P is on the curve");
+                let bq = ec
+                    .scalar_mul(&q, &BigUint::from(b))
+                    .expect("Q is on the curve");
+                assert_eq!(
+                    weil_pairing(&ec, &ap, &bq, &r),
+                    Ok(e.modpow(&BigUint::from(a * b), &ec.p))
+                );
+            }
+        }
+        // e(P + Q, Q) = e(P, Q) e(Q, Q)
+        let sum = ec.add(&p, &q).expect("P and Q are on the curve");
+        assert_eq!(weil_pairing(&ec, &sum, &q, &r), Ok(e.clone()));
+        // e(Q, P) = e(P, Q)^-1
+        let e_inv = FiniteField::inv(&e, &ec.p).expect("e is not 0");
+        assert_eq!(weil_pairing(&ec, &q, &p, &r), Ok(e_inv));
+    }
+
+    #[test]
+    fn test_degenerate() {
+        let (ec, p, q, r) = setup();
+        let one = BigUint::from(1u32);
+        assert_eq!(weil_pairing(&ec, &p, &p, &r), Ok(one.clone()));
+        let p3 = ec
+            .scalar_mul(&p, &BigUint::from(3u32))
+            .expect("P is on the curve");
+        assert_eq!(weil_pairing(&ec, &p, &p3, &r), Ok(one.clone()));
+        assert_eq!(weil_pairing(&ec, &Point::Identity, &q, &r), Ok(one));
+        assert_eq!(miller_loop(&ec, &p, &p3, &r), Err(EccError::DivisionByZero));
+
+        assert_eq!(
+            weil_pairing(&ec, &p, &q, &BigUint::from(7u32)),
+            Err(EccError::InvalidOrder)
+        );
+        assert_eq!(
+            miller_loop(&ec, &p, &q, &BigUint::from(0u32)),
+            Err(EccError::InvalidOrder)
+        );
+        let off_curve = Point::Coordinate(BigUint::from(3u32), BigUint::from(11u32));
+        assert_eq!(
+            weil_pairing(&ec, &p, &off_curve, &r),
+            Err(EccError::PointNotOnCurve)
+        );
+    }
+}
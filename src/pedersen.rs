@@ -0,0 +1,150 @@
+use crate::ecdsa::int2octets;
+use crate::{EccError, EllipticCurve, Point};
+use num_bigint::BigUint;
+
+// C = rH + vG commits to v: C says nothing about v when r is uniform below the order
+// of G, and opening C to another v' would give log_G H, which nobody may know
+pub struct PedersenCommitment {
+    pub ec: EllipticCurve,
+    pub g: Point,
+    pub h: Point,
+}
+
+// domain separation tag used by with_hashed_h
+const H_DST: &[u8] = b"ECC_RUST-PEDERSEN-H";
+
+impl PedersenCommitment {
+    // fails with InvalidPoint when g or h is the identity or not on ec, or when g = h
+    pub fn new(ec: EllipticCurve, g: Point, h: Point) -> Result<Self, EccError> {
+        for c in [&g, &h] {
+            if c == &Point::Identity || !ec.is_on_curve(c) {
+                return Err(EccError::InvalidPoint);
+            }
+        }
+        if g == h {
+            return Err(EccError::InvalidPoint);
+        }
+        Ok(PedersenCommitment { ec, g, h })
+    }
+    // h = hash_to_curve(x_G || y_G), so that log_G H is unknown to everyone, the
+    // caller included
+    // fails with InvalidPoint as new does
+    pub fn with_hashed_h(ec: EllipticCurve, g: Point) -> Result<Self, EccError> {
+        let msg = match &g {
+            Point::Coordinate(x, y) => {
+                let width = (ec.p.bits() as usize).div_ceil(8);
+                let mut msg = int2octets(x, width);
+                msg.extend_from_slice(&int2octets(y, width));
+                msg
+            }
+            Point::Identity => return Err(EccError::InvalidPoint),
+        };
+        let h = ec.hash_to_curve(&msg, H_DST)?;
+        Self::new(ec, g, h)
+    }
+    pub fn secp256k1() -> Self {
+        Self::with_hashed_h(
+            EllipticCurve::secp256k1(),
+            EllipticCurve::secp256k1_generator(),
+        )
+        .expect("G is a point of secp256k1")
+    }
+    // rH + vG, both products with scalar_mul_ct as v and r are secret until opened
+    pub fn commit(&self, value: &BigUint, randomness: &BigUint) -> Result<Point, EccError> {
+        let rh = self.ec.scalar_mul_ct(&self.h, randomness)?;
+        let vg = self.ec.scalar_mul_ct(&self.g, value)?;
+        self.ec.add(&rh, &vg)
+    }
+    // commitment opens to value with randomness
+    pub fn verify_commitment(
+        &self,
+        commitment: &Point,
+        value: &BigUint,
+        randomness: &BigUint,
+    ) -> bool {
+        self.commit(value, randomness).as_ref() == Ok(commitment)
+    }
+    // (r1 H + v1 G) + (r2 H + v2 G) = (r1 + r2) H + (v1 + v2) G, a commitment to
+    // v1 + v2 that opens with r1 + r2
+    // fails with PointNotOnCurve unless both commitments are points of ec
+    pub fn add_commitments(&self, c1: &Point, c2: &Point) -> Result<Point, EccError> {
+        self.ec.add(c1, c2)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use num_bigint::RandBigInt;
+
+    #[test]
+    fn test_homomorphic_secp256k1() {
+        let pedersen = PedersenCommitment::secp256k1();
+        let n = EllipticCurve::secp256k1_order();
+        assert!(pedersen.ec.is_on_curve(&pedersen.h));
+        assert_ne!(pedersen.h, pedersen.g);
+
+        let mut rng = rand::thread_rng();
+        let v1 = rng.gen_biguint_below(&n);
+        let r1 = rng.gen_biguint_below(&n);
+        let v2 = rng.gen_biguint_below(&n);
+        let r2 = rng.gen_biguint_below(&n);
+        let c1 = pedersen.commit(&v1, &r1).expect("Could not commit");
+        let c2 = pedersen.commit(&v2, &r2).expect("Could not commit");
+        let sum = pedersen
+            .add_commitments(&c1, &c2)
+            .expect("Could not add commitments");
+        assert_eq!(pedersen.commit(&(&v1 + &v2), &(&r1 + &r2)), Ok(sum.clone()));
+        assert!(pedersen.verify_commitment(&sum, &(&v1 + &v2), &(&r1 + &r2)));
+
+        assert!(pedersen.verify_commitment(&c1, &v1, &r1));
+        assert!(!pedersen.verify_commitment(&c1, &v2, &r1));
+        assert!(!pedersen.verify_commitment(&c1, &v1, &r2));
+    }
+
+    #[test]
+    fn test_homomorphic_wraps_around_order() {
+        // y^2 = x^3 + 2x + 2 mod 17, of order 19
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let g = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+        let pedersen = PedersenCommitment::with_hashed_h(ec, g).expect("Could not derive h");
+
+        // 12 + 15 = 8 mod 19
+        let c1 = pedersen
+            .commit(&BigUint::from(12u32), &BigUint::from(3u32))
+            .expect("Could not commit");
+        let c2 = pedersen
+            .commit(&BigUint::from(15u32), &BigUint::from(18u32))
+            .expect("Could not commit");
+        let sum = pedersen
+            .add_commitments(&c1, &c2)
+            .expect("Could not add commitments");
+        assert!(pedersen.verify_commitment(&sum, &BigUint::from(27u32), &BigUint::from(21u32)));
+        assert!(pedersen.verify_commitment(&sum, &BigUint::from(8u32), &BigUint::from(2u32)));
+    }
+
+    #[test]
+    fn test_new_rejects_bad_generators() {
+        let ec = EllipticCurve::secp256k1();
+        let g = EllipticCurve::secp256k1_generator();
+        assert!(PedersenCommitment::new(ec.clone(), g.clone(), g.clone()).is_err());
+        assert_eq!(
+            PedersenCommitment::new(ec.clone(), g.clone(), Point::Identity).err(),
+            Some(EccError::InvalidPoint)
+        );
+        let off_curve = Point::Coordinate(BigUint::from(1u32), BigUint::from(1u32));
+        assert_eq!(
+            PedersenCommitment::new(ec.clone(), off_curve, g).err(),
+            Some(EccError::InvalidPoint)
+        );
+        assert_eq!(
+            PedersenCommitment::with_hashed_h(ec, Point::Identity).err(),
+            Some(EccError::InvalidPoint)
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use num_bigint::{BigUint, RandBigInt};
+use rand::RngCore;
+
+// uniform in [low, high)
+#[cfg(all(feature = "std", not(feature = "getrandom")))]
+pub(crate) fn gen_biguint_range(low: &BigUint, high: &BigUint) -> BigUint {
+    gen_biguint_range_with(&mut rand::thread_rng(), low, high)
+}
+
+// uniform in [low, high), rejection sampling over getrandom bytes so that it only
+// needs core and alloc
+#[cfg(feature = "getrandom")]
+pub(crate) fn gen_biguint_range(low: &BigUint, high: &BigUint) -> BigUint {
+    assert!(low < high, "empty range");
+    let span = high - low;
+    let bits = span.bits();
+    let mut bytes = alloc::vec![0u8; bits.div_ceil(8) as usize];
+    loop {
+        getrandom::getrandom(&mut bytes).expect("getrandom failed");
+        // clear the bits above the width of span so at least half the draws land
+        if bits % 8 != 0 {
+            bytes[0] &= (1u8 << (bits % 8)) - 1;
+        }
+        let candidate = BigUint::from_bytes_be(&bytes);
+        if candidate < span {
+            return low + candidate;
+        }
+    }
+}
+
+// uniform in [low, high) from a caller supplied rng
+pub(crate) fn gen_biguint_range_with<R: RngCore>(
+    rng: &mut R,
+    low: &BigUint,
+    high: &BigUint,
+) -> BigUint {
+    rng.gen_biguint_range(low, high)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gen_biguint_range() {
+        let low = BigUint::from(1u32);
+        let high = BigUint::from(19u32);
+        let mut seen = [false; 19];
+        for _ in 0..1000 {
+            let d = gen_biguint_range(&low, &high);
+            assert!(d >= low && d < high);
+            seen[d.to_u32_digits().first().copied().unwrap_or(0) as usize] = true;
+        }
+        assert!(seen[1..].iter().all(|s| *s));
+
+        let q = crate::EllipticCurve::secp256k1_order();
+        for _ in 0..100 {
+            let d = gen_biguint_range(&low, &q);
+            assert!(d >= low && d < q);
+        }
+    }
+}
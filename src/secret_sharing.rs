@@ -0,0 +1,218 @@
+use crate::rng;
+use crate::{is_probable_prime, EccError, FiniteField};
+use alloc::vec::Vec;
+use num_bigint::BigUint;
+use rand::RngCore;
+
+// Shamir's (t, n) secret sharing over F_p
+// f(x) = s + a1 x + ... + a(t-1) x^(t-1) with random a1..a(t-1), share i is (i, f(i))
+// for i in [1, n]. Any t shares give f back by Lagrange interpolation and s = f(0),
+// t - 1 shares are consistent with every s in F_p and say nothing about it
+pub struct ShamirSecretSharing {}
+
+impl ShamirSecretSharing {
+    // fails with InvalidModulus when p is not prime, InvalidScalar when secret is not
+    // below p and InvalidThreshold unless 1 <= threshold <= shares < p
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    pub fn split(
+        secret: &BigUint,
+        threshold: usize,
+        shares: usize,
+        p: &BigUint,
+    ) -> Result<Vec<(BigUint, BigUint)>, EccError> {
+        Self::check(secret, threshold, shares, p)?;
+        let coefficients = (1..threshold)
+            .map(|_| rng::gen_biguint_range(&BigUint::from(0u32), p))
+            .collect();
+        Ok(Self::evaluate(secret, coefficients, shares, p))
+    }
+    // same as split, but the coefficients are drawn from rng
+    pub fn split_with<R: RngCore>(
+        secret: &BigUint,
+        threshold: usize,
+        shares: usize,
+        p: &BigUint,
+        rng: &mut R,
+    ) -> Result<Vec<(BigUint, BigUint)>, EccError> {
+        Self::check(secret, threshold, shares, p)?;
+        let coefficients = (1..threshold)
+            .map(|_| rng::gen_biguint_range_with(rng, &BigUint::from(0u32), p))
+            .collect();
+        Ok(Self::evaluate(secret, coefficients, shares, p))
+    }
+    // f(0) = sum(y_i * prod(x_j / (x_j - x_i) for j != i))
+    // with fewer than threshold shares this is some other value, not the secret
+    // fails with DivisionByZero when two shares have the same x
+    pub fn reconstruct(shares: &[(BigUint, BigUint)], p: &BigUint) -> Result<BigUint, EccError> {
+        let mut secret = BigUint::from(0u32);
+        for (i, (x_i, y_i)) in shares.iter().enumerate() {
+            let mut num = BigUint::from(1u32);
+            let mut den = BigUint::from(1u32);
+            for (j, (x_j, _)) in shares.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                num = FiniteField::mult(&num, x_j, p);
+                den = FiniteField::mult(&den, &FiniteField::subtract(x_j, x_i, p), p);
+            }
+            let basis = FiniteField::divide(&num, &den, p)?;
+            secret = FiniteField::add(&secret, &FiniteField::mult(y_i, &basis, p), p);
+        }
+        Ok(secret)
+    }
+    fn check(
+        secret: &BigUint,
+        threshold: usize,
+        shares: usize,
+        p: &BigUint,
+    ) -> Result<(), EccError> {
+        if !is_probable_prime(p) {
+            return Err(EccError::InvalidModulus);
+        }
+        if secret >= p {
+            return Err(EccError::InvalidScalar);
+        }
+        if threshold == 0 || threshold > shares || &BigUint::from(shares) >= p {
+            return Err(EccError::InvalidThreshold);
+        }
+        Ok(())
+    }
+    // (i, f(i)) for i in [1, shares], by Horner's rule
+    fn evaluate(
+        secret: &BigUint,
+        coefficients: Vec<BigUint>,
+        shares: usize,
+        p: &BigUint,
+    ) -> Vec<(BigUint, BigUint)> {
+        (1..=shares)
+            .map(|i| {
+                let x = BigUint::from(i);
+                let y = coefficients
+                    .iter()
+                    .rev()
+                    .fold(BigUint::from(0u32), |acc, a| {
+                        FiniteField::mult(&FiniteField::add(&acc, a, p), &x, p)
+                    });
+                (x, FiniteField::add(&y, secret, p))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EllipticCurve;
+    use num_bigint::RandBigInt;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_split_reconstruct() {
+        let p = EllipticCurve::secp256k1_order();
+        let mut rng = StdRng::seed_from_u64(3);
+        let secret = rng.gen_biguint_below(&p);
+        let shares =
+            ShamirSecretSharing::split_with(&secret, 3, 5, &p, &mut rng).expect("Could not split");
+        assert_eq!(shares.len(), 5);
+
+        // every choice of 3 shares out of 5
+        for i in 0..5 {
+            for j in i + 1..5 {
+                for k in j + 1..5 {
+                    let subset = [shares[i].clone(), shares[j].clone(), shares[k].clone()];
+                    assert_eq!(
+                        ShamirSecretSharing::reconstruct(&subset, &p),
+                        Ok(secret.clone())
+                    );
+                }
+            }
+        }
+        assert_eq!(
+            ShamirSecretSharing::reconstruct(&shares, &p),
+            Ok(secret.clone())
+        );
+        // 2 shares fit a line, not the parabola through the secret
+        for pair in shares.windows(2) {
+            assert_ne!(
+                ShamirSecretSharing::reconstruct(pair, &p),
+                Ok(secret.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_fewer_shares_say_nothing() {
+        // over F_17 with t = 3, two shares plus (0, s) fit a polynomial of degree 2
+        // for every s, so each secret is equally possible
+        let p = BigUint::from(17u32);
+        let mut rng = StdRng::seed_from_u64(9);
+        let shares = ShamirSecretSharing::split_with(&BigUint::from(11u32), 3, 4, &p, &mut rng)
+            .expect("Could not split");
+        let mut seen = [false; 17];
+        for s in 0u32..17 {
+            let candidate = [
+                (BigUint::from(0u32), BigUint::from(s)),
+                shares[0].clone(),
+                shares[1].clone(),
+            ];
+            // interpolate through the candidate and read f(3) off it
+            let at_three: Vec<(BigUint, BigUint)> = candidate
+                .iter()
+                .map(|(x, y)| {
+                    (
+                        FiniteField::subtract(x, &BigUint::from(3u32), &p),
+                        y.clone(),
+                    )
+                })
+                .collect();
+            let f3 = ShamirSecretSharing::reconstruct(&at_three, &p).expect("x are distinct");
+            seen[usize::try_from(&f3).expect("f(3) is below 17")] = true;
+        }
+        // 17 different polynomials, so 17 different third shares
+        assert!(seen.iter().all(|s| *s));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_split_threshold_one() {
+        let p = BigUint::from(17u32);
+        let secret = BigUint::from(9u32);
+        let shares = ShamirSecretSharing::split(&secret, 1, 3, &p).expect("Could not split");
+        assert!(shares.iter().all(|(_, y)| y == &secret));
+        assert_eq!(
+            ShamirSecretSharing::reconstruct(&shares[1..2], &p),
+            Ok(secret)
+        );
+    }
+
+    #[test]
+    fn test_split_errors() {
+        let p = BigUint::from(17u32);
+        let secret = BigUint::from(9u32);
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut split = |secret: &BigUint, t: usize, n: usize, p: &BigUint| {
+            ShamirSecretSharing::split_with(secret, t, n, p, &mut rng).err()
+        };
+        assert_eq!(split(&secret, 0, 3, &p), Some(EccError::InvalidThreshold));
+        assert_eq!(split(&secret, 4, 3, &p), Some(EccError::InvalidThreshold));
+        assert_eq!(split(&secret, 3, 17, &p), Some(EccError::InvalidThreshold));
+        assert_eq!(
+            split(&BigUint::from(17u32), 2, 3, &p),
+            Some(EccError::InvalidScalar)
+        );
+        assert_eq!(
+            split(&secret, 2, 3, &BigUint::from(15u32)),
+            Some(EccError::InvalidModulus)
+        );
+
+        let twice = [
+            (BigUint::from(1u32), BigUint::from(2u32)),
+            (BigUint::from(1u32), BigUint::from(5u32)),
+        ];
+        assert_eq!(
+            ShamirSecretSharing::reconstruct(&twice, &p),
+            Err(EccError::DivisionByZero)
+        );
+    }
+}
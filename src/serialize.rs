@@ -0,0 +1,198 @@
+use crate::ecdsa::Signature;
+use crate::{parse_hex, EllipticCurve, Point};
+use alloc::format;
+use alloc::string::String;
+use num_bigint::BigUint;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+// All the big numbers are written as lowercase hex strings without prefix
+
+fn to_hex(n: &BigUint) -> String {
+    n.to_str_radix(16)
+}
+
+// same rules as parse_hex, a 0x prefix is accepted but underscores and signs are not
+fn from_hex<E: de::Error>(s: &str) -> Result<BigUint, E> {
+    parse_hex(s).map_err(|_| E::custom(format!("invalid hex number: {}", s)))
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum PointRepr {
+    Identity,
+    Coordinate { x: String, y: String },
+}
+
+#[derive(Serialize, Deserialize)]
+struct EllipticCurveRepr {
+    a: String,
+    b: String,
+    p: String,
+    // left out when it is 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cofactor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SignatureRepr {
+    r: String,
+    s: String,
+}
+
+impl Serialize for Point {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self {
+            Point::Identity => PointRepr::Identity,
+            Point::Coordinate(x, y) => PointRepr::Coordinate {
+                x: to_hex(x),
+                y: to_hex(y),
+            },
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Point {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match PointRepr::deserialize(deserializer)? {
+            PointRepr::Identity => Ok(Point::Identity),
+            PointRepr::Coordinate { x, y } => Ok(Point::Coordinate(from_hex(&x)?, from_hex(&y)?)),
+        }
+    }
+}
+
+impl Serialize for EllipticCurve {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        EllipticCurveRepr {
+            a: to_hex(&self.a),
+            b: to_hex(&self.b),
+            p: to_hex(&self.p),
+            cofactor: (self.cofactor != BigUint::from(1u32)).then(|| to_hex(&self.cofactor)),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for EllipticCurve {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = EllipticCurveRepr::deserialize(deserializer)?;
+        let cofactor = match repr.cofactor {
+            Some(cofactor) => from_hex(&cofactor)?,
+            None => BigUint::from(1u32),
+        };
+        EllipticCurve::with_cofactor(
+            from_hex(&repr.a)?,
+            from_hex(&repr.b)?,
+            from_hex(&repr.p)?,
+            cofactor,
+        )
+        .map_err(|_| de::Error::custom("singular curve or zero cofactor"))
+    }
+}
+
+impl Serialize for Signature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (r, s) = &self.0;
+        SignatureRepr {
+            r: to_hex(r),
+            s: to_hex(s),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Signature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = SignatureRepr::deserialize(deserializer)?;
+        Ok(Signature((from_hex(&repr.r)?, from_hex(&repr.s)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_round_trip() {
+        let g = EllipticCurve::secp256k1_generator();
+        let json = serde_json::to_string(&g).expect("Could not serialize point");
+        assert_eq!(
+            json,
+            r#"{"type":"coordinate","x":"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798","y":"483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8"}"#
+        );
+        let back: Point = serde_json::from_str(&json).expect("Could not deserialize point");
+        assert_eq!(back, g);
+
+        let json = serde_json::to_string(&Point::Identity).expect("Could not serialize point");
+        assert_eq!(json, r#"{"type":"identity"}"#);
+        let back: Point = serde_json::from_str(&json).expect("Could not deserialize point");
+        assert_eq!(back, Point::Identity);
+    }
+    #[test]
+    fn test_curve_round_trip() {
+        let ec = EllipticCurve::p256();
+        let json = serde_json::to_string(&ec).expect("Could not serialize curve");
+        let back: EllipticCurve = serde_json::from_str(&json).expect("Could not deserialize curve");
+        assert_eq!(back.a, ec.a);
+        assert_eq!(back.b, ec.b);
+        assert_eq!(back.p, ec.p);
+
+        let ec = EllipticCurve::new(
+            BigUint::from(2u32),
+            BigUint::from(2u32),
+            BigUint::from(17u32),
+        )
+        .expect("Cannot build curve");
+        let json = serde_json::to_string(&ec).expect("Could not serialize curve");
+        assert_eq!(json, r#"{"a":"2","b":"2","p":"11"}"#);
+
+        let ec = EllipticCurve::with_cofactor(
+            BigUint::from(0u32),
+            BigUint::from(1u32),
+            BigUint::from(5u32),
+            BigUint::from(2u32),
+        )
+        .expect("Cannot build curve");
+        let json = serde_json::to_string(&ec).expect("Could not serialize curve");
+        assert_eq!(json, r#"{"a":"0","b":"1","p":"5","cofactor":"2"}"#);
+        let back: EllipticCurve = serde_json::from_str(&json).expect("Could not deserialize curve");
+        assert_eq!(back, ec);
+    }
+    #[test]
+    fn test_signature_round_trip() {
+        let signature = Signature((BigUint::from(7u32), BigUint::from(300u32)));
+        let json = serde_json::to_string(&signature).expect("Could not serialize signature");
+        assert_eq!(json, r#"{"r":"7","s":"12c"}"#);
+        let back: Signature = serde_json::from_str(&json).expect("Could not deserialize signature");
+        assert_eq!(back, signature);
+    }
+    #[test]
+    fn test_invalid_hex() {
+        for x in ["xyz", "", "1_0", "-1"] {
+            let json = format!(r#"{{"type":"coordinate","x":"{}","y":"1"}}"#, x);
+            assert!(serde_json::from_str::<Point>(&json).is_err(), "{}", x);
+        }
+        let json = r#"{"type":"coordinate","x":"0x5","y":"1"}"#;
+        let back: Point = serde_json::from_str(json).expect("Could not deserialize point");
+        assert_eq!(
+            back,
+            Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32))
+        );
+    }
+    #[test]
+    fn test_off_curve_point() {
+        // membership is left to an explicit is_on_curve call
+        let ec = EllipticCurve::secp256k1();
+        let json = r#"{"type":"coordinate","x":"1","y":"1"}"#;
+        let point: Point = serde_json::from_str(json).expect("Could not deserialize point");
+        assert_eq!(
+            point,
+            Point::Coordinate(BigUint::from(1u32), BigUint::from(1u32))
+        );
+        assert!(!ec.is_on_curve(&point));
+        assert_eq!(
+            serde_json::to_string(&point).expect("Could not serialize point"),
+            json
+        );
+    }
+}
@@ -0,0 +1,88 @@
+// the core+alloc part of the API, built with the crate's default features off:
+//
+// cargo test --no-default-features --test no_std
+//
+// nothing below comes from the std prelude, the harness itself still runs on std
+#![no_std]
+extern crate alloc;
+extern crate std;
+
+use alloc::vec::Vec;
+use ecc_rust::ecdsa::ECDSA;
+use ecc_rust::keys::PrivateKey;
+use ecc_rust::{EllipticCurve, FiniteField, Point};
+use num_bigint::BigUint;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[test]
+fn field_ops() {
+    let p = BigUint::from(17u32);
+    let c = BigUint::from(5u32);
+    let d = BigUint::from(13u32);
+    assert_eq!(FiniteField::add(&c, &d, &p), BigUint::from(1u32));
+    assert_eq!(FiniteField::subtract(&c, &d, &p), BigUint::from(9u32));
+    assert_eq!(FiniteField::mult(&c, &d, &p), BigUint::from(14u32));
+    let c_inv = FiniteField::inv(&c, &p).expect("5 is invertible mod 17");
+    assert_eq!(FiniteField::mult(&c, &c_inv, &p), BigUint::from(1u32));
+    assert_eq!(
+        FiniteField::inv_multiplication_ct(&c, &p),
+        Ok(c_inv.clone())
+    );
+    assert_eq!(
+        FiniteField::divide(&d, &c, &p),
+        Ok(FiniteField::mult(&d, &c_inv, &p))
+    );
+
+    let vals: Vec<BigUint> = (1u32..17).map(BigUint::from).collect();
+    let invs = FiniteField::batch_invert(&vals, &p).expect("1..17 are invertible mod 17");
+    for (v, inv) in vals.iter().zip(&invs) {
+        assert_eq!(FiniteField::mult(v, inv, &p), BigUint::from(1u32));
+    }
+    let root = FiniteField::sqrt(&BigUint::from(2u32), &p).expect("2 = 6^2 mod 17");
+    assert_eq!(FiniteField::mult(&root, &root, &p), BigUint::from(2u32));
+}
+
+#[test]
+fn curve_ops() {
+    // y^2 = x^3 + 2x + 2 mod 17, (5, 1) of order 19
+    let ec = EllipticCurve::new(
+        BigUint::from(2u32),
+        BigUint::from(2u32),
+        BigUint::from(17u32),
+    )
+    .expect("Cannot build curve");
+    let a_gen = Point::Coordinate(BigUint::from(5u32), BigUint::from(1u32));
+    let double = ec.doubling(&a_gen).expect("A is on the curve");
+    assert_eq!(ec.add(&a_gen, &a_gen), Ok(double.clone()));
+    assert_eq!(ec.scalar_mul(&a_gen, &BigUint::from(2u32)), Ok(double));
+    assert_eq!(
+        ec.scalar_mul(&a_gen, &BigUint::from(19u32)),
+        Ok(Point::Identity)
+    );
+    assert_eq!(ec.enumerate_points().map(|points| points.len()), Ok(19));
+}
+
+#[test]
+fn ecdsa_with_rng() {
+    // keys and nonces come from the caller's rng, there is no thread_rng without std
+    let ecdsa = ECDSA::new(
+        EllipticCurve::secp256k1(),
+        EllipticCurve::secp256k1_generator(),
+        EllipticCurve::secp256k1_order(),
+    )
+    .expect("secp256k1 parameters are consistent");
+    let priv_key =
+        PrivateKey::new(BigUint::from(0xc0ffeeu32), ecdsa.q_order()).expect("d is in range");
+    let pub_key = ecdsa.generate_pub_key(&priv_key);
+    let hash = BigUint::from(0x1234u32);
+    let mut rng = StdRng::seed_from_u64(7);
+    let signature = ecdsa
+        .sign_with_rng(&priv_key, &hash, &mut rng)
+        .expect("Could not sign");
+    assert!(ecdsa.verify(&hash, &signature, &pub_key));
+    let deterministic = ecdsa
+        .sign_deterministic(&priv_key, &hash)
+        .expect("Could not sign");
+    assert!(ecdsa.verify(&hash, &deterministic, &pub_key));
+}